@@ -1,12 +1,13 @@
 //! Driver for the AD5627R 2-output programmable reference generator (2-channel DAC).
 //!
 //! # Description
-//! This driver allows for configuring either or both DAC output voltages. It assumes that the DAC
-//! is using an internal 1.25V reference (AD5627R variant).
+//! This driver allows for configuring either or both DAC output voltages and reading back the
+//! programmed voltage of each output. It assumes that the DAC is using an internal 1.25V reference
+//! (AD5627R variant).
 #![no_std]
 #![deny(warnings)]
 
-use embedded_hal::blocking::i2c::Write;
+use embedded_hal::blocking::i2c::{Write, WriteRead};
 
 /// The maximum voltage that the DAC can output.
 pub const MAX_VOLTAGE: f32 = 2.5;
@@ -35,6 +36,7 @@ enum Command {
 }
 
 /// Represents which DAC output to update.
+#[derive(Copy, Clone)]
 pub enum Dac {
     A = 0b000,
     B = 0b001,
@@ -125,3 +127,37 @@ where
         Ok(programmed_voltage)
     }
 }
+
+impl<I2C> Ad5627<I2C>
+where
+    I2C: Write + WriteRead,
+{
+    /// Read back the output voltage programmed into a specific DAC channel.
+    ///
+    /// # Note
+    /// The voltage is read from the input register of the DAC, so it reflects the hardware state
+    /// even if the device has been reset (e.g. due to a brown-out) since it was last configured.
+    ///
+    /// # Args
+    /// * `dac` - Specifies which DAC to read. Both DACs cannot be read at once.
+    ///
+    /// # Returns
+    /// The voltage programmed into the DAC.
+    pub fn get_voltage(&mut self, dac: Dac) -> Result<f32, Error<<I2C as WriteRead>::Error>> {
+        if let Dac::Both = dac {
+            return Err(Error::Range);
+        }
+
+        // Readback is performed by addressing the DAC in the command byte and then reading the
+        // MSB-aligned 12-bit code of its input register.
+        let mut buffer = [0u8; 2];
+        self.i2c.write_read(
+            self.address,
+            &[((Command::WriteInput as u8) << 3) | dac as u8],
+            &mut buffer,
+        )?;
+
+        let code = u16::from_be_bytes(buffer) >> 4;
+        Ok((code as f32) / (0x1000 as f32) * 2.5)
+    }
+}
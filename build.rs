@@ -5,4 +5,9 @@
 //! environment variables into the build.
 fn main() {
     built::write_built_file().expect("Failed to acquire build-time information");
+
+    // The defmt linker script is required to place the defmt interned strings.
+    if std::env::var_os("CARGO_FEATURE_DEFMT").is_some() {
+        println!("cargo:rustc-link-arg=-Tdefmt.x");
+    }
 }
@@ -0,0 +1,274 @@
+//! Conversion between UNIX time and calendar dates
+//!
+//! # Description
+//! This crate converts between UNIX time and calendar dates in UTC and evaluates daily time
+//! windows without any dependencies, so that the conversion can be tested on the host. Dates are converted using the proleptic
+//! Gregorian calendar, with years starting in March so that leap days are at the end of each year.
+#![no_std]
+#![deny(warnings)]
+
+/// The number of seconds in a day.
+pub const SECONDS_PER_DAY: u32 = 86_400;
+
+/// A calendar date and time in UTC.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DateTime {
+    pub year: u32,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl DateTime {
+    /// Convert a UNIX time into a calendar date and time.
+    ///
+    /// # Args
+    /// * `time` - The number of seconds since 1970-01-01T00:00:00Z.
+    pub fn from_unix(time: u32) -> Self {
+        let days = time / SECONDS_PER_DAY;
+        let seconds = time % SECONDS_PER_DAY;
+
+        // Convert days since 1970-01-01 into a civil date, with years starting in March so that
+        // leap days are at the end of each year.
+        let z = days + 719_468;
+        let era = z / 146_097;
+        let day_of_era = z % 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = year_of_era + era * 400 + (month <= 2) as u32;
+
+        Self {
+            year,
+            month,
+            day,
+            hour: seconds / 3600,
+            minute: (seconds / 60) % 60,
+            second: seconds % 60,
+        }
+    }
+
+    /// Convert the calendar date and time into a UNIX time.
+    ///
+    /// # Note
+    /// The date must not be before 1970-01-01.
+    pub fn to_unix(&self) -> u32 {
+        let year = self.year - (self.month <= 2) as u32;
+        let era = year / 400;
+        let year_of_era = year % 400;
+        let mp = if self.month > 2 {
+            self.month - 3
+        } else {
+            self.month + 9
+        };
+        let day_of_year = (153 * mp + 2) / 5 + self.day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * 146_097 + day_of_era - 719_468;
+
+        days * SECONDS_PER_DAY + self.hour * 3600 + self.minute * 60 + self.second
+    }
+}
+
+/// A window of time that recurs daily on selected weekdays.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DailyWindow {
+    /// The start of the window in minutes after midnight UTC.
+    pub start_minute: u16,
+
+    /// The end of the window in minutes after midnight UTC. If earlier than `start_minute`, the
+    /// window spans midnight.
+    pub end_minute: u16,
+
+    /// The weekdays on which the window starts. Bit 0 corresponds to Monday and bit 6 to Sunday.
+    pub weekdays: u8,
+}
+
+impl DailyWindow {
+    /// Check if the window starts on a day.
+    ///
+    /// # Args
+    /// * `day` - The number of days since 1970-01-01, which was a Thursday.
+    fn starts_on(&self, day: u32) -> bool {
+        let weekday = (day + 3) % 7;
+        self.weekdays & (1 << weekday) != 0
+    }
+
+    /// Check if a time is within the window.
+    ///
+    /// # Args
+    /// * `time` - The number of seconds since 1970-01-01T00:00:00Z.
+    pub fn contains(&self, time: u32) -> bool {
+        let day = time / SECONDS_PER_DAY;
+        let minute = ((time % SECONDS_PER_DAY) / 60) as u16;
+
+        if self.start_minute <= self.end_minute {
+            self.starts_on(day) && (self.start_minute..self.end_minute).contains(&minute)
+        } else {
+            // Windows spanning midnight belong to the day on which they start. There is no day
+            // before 1970-01-01, so no window can carry over into it.
+            (self.starts_on(day) && minute >= self.start_minute)
+                || (day
+                    .checked_sub(1)
+                    .is_some_and(|previous| self.starts_on(previous))
+                    && minute < self.end_minute)
+        }
+    }
+}
+
+impl core::fmt::Display for DateTime {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: u32, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> DateTime {
+        DateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        }
+    }
+
+    #[test]
+    fn epoch_boundaries() {
+        let cases = [
+            (0, date(1970, 1, 1, 0, 0, 0)),
+            (946_684_799, date(1999, 12, 31, 23, 59, 59)),
+            (946_684_800, date(2000, 1, 1, 0, 0, 0)),
+            (2_147_483_647, date(2038, 1, 19, 3, 14, 7)),
+            (4_102_444_799, date(2099, 12, 31, 23, 59, 59)),
+            (4_102_444_800, date(2100, 1, 1, 0, 0, 0)),
+            (u32::MAX, date(2106, 2, 7, 6, 28, 15)),
+        ];
+
+        for (time, expected) in cases {
+            assert_eq!(DateTime::from_unix(time), expected, "{time}");
+            assert_eq!(expected.to_unix(), time, "{expected}");
+        }
+    }
+
+    #[test]
+    fn leap_years() {
+        // 2000 is a leap year as it is divisible by 400.
+        assert_eq!(DateTime::from_unix(951_782_400), date(2000, 2, 29, 0, 0, 0));
+        assert_eq!(DateTime::from_unix(951_868_800), date(2000, 3, 1, 0, 0, 0));
+
+        // 2024 is a leap year.
+        assert_eq!(
+            DateTime::from_unix(1_709_164_800),
+            date(2024, 2, 29, 0, 0, 0)
+        );
+        assert_eq!(
+            DateTime::from_unix(1_704_067_199),
+            date(2023, 12, 31, 23, 59, 59)
+        );
+
+        // 2100 is not a leap year as it is divisible by 100 but not by 400.
+        assert_eq!(
+            DateTime::from_unix(4_107_456_000),
+            date(2100, 2, 28, 0, 0, 0)
+        );
+        assert_eq!(
+            DateTime::from_unix(4_107_542_400),
+            date(2100, 3, 1, 0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        // Check every day across several leap cycles, including the end of each day.
+        for day in 0..(366 * 12) {
+            for time in [day * SECONDS_PER_DAY, (day + 1) * SECONDS_PER_DAY - 1] {
+                let time = 946_684_800 + time;
+                assert_eq!(DateTime::from_unix(time).to_unix(), time);
+            }
+        }
+    }
+
+    #[test]
+    fn window_spanning_midnight() {
+        // 22:00 to 06:00, starting on Thursdays only.
+        let window = DailyWindow {
+            start_minute: 22 * 60,
+            end_minute: 6 * 60,
+            weekdays: 1 << 3,
+        };
+
+        // 1970-01-01 was a Thursday and has no previous day to carry over from.
+        assert!(!window.contains(0));
+        assert!(!window.contains(21 * 3600 + 59 * 60));
+        assert!(window.contains(22 * 3600));
+
+        // The window carries over into the early hours of Friday.
+        assert!(window.contains(SECONDS_PER_DAY));
+        assert!(window.contains(SECONDS_PER_DAY + 5 * 3600 + 59 * 60));
+        assert!(!window.contains(SECONDS_PER_DAY + 6 * 3600));
+
+        // The window does not start on Friday.
+        assert!(!window.contains(SECONDS_PER_DAY + 22 * 3600));
+
+        // Even a window starting every day does not carry over into 1970-01-01.
+        let daily = DailyWindow {
+            weekdays: 0x7F,
+            ..window
+        };
+        assert!(!daily.contains(0));
+        assert!(daily.contains(22 * 3600));
+    }
+
+    #[test]
+    fn window_within_day() {
+        // 08:00 to 20:00 on Mondays only.
+        let window = DailyWindow {
+            start_minute: 8 * 60,
+            end_minute: 20 * 60,
+            weekdays: 1 << 0,
+        };
+
+        // 1970-01-05 was a Monday.
+        let monday = 4 * SECONDS_PER_DAY;
+        assert!(!window.contains(monday + 7 * 3600 + 59 * 60));
+        assert!(window.contains(monday + 8 * 3600));
+        assert!(!window.contains(monday + 20 * 3600));
+        assert!(!window.contains(monday + SECONDS_PER_DAY + 8 * 3600));
+    }
+
+    #[test]
+    fn display() {
+        let mut buffer = [0u8; 20];
+        let mut writer = Writer(&mut buffer, 0);
+        core::fmt::write(&mut writer, format_args!("{}", date(2024, 2, 29, 1, 2, 3))).unwrap();
+        assert_eq!(&buffer[..], b"2024-02-29T01:02:03Z");
+    }
+
+    /// A minimal writer into a fixed buffer, as the crate does not link `std`.
+    struct Writer<'a>(&'a mut [u8], usize);
+
+    impl core::fmt::Write for Writer<'_> {
+        fn write_str(&mut self, s: &str) -> core::fmt::Result {
+            let end = self.1 + s.len();
+            self.0
+                .get_mut(self.1..end)
+                .ok_or(core::fmt::Error)?
+                .copy_from_slice(s.as_bytes());
+            self.1 = end;
+            Ok(())
+        }
+    }
+}
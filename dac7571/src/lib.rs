@@ -4,7 +4,7 @@
 //! This driver does not support any low-power operation modes.
 #![no_std]
 
-use embedded_hal::blocking::i2c::Write;
+use embedded_hal::blocking::i2c::{Read, Write};
 
 /// A driver for the DAC7571 digital to analog converter.
 pub struct Dac7571<I2C>
@@ -77,3 +77,26 @@ where
         Ok(dac_output)
     }
 }
+
+impl<I2C> Dac7571<I2C>
+where
+    I2C: Write + Read,
+{
+    /// Read back the output voltage programmed into the DAC.
+    ///
+    /// # Note
+    /// The voltage is read from the DAC register, so it reflects the hardware state even if the
+    /// device has been reset (e.g. due to a brown-out) since it was last configured.
+    ///
+    /// # Returns
+    /// The nominal DAC output voltage.
+    pub fn get_voltage(&mut self) -> Result<f32, Error<<I2C as Read>::Error>> {
+        // The register is read in the same format as it is written: the power-down bits followed
+        // by the right-aligned 12-bit code.
+        let mut buffer = [0u8; 2];
+        self.i2c.read(self.address, &mut buffer)?;
+
+        let dac_code = u16::from_be_bytes(buffer) & 0xFFF;
+        Ok(dac_code as f32 / 4096.0 * self.supply_voltage)
+    }
+}
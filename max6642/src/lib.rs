@@ -122,4 +122,21 @@ where
 
         Ok(temp_c)
     }
+
+    /// Get the temperature of the MAX6642 die.
+    ///
+    /// # Returns
+    /// The local temperature of the sensor in degrees celsius.
+    pub fn get_local_temperature(&mut self) -> Result<f32, Error<<I2C as WriteRead>::Error>> {
+        let temp_c = self.read(Command::ReadLocalTemperature)?;
+
+        // 0.25C temperature is stored in the top 2 bits of the extended data register.
+        let temp_c_4ths = self
+            .read(Command::ReadInternalExtendedTemperature)?
+            .get_bits(6..8);
+
+        let temp_c = (temp_c as f32) + (temp_c_4ths as f32) * 0.25;
+
+        Ok(temp_c)
+    }
 }
@@ -0,0 +1,265 @@
+//! Policies for persisting Booster settings
+//!
+//! # Description
+//! This crate decides whether and when settings are written to non-volatile memory without any
+//! dependencies, so that the decisions can be tested on the host.
+#![no_std]
+#![deny(warnings)]
+
+/// An error that occurs while writing settings.
+#[derive(Debug, PartialEq)]
+pub enum WriteError<E> {
+    /// The device is locked, so the settings were not written.
+    Locked,
+
+    /// Writing the settings failed.
+    Write(E),
+}
+
+/// Write settings unless the device is locked.
+///
+/// # Args
+/// * `locked` - Specified true if the device is locked against settings changes.
+/// * `write` - The function that writes the settings. It is not called if the device is locked.
+pub fn write_unless_locked<T, E>(
+    locked: bool,
+    write: impl FnOnce() -> Result<T, E>,
+) -> Result<T, WriteError<E>> {
+    if locked {
+        return Err(WriteError::Locked);
+    }
+
+    write().map_err(WriteError::Write)
+}
+
+/// The reason that settings are due to be saved.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Save {
+    /// A save was explicitly requested.
+    Requested,
+
+    /// The settings were modified and have remained unchanged for the quiet period.
+    Automatic,
+}
+
+/// Tracks when settings are to be saved to non-volatile memory.
+///
+/// # Note
+/// Requested saves are deferred until saves are next polled, so that repeated requests are
+/// coalesced into a single write. If enabled, modified settings are additionally saved
+/// automatically once they have remained unchanged for a quiet period.
+pub struct DeferredSave<T> {
+    modified: Option<T>,
+    requested: bool,
+    auto_save: bool,
+}
+
+impl<T> Default for DeferredSave<T> {
+    fn default() -> Self {
+        Self {
+            modified: None,
+            requested: false,
+            auto_save: false,
+        }
+    }
+}
+
+impl<T: Copy + PartialOrd> DeferredSave<T> {
+    /// Request that the settings be saved when saves are next polled.
+    pub fn request(&mut self) {
+        self.requested = true;
+    }
+
+    /// Record that the settings were modified.
+    ///
+    /// # Args
+    /// * `now` - The time of the modification.
+    pub fn modify(&mut self, now: T) {
+        self.modified = Some(now);
+    }
+
+    /// Check if the settings are due to be saved.
+    ///
+    /// # Args
+    /// * `dirty` - Specified true if the settings differ from the saved settings.
+    /// * `now` - The current time.
+    /// * `quiet_period_end` - Calculates the end of the quiet period from the time of the last
+    ///   modification. If None, modified settings are not saved automatically.
+    ///
+    /// # Returns
+    /// The reason that the settings must be saved now, if any.
+    pub fn poll(
+        &mut self,
+        dirty: bool,
+        now: T,
+        quiet_period_end: Option<impl FnOnce(T) -> Option<T>>,
+    ) -> Option<Save> {
+        self.auto_save = quiet_period_end.is_some();
+
+        if self.requested {
+            self.requested = false;
+            self.modified = None;
+            return Some(Save::Requested);
+        }
+
+        // Modifications that were reverted do not need to be saved.
+        if !dirty {
+            self.modified = None;
+            return None;
+        }
+
+        let (modified, quiet_period_end) = self.modified.zip(quiet_period_end)?;
+        match quiet_period_end(modified) {
+            Some(deadline) if now > deadline => {
+                self.modified = None;
+                Some(Save::Automatic)
+            }
+            _ => None,
+        }
+    }
+
+    /// Check if the settings have a pending save that must be written without delay.
+    ///
+    /// # Note
+    /// This is intended for use when power is failing or the device is about to reset. Requested
+    /// saves and, if automatic saving is enabled, modified settings awaiting their quiet period
+    /// are pending.
+    ///
+    /// # Returns
+    /// True if the settings must be saved now.
+    pub fn flush(&mut self) -> bool {
+        let pending = self.requested || (self.auto_save && self.modified.is_some());
+        self.requested = false;
+        self.modified = None;
+        pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The quiet period used for automatic saves in seconds.
+    const QUIET_PERIOD: u32 = 10;
+
+    fn quiet_period(modified: u32) -> Option<u32> {
+        modified.checked_add(QUIET_PERIOD)
+    }
+
+    /// Disables automatic saves.
+    const MANUAL: Option<fn(u32) -> Option<u32>> = None;
+
+    #[test]
+    fn requested_saves_are_coalesced() {
+        let mut saves = DeferredSave::default();
+        saves.request();
+        saves.request();
+
+        assert_eq!(saves.poll(true, 0, MANUAL), Some(Save::Requested));
+        assert_eq!(saves.poll(true, 1, MANUAL), None);
+    }
+
+    #[test]
+    fn modifications_are_not_saved_without_auto_save() {
+        let mut saves = DeferredSave::default();
+        saves.modify(0);
+
+        assert_eq!(saves.poll(true, 1000, MANUAL), None);
+        assert!(!saves.flush());
+    }
+
+    #[test]
+    fn modifications_are_saved_after_quiet_period() {
+        let mut saves = DeferredSave::default();
+        saves.modify(0);
+        assert_eq!(saves.poll(true, QUIET_PERIOD, Some(quiet_period)), None);
+
+        // Further modifications restart the quiet period.
+        saves.modify(5);
+        assert_eq!(saves.poll(true, QUIET_PERIOD + 1, Some(quiet_period)), None);
+        assert_eq!(
+            saves.poll(true, QUIET_PERIOD + 6, Some(quiet_period)),
+            Some(Save::Automatic)
+        );
+
+        // The settings are only saved once.
+        assert_eq!(saves.poll(true, 1000, Some(quiet_period)), None);
+    }
+
+    #[test]
+    fn reverted_modifications_are_not_saved() {
+        let mut saves = DeferredSave::default();
+        saves.modify(0);
+
+        assert_eq!(saves.poll(false, 1000, Some(quiet_period)), None);
+        assert_eq!(saves.poll(true, 1000, Some(quiet_period)), None);
+    }
+
+    #[test]
+    fn quiet_period_overflow_is_never_saved() {
+        let mut saves = DeferredSave::default();
+        saves.modify(u32::MAX);
+
+        assert_eq!(saves.poll(true, u32::MAX, Some(quiet_period)), None);
+    }
+
+    #[test]
+    fn requested_save_clears_modification() {
+        let mut saves = DeferredSave::default();
+        saves.modify(0);
+        saves.request();
+
+        assert_eq!(
+            saves.poll(true, 1, Some(quiet_period)),
+            Some(Save::Requested)
+        );
+        assert_eq!(saves.poll(true, 1000, Some(quiet_period)), None);
+        assert!(!saves.flush());
+    }
+
+    #[test]
+    fn flush_writes_pending_saves() {
+        // Requested saves are flushed regardless of automatic saving.
+        let mut saves = DeferredSave::<u32>::default();
+        saves.request();
+        assert!(saves.flush());
+        assert!(!saves.flush());
+
+        // Modifications awaiting their quiet period are flushed if automatic saving is enabled.
+        let mut saves = DeferredSave::default();
+        assert_eq!(saves.poll(true, 0, Some(quiet_period)), None);
+        saves.modify(1);
+        assert!(saves.flush());
+        assert!(!saves.flush());
+    }
+
+    #[test]
+    fn locked_write_is_skipped() {
+        let mut written = false;
+        let result = write_unless_locked(true, || -> Result<(), ()> {
+            written = true;
+            Ok(())
+        });
+
+        assert_eq!(result, Err(WriteError::Locked));
+        assert!(!written);
+    }
+
+    #[test]
+    fn unlocked_write_is_performed() {
+        let mut written = false;
+        let result = write_unless_locked(false, || -> Result<(), ()> {
+            written = true;
+            Ok(())
+        });
+
+        assert_eq!(result, Ok(()));
+        assert!(written);
+    }
+
+    #[test]
+    fn write_errors_are_reported() {
+        let result = write_unless_locked(false, || -> Result<(), u8> { Err(3) });
+        assert_eq!(result, Err(WriteError::Write(3)));
+    }
+}
@@ -0,0 +1,38 @@
+#!/usr/bin/python
+"""
+Description: Embeds the CRC32 of a Booster firmware binary image for the boot-time integrity check.
+
+The CRC is written into the record marked by `BSTR-CRC` and covers the whole image except for the
+CRC value itself.
+"""
+import argparse
+import struct
+import zlib
+
+MAGIC = b'BSTR-CRC'
+
+
+def main():
+    """ Patch the CRC of a binary image. """
+    parser = argparse.ArgumentParser(description='Embed the CRC32 into a Booster firmware image')
+    parser.add_argument('image', help='The binary firmware image (e.g. `booster.bin`) to patch')
+    args = parser.parse_args()
+
+    with open(args.image, 'rb') as image_file:
+        image = bytearray(image_file.read())
+
+    assert image.count(MAGIC) == 1, 'The image must contain exactly one CRC record'
+    offset = image.index(MAGIC) + len(MAGIC)
+
+    crc = zlib.crc32(image[:offset])
+    crc = zlib.crc32(image[offset + 4:], crc)
+    image[offset:offset + 4] = struct.pack('<I', crc)
+
+    with open(args.image, 'wb') as image_file:
+        image_file.write(image)
+
+    print(f'Embedded CRC 0x{crc:08x}')
+
+
+if __name__ == '__main__':
+    main()
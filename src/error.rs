@@ -0,0 +1,139 @@
+//! Booster NGFW error definitions
+//!
+//! # Design
+//! Errors of the I2C devices of a channel carry the failing device, its I2C address, and the
+//! attempted operation, such that control interfaces can report actionable diagnostics.
+use crate::hardware::Channel;
+use serde::Serialize;
+
+/// The I2C devices on the bus segment of each channel.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+pub enum Device {
+    /// The ADS7924 power monitor on the mainboard.
+    #[serde(rename = "ADS7924")]
+    Ads7924,
+
+    /// The DAC7571 bias DAC on the mainboard.
+    #[serde(rename = "DAC7571")]
+    Dac7571,
+
+    /// The AD5627 interlock threshold DAC on the RF module.
+    #[serde(rename = "AD5627")]
+    Ad5627,
+
+    /// The MCP3221 input power ADC on the RF module.
+    #[serde(rename = "MCP3221")]
+    Mcp3221,
+
+    /// The MAX6642 temperature monitor on the RF module.
+    #[serde(rename = "MAX6642")]
+    Max6642,
+
+    /// The 24AA02E48 EEPROM on the RF module.
+    #[serde(rename = "24AA02E48")]
+    Eeprom,
+}
+
+impl Device {
+    /// Get the part name of the device.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Device::Ads7924 => "ADS7924",
+            Device::Dac7571 => "DAC7571",
+            Device::Ad5627 => "AD5627",
+            Device::Mcp3221 => "MCP3221",
+            Device::Max6642 => "MAX6642",
+            Device::Eeprom => "24AA02E48",
+        }
+    }
+
+    /// Get the 7-bit I2C address of the device.
+    pub const fn address(&self) -> u8 {
+        match self {
+            Device::Ads7924 => 0x49,
+            Device::Dac7571 => 0x4C,
+            Device::Ad5627 => 0x0E,
+            Device::Mcp3221 => 0x4D,
+            Device::Max6642 => 0x4A,
+            Device::Eeprom => 0x50,
+        }
+    }
+}
+
+/// A failed operation on an I2C device of a channel.
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct DeviceError {
+    pub channel: Channel,
+    pub device: Device,
+    pub address: u8,
+    pub operation: &'static str,
+}
+
+impl core::fmt::Display for DeviceError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "Channel {} {} (0x{:02X}): {} failed",
+            self.channel as u8,
+            self.device.name(),
+            self.address,
+            self.operation
+        )
+    }
+}
+
+/// An enumeration of possible errors with the device.
+#[derive(Debug, Copy, Clone, Serialize)]
+pub enum Error {
+    Invalid,
+    InvalidState,
+    Interface(DeviceError),
+    /// The bias DAC output read back differs from the written output.
+    BiasMismatch {
+        channel: Channel,
+        nominal: f32,
+        readback: f32,
+    },
+    Foldback,
+    Bounds,
+    Fault,
+}
+
+impl Error {
+    /// Construct an error of a failed I2C device operation.
+    ///
+    /// # Args
+    /// * `channel` - The channel the device belongs to.
+    /// * `device` - The device that failed.
+    /// * `operation` - A short description of the attempted operation, e.g. `read temperature`.
+    pub fn interface(channel: Channel, device: Device, operation: &'static str) -> Self {
+        Error::Interface(DeviceError {
+            channel,
+            device,
+            address: device.address(),
+            operation,
+        })
+    }
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::Invalid => write!(f, "Invalid value"),
+            Error::InvalidState => write!(f, "Invalid channel state"),
+            Error::Interface(error) => write!(f, "{}", error),
+            Error::BiasMismatch {
+                channel,
+                nominal,
+                readback,
+            } => write!(
+                f,
+                "Channel {} bias DAC reads back {} V instead of {} V",
+                *channel as u8, readback, nominal
+            ),
+            Error::Foldback => write!(f, "Foldback active"),
+            Error::Bounds => write!(f, "Value out of bounds"),
+            Error::Fault => write!(f, "Channel fault"),
+        }
+    }
+}
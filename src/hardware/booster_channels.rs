@@ -1,16 +1,58 @@
 //! Booster NGFW channel management control interface definitions.
 
-use stm32f4xx_hal as hal;
+use heapless::Vec;
+use serde::Serialize;
+use stm32f4xx_hal::hal::blocking::i2c::Write;
 use tca9548::{self, Tca9548};
 
 use super::rf_channel::{ChannelPins as RfChannelPins, RfChannel, RfChannelMachine};
+use crate::error::Device;
+
 use super::{delay::AsmDelay, Channel, I2cBusManager, I2cProxy, SystemTimer};
 
+/// The devices on the I2C bus segment of each channel. The ADS7924 power monitor and DAC7571 bias
+/// DAC are located on the mainboard. The remaining devices are located on the RF module.
+pub const CHANNEL_DEVICES: [Device; 6] = [
+    Device::Ads7924,
+    Device::Dac7571,
+    Device::Ad5627,
+    Device::Mcp3221,
+    Device::Max6642,
+    Device::Eeprom,
+];
+
+/// The devices of [CHANNEL_DEVICES] located on the RF module.
+pub const MODULE_DEVICES: &[Device] = &[
+    Device::Ad5627,
+    Device::Mcp3221,
+    Device::Max6642,
+    Device::Eeprom,
+];
+
+/// The range of I2C addresses that are scanned. Reserved addresses are excluded.
+const SCAN_ADDRESSES: core::ops::RangeInclusive<u8> = 0x08..=0x77;
+
+/// The maximum number of responding addresses reported per bus segment by an I2C scan.
+const MAX_SCAN_DEVICES: usize = 16;
+
+/// The I2C addresses of the devices that responded to an I2C scan.
+#[derive(Serialize)]
+pub struct I2cScan {
+    /// The addresses on the shared bus.
+    pub shared: Vec<u8, MAX_SCAN_DEVICES>,
+
+    /// The addresses on the bus segment of each channel. Addresses on the shared bus are omitted.
+    pub channels: [Vec<u8, MAX_SCAN_DEVICES>; 8],
+}
+
 /// Represents a control structure for interfacing to booster RF channels.
 pub struct BoosterChannels {
     channels: [Option<RfChannelMachine>; 8],
-    adc: hal::adc::Adc<hal::pac::ADC3>,
     mux: Tca9548<I2cProxy>,
+    // An interface to the shared I2C bus used for probing devices.
+    i2c: I2cProxy,
+    // The channel index currently selected on the I2C multiplexer, if known.
+    selected: Option<usize>,
 }
 
 impl From<Channel> for tca9548::Bus {
@@ -36,7 +78,6 @@ impl BoosterChannels {
     ///
     /// # Args
     /// * `mux` - The I2C mux used for switching between channel communications.
-    /// * `adc` - The ADC used to measure analog channels.
     /// * `manager` - The I2C bus manager used for the shared I2C bus.
     /// * `pins` - An array of all RfChannel control/status pins.
     /// * `delay` - A means of delaying during setup.
@@ -45,7 +86,6 @@ impl BoosterChannels {
     /// A `BoosterChannels` object that can be used to manage all available RF channels.
     pub fn new(
         mut mux: Tca9548<I2cProxy>,
-        adc: hal::adc::Adc<hal::pac::ADC3>,
         manager: &'static I2cBusManager,
         pins: [RfChannelPins; 8],
         clock: SystemTimer,
@@ -59,36 +99,136 @@ impl BoosterChannels {
             mux.select_bus(Some(idx.into()))
                 .expect("Failed to select channel");
 
-            if let Some(channel) = RfChannel::new(manager, pins, clock, delay.clone()) {
+            if let Some(channel) = RfChannel::new(idx, manager, pins, clock, delay.clone()) {
                 let mut machine = RfChannelMachine::new(channel);
-                machine.handle_startup();
+                if super::safe_mode::is_active() {
+                    // In safe mode, channels are kept off until released locally.
+                    machine.set_lockout(true);
+                }
+
+                // Note: Channels are powered up later in a staggered sequence, so that the supply
+                // is not loaded by all channels at once.
                 channels[idx as usize].replace(machine);
             } else {
                 info!("Channel {} did not enumerate", idx as usize);
             }
         }
 
-        BoosterChannels { channels, mux, adc }
+        BoosterChannels {
+            channels,
+            mux,
+            i2c: manager.acquire_i2c(),
+            selected: None,
+        }
+    }
+
+    /// Check if any channel was faulted by a supply alert of its RF module.
+    pub fn supply_faulted(&self) -> bool {
+        self.channels
+            .iter()
+            .flatten()
+            .any(|channel| channel.is_supply_faulted())
     }
 
-    /// Select a given channel on the I2C multiplexer and get
-    /// mutable references to that channel and the ADC.
+    /// Trip all channels that depend on a tripped channel.
+    ///
+    /// # Note
+    /// Dependencies are followed transitively, so that the dependents of dependent channels are
+    /// tripped as well.
+    ///
+    /// # Args
+    /// * `source` - The channel whose interlock tripped.
+    pub fn trip_dependents(&mut self, source: Channel) {
+        let mut visited = 1u8 << source as u8;
+        let mut pending = self.channels[source as usize]
+            .as_ref()
+            .map_or(0, |channel| channel.context().dependents());
+
+        while pending & !visited != 0 {
+            let index = (pending & !visited).trailing_zeros() as usize;
+            visited |= 1 << index;
+
+            if let Some(channel) = self.channels[index].as_mut() {
+                log::warn!("Tripping channel {} dependent on {:?}", index, source);
+                channel.trip_chained();
+                pending |= channel.context().dependents();
+            }
+        }
+    }
+
+    /// Select a given channel on the I2C multiplexer and get a mutable reference to that channel.
+    ///
+    /// # Note
+    /// The multiplexer is only reconfigured if a different channel was previously selected, so
+    /// that consecutive accesses to a channel do not incur redundant I2C transactions.
     ///
     /// # Args
     /// * `channel` - The channel to get.
     ///
     /// # Returns
-    /// An optional pair of mutable references to the channel and the ADC and
-    /// `None` if the channel is absent.
-    pub fn channel_mut(
-        &mut self,
-        channel: Channel,
-    ) -> Option<(&mut RfChannelMachine, &mut hal::adc::Adc<hal::pac::ADC3>)> {
-        let mux = &mut self.mux;
-        let adc = &mut self.adc;
-        self.channels[channel as usize].as_mut().map(|ch| {
-            mux.select_bus(Some(channel.into())).unwrap();
-            (ch, adc)
-        })
+    /// A mutable reference to the channel and `None` if the channel is absent.
+    pub fn channel_mut(&mut self, channel: Channel) -> Option<&mut RfChannelMachine> {
+        self.channels[channel as usize].as_ref()?;
+        self.select(channel);
+        self.channels[channel as usize].as_mut()
+    }
+
+    /// Select a channel on the I2C multiplexer if it is not already selected.
+    fn select(&mut self, channel: Channel) {
+        if self.selected != Some(channel as usize) {
+            self.mux.select_bus(Some(channel.into())).unwrap();
+            self.selected = Some(channel as usize);
+        }
+    }
+
+    /// Probe for the presence of a device on the I2C bus.
+    ///
+    /// # Note
+    /// The channel need not have enumerated, so that partially installed RF modules can be
+    /// diagnosed.
+    ///
+    /// # Args
+    /// * `channel` - The channel whose bus segment to probe. If None, all segments are
+    ///   disconnected, so that only devices on the shared bus respond.
+    /// * `address` - The I2C address of the device.
+    ///
+    /// # Returns
+    /// True if the device acknowledged its address.
+    pub fn probe(&mut self, channel: Option<Channel>, address: u8) -> bool {
+        match channel {
+            Some(channel) => self.select(channel),
+            None => {
+                self.mux.select_bus(None).unwrap();
+                self.selected = None;
+            }
+        }
+
+        self.i2c.write(address, &[]).is_ok()
+    }
+
+    /// Scan the shared I2C bus and the bus segment of each channel for responding devices.
+    ///
+    /// # Note
+    /// If more than [MAX_SCAN_DEVICES] addresses respond on a segment (e.g. because a bus line is
+    /// stuck low), only the lowest addresses are reported.
+    pub fn scan_i2c(&mut self) -> I2cScan {
+        let mut shared: Vec<u8, MAX_SCAN_DEVICES> = Vec::new();
+        for address in SCAN_ADDRESSES {
+            if self.probe(None, address) {
+                shared.push(address).ok();
+            }
+        }
+
+        // Devices on the shared bus also respond while a segment is selected.
+        let mut channels: [Vec<u8, MAX_SCAN_DEVICES>; 8] = Default::default();
+        for (channel, addresses) in enum_iterator::all::<Channel>().zip(channels.iter_mut()) {
+            for address in SCAN_ADDRESSES.filter(|address| !shared.contains(address)) {
+                if self.probe(Some(channel), address) {
+                    addresses.push(address).ok();
+                }
+            }
+        }
+
+        I2cScan { shared, channels }
     }
 }
@@ -6,10 +6,14 @@ use max6639::Max6639;
 /// The default fan speed on power-up.
 pub const DEFAULT_FAN_SPEED: f32 = 0.2;
 
+/// The default number of fan operating hours after which fan service is due.
+pub const DEFAULT_FAN_SERVICE_HOURS: u32 = 40_000;
+
 /// Provides control of the chassis-mounted cooling fans.
 pub struct ChassisFans {
     fans: [Max6639<I2cProxy>; 3],
     duty_cycle: f32,
+    running: bool,
     leds: MainboardLeds,
 }
 
@@ -27,6 +31,7 @@ impl ChassisFans {
         ChassisFans {
             fans,
             duty_cycle: default_speed.clamp(0.0, 1.0),
+            running: false,
             leds,
         }
     }
@@ -49,6 +54,11 @@ impl ChassisFans {
         self.set_duty_cycles(0.0)
     }
 
+    /// Check if the fans are currently commanded to run.
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
     /// Set the duty cycle of the fans.
     ///
     /// # Args
@@ -56,6 +66,7 @@ impl ChassisFans {
     fn set_duty_cycles(&mut self, duty_cycle: f32) {
         // Bound the duty cycle to a normalized range.
         let duty_cycle = duty_cycle.clamp(0.0, 1.0);
+        self.running = duty_cycle > 0.0;
 
         let leds = &mut self.leds;
 
@@ -85,15 +96,19 @@ impl ChassisFans {
         }
     }
 
-    fn read_rpms(&mut self) -> [u16; 6] {
+    /// Read the current speed of all fans.
+    ///
+    /// # Returns
+    /// The speed of each fan in RPM.
+    pub fn read_rpms(&mut self) -> Result<[u16; 6], max6639::Error<I2cError>> {
         let mut rpms: [u16; 6] = [0; 6];
-        rpms[0] = self.fans[0].current_rpms(max6639::Fan::Fan1).unwrap();
-        rpms[1] = self.fans[0].current_rpms(max6639::Fan::Fan2).unwrap();
-        rpms[2] = self.fans[1].current_rpms(max6639::Fan::Fan1).unwrap();
-        rpms[3] = self.fans[1].current_rpms(max6639::Fan::Fan2).unwrap();
-        rpms[4] = self.fans[2].current_rpms(max6639::Fan::Fan1).unwrap();
-        rpms[5] = self.fans[2].current_rpms(max6639::Fan::Fan2).unwrap();
-        rpms
+        rpms[0] = self.fans[0].current_rpms(max6639::Fan::Fan1)?;
+        rpms[1] = self.fans[0].current_rpms(max6639::Fan::Fan2)?;
+        rpms[2] = self.fans[1].current_rpms(max6639::Fan::Fan1)?;
+        rpms[3] = self.fans[1].current_rpms(max6639::Fan::Fan2)?;
+        rpms[4] = self.fans[2].current_rpms(max6639::Fan::Fan1)?;
+        rpms[5] = self.fans[2].current_rpms(max6639::Fan::Fan2)?;
+        Ok(rpms)
     }
 
     /// Perform a self-test of the fan operation.
@@ -103,7 +118,7 @@ impl ChassisFans {
     ///
     /// # Returns
     /// True if 5 of the six fans properly spun up to a high-speed RPM and all fans had no speed
-    /// when disabled.
+    /// when disabled. False if the fan speeds could not be read.
     pub fn self_test(
         &mut self,
         delay: &mut impl stm32f4xx_hal::hal::blocking::delay::DelayMs<u16>,
@@ -116,6 +131,10 @@ impl ChassisFans {
         delay.delay_ms(7000);
         let dead_rpms = self.read_rpms();
 
+        let (Ok(high_rpms), Ok(dead_rpms)) = (high_rpms, dead_rpms) else {
+            return false;
+        };
+
         // Check that all dead RPMS are zero.
         let fans_powered_down = dead_rpms.iter().filter(|&rpms| *rpms == 0).count();
 
@@ -0,0 +1,244 @@
+//! Booster power detector sampling
+//!
+//! # Design
+//! Channel measurements are split into two paths:
+//! * The fast path samples the output and reflected power detectors (via ADC3) and the overdrive
+//!   inputs of all channels from a 1 kHz timer interrupt. It has the highest interrupt priority and
+//!   never accesses the I2C bus, so protection latency is not bound by I2C throughput.
+//! * The slow path reads the I2C monitors of the RF modules (temperature, supply, and input power)
+//!   when channels are serviced and telemetry is acquired. Power measurements are read from the
+//!   detector voltages most recently sampled by the fast path.
+//!
+//! The detectors of one channel are sampled per interrupt, so each channel is sampled every 8 ms.
+//! The overdrive inputs of all channels are checked on every interrupt. If an overdrive is latched
+//! or a detector exceeds its interlock threshold (by [TRIP_MARGIN], so that the hardware
+//! comparators trip first), the RF output of the channel is disabled immediately and the trip is
+//! recorded. The channel state machine processes the trip when the channel is next serviced.
+use super::{
+    gate, hal,
+    rf_channel::{AdcPin, AdcSampleTime, Interlock},
+    Channel,
+};
+use core::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+
+/// The rate at which the fast path is executed.
+const SAMPLE_RATE_HZ: u32 = 1_000;
+
+/// The detector voltage above the interlock threshold at which the fast path trips a channel.
+const TRIP_MARGIN: f32 = 0.050;
+
+/// The first GPIOG pin used for the ON/OFF signals of the channels.
+const SIGNAL_ON_OFFSET: u32 = 8;
+
+/// The bit pattern of positive infinity, used for detector limits that have not been configured.
+const NO_LIMIT: u32 = 0x7F80_0000;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const ZERO: AtomicU32 = AtomicU32::new(0);
+
+#[allow(clippy::declare_interior_mutable_const)]
+const UNLIMITED: AtomicU32 = AtomicU32::new(NO_LIMIT);
+
+/// The most recently sampled detector voltages of each channel.
+static OUTPUT_VOLTAGE: [AtomicU32; 8] = [ZERO; 8];
+static REFLECTED_VOLTAGE: [AtomicU32; 8] = [ZERO; 8];
+
+/// The interlock threshold voltages of the detectors of each channel.
+static OUTPUT_LIMIT: [AtomicU32; 8] = [UNLIMITED; 8];
+static REFLECTED_LIMIT: [AtomicU32; 8] = [UNLIMITED; 8];
+
+/// The channels with a pending trip of the output and reflected power interlocks.
+static OUTPUT_TRIPS: AtomicU8 = AtomicU8::new(0);
+static REFLECTED_TRIPS: AtomicU8 = AtomicU8::new(0);
+
+/// The ADC sample time of detector conversions.
+static SAMPLE_TIME: AtomicU8 = AtomicU8::new(AdcSampleTime::Cycles480 as u8);
+
+/// The power detectors of all channels.
+pub struct Detectors {
+    adc: hal::adc::Adc<hal::pac::ADC3>,
+    pins: [(AdcPin, AdcPin); 8],
+    index: usize,
+}
+
+impl Detectors {
+    /// Construct the power detectors.
+    ///
+    /// # Args
+    /// * `adc` - The ADC used to sample the detectors.
+    /// * `pins` - The output and reflected power detector pins of each channel.
+    pub fn new(adc: hal::adc::Adc<hal::pac::ADC3>, pins: [(AdcPin, AdcPin); 8]) -> Self {
+        Self {
+            adc,
+            pins,
+            index: 0,
+        }
+    }
+
+    /// Sample a detector.
+    ///
+    /// # Returns
+    /// The detector voltage in volts.
+    fn sample(&mut self, pin: usize, reflected: bool) -> f32 {
+        let (output_pin, reflected_pin) = &self.pins[pin];
+        let pin = if reflected { reflected_pin } else { output_pin };
+
+        let sample = pin.convert(&mut self.adc, sample_time().into());
+        self.adc.sample_to_millivolts(sample) as f32 / 1000.0
+    }
+
+    /// Execute the fast path.
+    ///
+    /// # Note
+    /// This samples the detectors of the next channel and checks all channels for interlock trips.
+    pub fn poll(&mut self) {
+        let index = self.index;
+        self.index = (index + 1) % self.pins.len();
+
+        let output = self.sample(index, false);
+        let reflected = self.sample(index, true);
+        OUTPUT_VOLTAGE[index].store(output.to_bits(), Ordering::Relaxed);
+        REFLECTED_VOLTAGE[index].store(reflected.to_bits(), Ordering::Relaxed);
+
+        // The output and reflected overdrive inputs of the channels are located on the lower and
+        // upper bytes of GPIOE.
+        let gpioe = unsafe { &*hal::pac::GPIOE::ptr() };
+        let overdrive = gpioe.idr.read().bits();
+        let mut output_trips = overdrive as u8;
+        let mut reflected_trips = (overdrive >> 8) as u8;
+
+        if output > limit(&OUTPUT_LIMIT[index]) + TRIP_MARGIN {
+            output_trips |= 1 << index;
+        }
+        if reflected > limit(&REFLECTED_LIMIT[index]) + TRIP_MARGIN {
+            reflected_trips |= 1 << index;
+        }
+
+        // Only channels with an enabled RF output can trip.
+        let enabled = gate::requested();
+        let output_trips = output_trips & enabled;
+        let reflected_trips = reflected_trips & enabled & !output_trips;
+        let trips = output_trips | reflected_trips;
+        if trips == 0 {
+            return;
+        }
+
+        OUTPUT_TRIPS.fetch_or(output_trips, Ordering::Relaxed);
+        REFLECTED_TRIPS.fetch_or(reflected_trips, Ordering::Relaxed);
+
+        // Disable the RF output of the tripped channels and prevent the gate from enabling them.
+        for channel in enum_iterator::all::<Channel>().filter(|ch| trips & (1 << *ch as u8) != 0) {
+            gate::request(channel, false);
+        }
+
+        let gpiog = unsafe { &*hal::pac::GPIOG::ptr() };
+        // Note that the upper 16 bits of this register are the ODR reset bits.
+        gpiog
+            .bsrr
+            .write(|w| unsafe { w.bits((trips as u32) << (SIGNAL_ON_OFFSET + 16)) });
+    }
+}
+
+/// Load a detector limit.
+fn limit(limit: &AtomicU32) -> f32 {
+    f32::from_bits(limit.load(Ordering::Relaxed))
+}
+
+/// Start the timer that executes the fast path.
+///
+/// # Note
+/// The fast path is executed from the TIM7 interrupt.
+///
+/// # Args
+/// * `timer_clock` - The frequency of the APB1 timer clock in Hz.
+pub fn start(timer_clock: u32) {
+    let rcc = unsafe { &*hal::pac::RCC::ptr() };
+    let tim7 = unsafe { &*hal::pac::TIM7::ptr() };
+
+    rcc.apb1enr.modify(|_, w| w.tim7en().set_bit());
+
+    // Count at 1 MHz.
+    let prescaler = timer_clock / 1_000_000 - 1;
+    let reload = 1_000_000 / SAMPLE_RATE_HZ - 1;
+
+    unsafe {
+        tim7.psc.write(|w| w.bits(prescaler));
+        tim7.arr.write(|w| w.bits(reload));
+    }
+
+    // Load the prescaler without raising an interrupt.
+    tim7.egr.write(|w| w.ug().set_bit());
+    tim7.sr.write(|w| unsafe { w.bits(0) });
+
+    tim7.dier.write(|w| w.uie().set_bit());
+    tim7.cr1.modify(|_, w| w.cen().set_bit());
+}
+
+/// Acknowledge a fast path timer interrupt.
+pub fn acknowledge() {
+    let tim7 = unsafe { &*hal::pac::TIM7::ptr() };
+    tim7.sr.write(|w| unsafe { w.bits(0) });
+}
+
+/// Configure the ADC sample time of detector conversions.
+pub fn set_sample_time(time: AdcSampleTime) {
+    SAMPLE_TIME.store(time as u8, Ordering::Relaxed);
+}
+
+/// Get the ADC sample time of detector conversions.
+fn sample_time() -> AdcSampleTime {
+    match SAMPLE_TIME.load(Ordering::Relaxed) {
+        0 => AdcSampleTime::Cycles3,
+        1 => AdcSampleTime::Cycles15,
+        2 => AdcSampleTime::Cycles28,
+        3 => AdcSampleTime::Cycles56,
+        4 => AdcSampleTime::Cycles84,
+        5 => AdcSampleTime::Cycles112,
+        6 => AdcSampleTime::Cycles144,
+        _ => AdcSampleTime::Cycles480,
+    }
+}
+
+/// Configure the interlock threshold voltages that the detectors of a channel are checked against.
+///
+/// # Args
+/// * `channel` - The channel whose limit to configure.
+/// * `interlock` - The interlock of the limit. Only output and reflected interlocks have limits.
+/// * `voltage` - The interlock threshold voltage of the detector.
+pub fn set_limit(channel: Channel, interlock: Interlock, voltage: f32) {
+    let limits = match interlock {
+        Interlock::Output => &OUTPUT_LIMIT,
+        Interlock::Reflected => &REFLECTED_LIMIT,
+        _ => return,
+    };
+
+    limits[channel as usize].store(voltage.to_bits(), Ordering::Relaxed);
+}
+
+/// Get the most recently sampled output power detector voltage of a channel.
+pub fn output_voltage(channel: Channel) -> f32 {
+    f32::from_bits(OUTPUT_VOLTAGE[channel as usize].load(Ordering::Relaxed))
+}
+
+/// Get the most recently sampled reflected power detector voltage of a channel.
+pub fn reflected_voltage(channel: Channel) -> f32 {
+    f32::from_bits(REFLECTED_VOLTAGE[channel as usize].load(Ordering::Relaxed))
+}
+
+/// Take the pending fast path trip of a channel.
+///
+/// # Returns
+/// The interlock that tripped, if any.
+pub fn take_trip(channel: Channel) -> Option<Interlock> {
+    let mask = 1 << channel as u8;
+    let output = OUTPUT_TRIPS.fetch_and(!mask, Ordering::Relaxed) & mask != 0;
+    let reflected = REFLECTED_TRIPS.fetch_and(!mask, Ordering::Relaxed) & mask != 0;
+
+    if output {
+        Some(Interlock::Output)
+    } else if reflected {
+        Some(Interlock::Reflected)
+    } else {
+        None
+    }
+}
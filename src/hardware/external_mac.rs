@@ -1,11 +1,107 @@
 use super::Mac;
 use enc424j600::EthPhy;
 
-use smoltcp_nal::smoltcp;
+use smoltcp_nal::smoltcp::{
+    self,
+    phy::{Device, TxToken as _},
+    wire::{
+        ArpOperation, ArpPacket, ArpRepr, EthernetAddress, EthernetFrame, EthernetProtocol,
+        EthernetRepr, Ipv4Address,
+    },
+};
+
+/// The address of the ENC424J600 PHSTAT3 PHY register.
+const ENC424J600_PHSTAT3: u8 = 0x1F;
+
+/// The negotiated mode of an established ethernet link.
+#[derive(Copy, Clone, Debug)]
+pub struct LinkStatus {
+    /// The link speed in Mbps.
+    pub speed_mbps: u16,
+
+    /// True if the link is full duplex.
+    pub full_duplex: bool,
+}
+
+impl Mac {
+    /// Check if the ethernet link of the PHY is established.
+    pub fn is_link_up(&mut self) -> bool {
+        match self {
+            Mac::W5500(w5500) => w5500.is_link_up(),
+            Mac::Enc424j600(mac) => mac.is_link_up(),
+        }
+    }
+
+    /// Get the negotiated speed and duplex of the ethernet link.
+    ///
+    /// # Returns
+    /// The link status, or None if the PHY status could not be read.
+    pub fn link_status(&mut self) -> Option<LinkStatus> {
+        let (speed_100, full_duplex) = match self {
+            Mac::W5500(w5500) => {
+                // PHYCFGR: SPD (bit 1) is set at 100 Mbps and DPX (bit 2) is set at full duplex.
+                let phy: u8 = w5500.phy_config().ok()?.into();
+                (phy & (1 << 1) != 0, phy & (1 << 2) != 0)
+            }
+            Mac::Enc424j600(mac) => {
+                // PHSTAT3: SPDDPX (bits 4:2) has bit 3 set at 100 Mbps and bit 4 set at full
+                // duplex.
+                let phy = mac.read_phy_reg(ENC424J600_PHSTAT3).ok()?;
+                (phy & (1 << 3) != 0, phy & (1 << 4) != 0)
+            }
+        };
+
+        Some(LinkStatus {
+            speed_mbps: if speed_100 { 100 } else { 10 },
+            full_duplex,
+        })
+    }
+
+    /// Transmit a gratuitous ARP announcement.
+    ///
+    /// # Note
+    /// The announcement causes hosts on the network to update any cached address mapping, such
+    /// that they quickly re-learn the address of the device after the link is re-established.
+    ///
+    /// # Args
+    /// * `hardware_addr` - The MAC address of the device.
+    /// * `protocol_addr` - The IP address of the device.
+    pub fn announce(&mut self, hardware_addr: EthernetAddress, protocol_addr: Ipv4Address) {
+        let arp = ArpRepr::EthernetIpv4 {
+            operation: ArpOperation::Request,
+            source_hardware_addr: hardware_addr,
+            source_protocol_addr: protocol_addr,
+            target_hardware_addr: EthernetAddress([0; 6]),
+            target_protocol_addr: protocol_addr,
+        };
+
+        let ethernet = EthernetRepr {
+            src_addr: hardware_addr,
+            dst_addr: EthernetAddress::BROADCAST,
+            ethertype: EthernetProtocol::Arp,
+        };
+
+        let Some(token) = self.transmit(smoltcp::time::Instant::ZERO) else {
+            return;
+        };
+
+        token.consume(ethernet.buffer_len() + arp.buffer_len(), |buffer| {
+            let mut frame = EthernetFrame::new_unchecked(buffer);
+            ethernet.emit(&mut frame);
+            arp.emit(&mut ArpPacket::new_unchecked(frame.payload_mut()));
+        });
+    }
+}
 
 impl smoltcp::phy::Device for Mac {
-    type RxToken<'a> = RxToken where Self: 'a;
-    type TxToken<'a> = TxToken<'a> where Self: 'a;
+    type RxToken<'a>
+        = RxToken
+    where
+        Self: 'a;
+    type TxToken<'a>
+        = TxToken<'a>
+    where
+        Self: 'a;
 
     fn capabilities(&self) -> smoltcp::phy::DeviceCapabilities {
         let mut caps = smoltcp::phy::DeviceCapabilities::default();
@@ -0,0 +1,89 @@
+//! Booster measurement filtering
+//!
+//! # Design
+//! Channel measurements used in telemetry and software protections are smoothed with single-pole
+//! IIR (exponential moving average) filters, so that noisy single samples do not raise false
+//! warnings. The filter coefficient is derived from the configured time constant and the time
+//! elapsed since the previous sample, because measurements are not acquired at a fixed rate.
+//!
+//! Powers are filtered in dBm. Hardware interlocks and the input overdrive check always use
+//! unfiltered measurements, so that filtering never delays an interlock trip.
+use super::SystemTimer;
+use minimq::embedded_time::{duration::Milliseconds, Instant};
+use serde::{Deserialize, Serialize};
+
+/// The maximum configurable filter time constant in seconds.
+const MAX_TIME_CONSTANT: f32 = 60.0;
+
+/// The time constants of the channel measurement filters in seconds. Measurements are not
+/// filtered if the time constant is zero.
+#[derive(Serialize, Deserialize, miniconf::Tree, Copy, Clone, Debug, Default, PartialEq)]
+pub struct MeasurementFilter {
+    /// The time constant of temperature measurements.
+    pub temperature: f32,
+
+    /// The time constant of supply current measurements.
+    pub current: f32,
+
+    /// The time constant of input, output, and reflected power measurements.
+    pub power: f32,
+}
+
+impl MeasurementFilter {
+    /// Check that all time constants are valid.
+    pub fn is_valid(&self) -> bool {
+        [self.temperature, self.current, self.power]
+            .iter()
+            .all(|tau| (0.0..=MAX_TIME_CONSTANT).contains(tau))
+    }
+}
+
+/// A single-pole IIR filter of a measurement.
+#[derive(Copy, Clone, Default)]
+pub struct Iir {
+    state: f32,
+    last: Option<Instant<SystemTimer>>,
+}
+
+impl Iir {
+    /// Filter a measurement sample.
+    ///
+    /// # Note
+    /// The first sample initializes the filter state.
+    ///
+    /// # Args
+    /// * `sample` - The measurement sample.
+    /// * `now` - The time at which the sample was acquired.
+    /// * `time_constant` - The filter time constant in seconds.
+    ///
+    /// # Returns
+    /// The filtered measurement.
+    pub fn update(&mut self, sample: f32, now: Instant<SystemTimer>, time_constant: f32) -> f32 {
+        let elapsed = self
+            .last
+            .and_then(|last| now.checked_duration_since(&last))
+            .and_then(|elapsed| Milliseconds::<u32>::try_from(elapsed).ok());
+        self.last.replace(now);
+
+        match elapsed {
+            Some(Milliseconds(elapsed)) if time_constant > 0.0 => {
+                let alpha = 1.0 - libm::expf(-(elapsed as f32 / 1000.0) / time_constant);
+                self.state += alpha * (sample - self.state);
+            }
+            _ => self.state = sample,
+        }
+
+        self.state
+    }
+}
+
+/// The measurement filters of a channel.
+#[derive(Copy, Clone, Default)]
+pub struct ChannelFilters {
+    pub temperature: Iir,
+    pub p28v_current: Iir,
+    pub p5v_current: Iir,
+    pub input_power: Iir,
+    pub output_power: Iir,
+    pub reflected_power: Iir,
+}
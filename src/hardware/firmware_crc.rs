@@ -0,0 +1,79 @@
+//! Booster firmware integrity check
+//!
+//! # Design
+//! After linking, the binary image is patched by `py/firmware_crc.py`, which writes the CRC32 of
+//! the image into the [FIRMWARE_CRC] record. The CRC covers the whole image in flash except for
+//! the CRC value itself. At startup, the CRC of the image in flash is computed and compared
+//! against the record to detect corrupted firmware.
+//!
+//! Images that have not been patched (e.g. when flashed directly via a debug probe) are not
+//! checked.
+use serde::Serialize;
+
+/// The flash address that the firmware image starts at.
+const FLASH_START: usize = 0x0800_0000;
+
+/// The CRC value of images that have not been patched.
+const UNPATCHED: u32 = 0xFFFF_FFFF;
+
+/// A record in the firmware image that stores the CRC of the image.
+#[repr(C)]
+struct CrcRecord {
+    /// A marker used to locate the record in the binary image.
+    magic: [u8; 8],
+    crc: u32,
+}
+
+#[used]
+static FIRMWARE_CRC: CrcRecord = CrcRecord {
+    magic: *b"BSTR-CRC",
+    crc: UNPATCHED,
+};
+
+extern "C" {
+    // Symbols provided by the cortex-m-rt linker script. The `.data` section is the last section
+    // of the image in flash.
+    static __sidata: u32;
+    static __sdata: u32;
+    static __edata: u32;
+}
+
+/// The result of the firmware integrity check.
+#[derive(Serialize, Copy, Clone, Debug, PartialEq)]
+pub enum Integrity {
+    /// The image does not contain a CRC.
+    Unchecked,
+
+    /// The image CRC matches.
+    Valid,
+
+    /// The image CRC does not match. The firmware in flash is corrupted.
+    Corrupted,
+}
+
+/// Verify the CRC of the firmware image in flash.
+pub fn check() -> Integrity {
+    // Note: The record is patched after compilation, so the compiler may not assume its value.
+    let expected = unsafe { core::ptr::read_volatile(&FIRMWARE_CRC.crc) };
+    if expected == UNPATCHED {
+        return Integrity::Unchecked;
+    }
+
+    let image = unsafe {
+        let data_len = &__edata as *const u32 as usize - &__sdata as *const u32 as usize;
+        let end = &__sidata as *const u32 as usize + data_len;
+        core::slice::from_raw_parts(FLASH_START as *const u8, end - FLASH_START)
+    };
+
+    let offset = &FIRMWARE_CRC.crc as *const u32 as usize - FLASH_START;
+
+    let mut crc32 = crc_any::CRC::crc32();
+    crc32.digest(&image[..offset]);
+    crc32.digest(&image[offset + core::mem::size_of::<u32>()..]);
+
+    if crc32.get_crc() as u32 == expected {
+        Integrity::Valid
+    } else {
+        Integrity::Corrupted
+    }
+}
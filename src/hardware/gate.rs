@@ -0,0 +1,174 @@
+//! Booster hardware RF gate input
+//!
+//! # Design
+//! The spare PG6 GPIO can be used as a hardware gate input, so that the RF output of selected
+//! channels can be switched synchronously with an experiment without any network latency. The gate
+//! is ANDed with the ON/OFF signal of each gated channel: the RF output of a gated channel is only
+//! on if the channel output is enabled and the gate is open.
+//!
+//! Both edges of the gate input raise an interrupt (via EXTI line 6) and the ON/OFF signals of the
+//! gated channels are updated directly via their control pins, so that gating is never delayed by
+//! RF module communication. A falling edge on ON/OFF re-arms the interlocks of a channel, so
+//! channels with a tripped interlock are never gated off. Their RF output remains latched off until
+//! the trip is handled.
+use super::{hal, Channel};
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use serde::{Deserialize, Serialize};
+
+/// The GPIOG pin used as the gate input.
+const GATE_PIN: u32 = 6;
+
+/// The EXTI line connected to the gate input.
+const GATE_EXTI_LINE: u32 = 1 << GATE_PIN;
+
+/// The SYSCFG EXTI source selection of GPIOG.
+const EXTI_SOURCE_GPIOG: u32 = 0b0110;
+
+/// The first GPIOG pin used for the ON/OFF signals of the channels.
+const SIGNAL_ON_OFFSET: u32 = 8;
+
+/// The channels whose RF output is gated by the gate input.
+static GATED: AtomicU8 = AtomicU8::new(0);
+
+/// The channels whose RF output is enabled by the channel state machine.
+static REQUESTED: AtomicU8 = AtomicU8::new(0);
+
+/// Specified true if the gate is open when the gate input is high.
+static ACTIVE_HIGH: AtomicBool = AtomicBool::new(true);
+
+/// Configuration of the hardware gate input.
+#[derive(Serialize, Deserialize, miniconf::Tree, Copy, Clone, Debug, PartialEq)]
+pub struct Gate {
+    /// Specified true to gate the RF output of the selected channels.
+    pub enabled: bool,
+
+    /// Specified true if the gate is open when the gate input is high. Otherwise, the gate is open
+    /// when the input is low.
+    pub active_high: bool,
+
+    /// The channels whose RF output is gated. Bit 0 corresponds to channel 0.
+    pub channels: u8,
+}
+
+impl Default for Gate {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            active_high: true,
+            channels: 0xFF,
+        }
+    }
+}
+
+/// Enable the gate input interrupt.
+///
+/// # Note
+/// The gate input pin must already be configured as an input.
+pub fn enable() {
+    let rcc = unsafe { &*hal::pac::RCC::ptr() };
+    let syscfg = unsafe { &*hal::pac::SYSCFG::ptr() };
+    let exti = unsafe { &*hal::pac::EXTI::ptr() };
+
+    rcc.apb2enr.modify(|_, w| w.syscfgen().set_bit());
+
+    // EXTI lines 4 to 7 are selected in EXTICR2, with four bits per line.
+    let shift = (GATE_PIN - 4) * 4;
+    unsafe {
+        syscfg
+            .exticr2
+            .modify(|r, w| w.bits((r.bits() & !(0xF << shift)) | (EXTI_SOURCE_GPIOG << shift)));
+
+        exti.rtsr.modify(|r, w| w.bits(r.bits() | GATE_EXTI_LINE));
+        exti.ftsr.modify(|r, w| w.bits(r.bits() | GATE_EXTI_LINE));
+        exti.imr.modify(|r, w| w.bits(r.bits() | GATE_EXTI_LINE));
+        exti.pr.write(|w| w.bits(GATE_EXTI_LINE));
+    }
+}
+
+/// Disable the gate input interrupt and release all requested channel outputs.
+///
+/// # Note
+/// This is used when channels are shut down, so that the gate can no longer enable any output.
+pub fn disable() {
+    let exti = unsafe { &*hal::pac::EXTI::ptr() };
+    exti.imr
+        .modify(|r, w| unsafe { w.bits(r.bits() & !GATE_EXTI_LINE) });
+    REQUESTED.store(0, Ordering::Relaxed);
+}
+
+/// Acknowledge a gate input interrupt.
+pub fn acknowledge() {
+    let exti = unsafe { &*hal::pac::EXTI::ptr() };
+    exti.pr.write(|w| unsafe { w.bits(GATE_EXTI_LINE) });
+}
+
+/// Configure the gate and apply it to the channel outputs.
+///
+/// # Args
+/// * `gate` - The gate configuration.
+pub fn configure(gate: &Gate) {
+    ACTIVE_HIGH.store(gate.active_high, Ordering::Relaxed);
+    GATED.store(
+        if gate.enabled { gate.channels } else { 0 },
+        Ordering::Relaxed,
+    );
+    update();
+}
+
+/// Check if the gate is open.
+fn is_open() -> bool {
+    let gpiog = unsafe { &*hal::pac::GPIOG::ptr() };
+    let high = gpiog.idr.read().bits() & (1 << GATE_PIN) != 0;
+    high == ACTIVE_HIGH.load(Ordering::Relaxed)
+}
+
+/// Check if the RF output of a channel is currently passed by the gate.
+pub fn is_passing(channel: Channel) -> bool {
+    GATED.load(Ordering::Relaxed) & (1 << channel as u8) == 0 || is_open()
+}
+
+/// Record whether the channel state machine enables the RF output of a channel.
+///
+/// # Args
+/// * `channel` - The channel whose output is enabled or disabled.
+/// * `enabled` - Specified true if the RF output is enabled.
+pub fn request(channel: Channel, enabled: bool) {
+    let mask = 1 << channel as u8;
+    if enabled {
+        REQUESTED.fetch_or(mask, Ordering::Relaxed);
+    } else {
+        REQUESTED.fetch_and(!mask, Ordering::Relaxed);
+    }
+}
+
+/// Get the channels whose RF output is enabled by the channel state machine.
+pub fn requested() -> u8 {
+    REQUESTED.load(Ordering::Relaxed)
+}
+
+/// Update the ON/OFF signals of all channels according to the gate input.
+pub fn update() {
+    let gpioe = unsafe { &*hal::pac::GPIOE::ptr() };
+    let gpiog = unsafe { &*hal::pac::GPIOG::ptr() };
+
+    cortex_m::interrupt::free(|_| {
+        let passing = if is_open() {
+            0xFF
+        } else {
+            !GATED.load(Ordering::Relaxed)
+        };
+
+        // The output and reflected overdrive inputs of the channels are located on the lower and
+        // upper bytes of GPIOE.
+        let overdrive = gpioe.idr.read().bits();
+        let tripped = (overdrive | (overdrive >> 8)) as u8;
+
+        let set = REQUESTED.load(Ordering::Relaxed) & passing;
+        let reset = !passing & !tripped;
+
+        // Note that the upper 16 bits of this register are the ODR reset bits.
+        gpiog.bsrr.write(|w| unsafe {
+            w.bits(((set as u32) << SIGNAL_ON_OFFSET) | ((reset as u32) << (SIGNAL_ON_OFFSET + 16)))
+        });
+    });
+}
@@ -0,0 +1,367 @@
+//! Booster manufacturing test mode
+//!
+//! # Design
+//! With the `manufacturing` feature, the USB shell provides end-of-line test commands that exercise
+//! the mainboard and RF module hardware directly. Each test writes one JSON record per checked item
+//! followed by a summary record of the test, e.g.
+//! `{"test":"i2c","channel":"Zero","item":"DAC7571","passed":true}` and
+//! `{"test":"i2c","passed":true}`, so that the report can be parsed by test fixtures.
+//!
+//! Tests that drive channel control signals or DACs bypass the channel state machine. They are
+//! refused while any channel is powered.
+use super::{
+    booster_channels::{BoosterChannels, CHANNEL_DEVICES},
+    hal, Channel,
+};
+use core::fmt::Write;
+use serde::Serialize;
+
+/// The number of CPU cycles to wait for an output pin to settle before reading it back.
+const SETTLE_CYCLES: u32 = 1_000;
+
+/// The number of steps of each DAC sweep.
+const DAC_SWEEP_STEPS: usize = 5;
+
+/// The maximum deviation of a DAC readback from its nominal voltage.
+const DAC_TOLERANCE: f32 = 0.001;
+
+/// The plausible range of ADC channel voltages.
+const ADC_RANGE: core::ops::RangeInclusive<f32> = 0.0..=3.5;
+
+/// The names and I2C addresses of the devices on the shared I2C bus: the TCA9548 multiplexer and
+/// the MAX6639 fan controllers.
+const SHARED_DEVICES: [(&str, u8); 4] = [
+    ("TCA9548", 0x70),
+    ("MAX6639-1", 0x2C),
+    ("MAX6639-2", 0x2E),
+    ("MAX6639-3", 0x2F),
+];
+
+/// The manufacturing tests.
+#[derive(Copy, Clone, Debug)]
+pub enum Test {
+    /// Toggle every output GPIO and read back its level, and read the level of every input GPIO.
+    Gpio,
+
+    /// Probe every I2C device on the shared bus and on the bus segment of each channel.
+    I2c,
+
+    /// Sweep the bias and interlock threshold DACs of each installed channel and read them back.
+    Dac,
+
+    /// Read all ADC channels of each installed channel.
+    Adc,
+
+    /// Run all tests.
+    All,
+}
+
+impl Test {
+    /// The usage of all manufacturing test commands.
+    pub const USAGE: &'static str = "mfg-gpio, mfg-i2c, mfg-dac, mfg-adc, mfg-all";
+
+    /// Parse a manufacturing test command.
+    ///
+    /// # Args
+    /// * `command` - The command provided to the platform, e.g. `mfg-i2c`.
+    ///
+    /// # Returns
+    /// The test if it is a valid manufacturing test command. None otherwise.
+    pub fn parse(command: &str) -> Option<Self> {
+        let test = match command.strip_prefix("mfg-")? {
+            "gpio" => Self::Gpio,
+            "i2c" => Self::I2c,
+            "dac" => Self::Dac,
+            "adc" => Self::Adc,
+            "all" => Self::All,
+            _ => return None,
+        };
+
+        Some(test)
+    }
+}
+
+/// A record of the manufacturing test report.
+#[derive(Serialize, Default)]
+struct Record<'a> {
+    test: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    channel: Option<Channel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    item: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nominal: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    level: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a str>,
+    passed: bool,
+}
+
+impl Record<'_> {
+    /// Write the record as a line of JSON.
+    fn write(&self, output: &mut impl Write) {
+        let mut buffer = [0u8; 192];
+        if let Ok(len) = serde_json_core::to_slice(self, &mut buffer) {
+            let line = core::str::from_utf8(&buffer[..len]).unwrap_or_default();
+            writeln!(output, "{line}").ok();
+        }
+    }
+}
+
+/// Toggle an output pin and verify that its level follows.
+///
+/// # Note
+/// The pin is toggled to the opposite level and back, such that it retains its original level.
+macro_rules! toggle_output {
+    ($port:ident, $pin:expr) => {{
+        let gpio = unsafe { &*hal::pac::$port::ptr() };
+        let mask = 1u32 << $pin;
+        let set = gpio.odr.read().bits() & mask != 0;
+
+        let mut passed = true;
+        for level in [!set, set] {
+            // Note that the upper 16 bits of this register are the ODR reset bits.
+            let bits = if level { mask } else { mask << 16 };
+            gpio.bsrr.write(|w| unsafe { w.bits(bits) });
+            cortex_m::asm::delay(SETTLE_CYCLES);
+            passed &= (gpio.idr.read().bits() & mask != 0) == level;
+        }
+
+        passed
+    }};
+}
+
+/// Read the level of an input pin.
+macro_rules! read_input {
+    ($port:ident, $pin:expr) => {{
+        let gpio = unsafe { &*hal::pac::$port::ptr() };
+        gpio.idr.read().bits() & (1u32 << $pin) != 0
+    }};
+}
+
+/// Check if any channel is powered.
+fn channels_powered() -> bool {
+    // The enable signals of the channels are located on the lower byte of GPIOD.
+    let gpiod = unsafe { &*hal::pac::GPIOD::ptr() };
+    gpiod.odr.read().bits() & 0xFF != 0
+}
+
+/// Execute a manufacturing test.
+///
+/// # Args
+/// * `channels` - The RF channels.
+/// * `test` - The test to execute.
+/// * `output` - The terminal to write the test report to.
+///
+/// # Returns
+/// True if the test passed.
+pub fn execute(channels: &mut BoosterChannels, test: Test, output: &mut impl Write) -> bool {
+    let (name, passed) = match test {
+        Test::Gpio => ("gpio", test_gpio(output)),
+        Test::I2c => ("i2c", test_i2c(channels, output)),
+        Test::Dac => ("dac", test_dac(channels, output)),
+        Test::Adc => ("adc", test_adc(channels, output)),
+        Test::All => {
+            // All tests are run, even if an earlier test fails.
+            let passed = [Test::Gpio, Test::I2c, Test::Dac, Test::Adc]
+                .iter()
+                .fold(true, |passed, &test| {
+                    execute(channels, test, output) && passed
+                });
+            ("all", passed)
+        }
+    };
+
+    Record {
+        test: name,
+        passed,
+        ..Default::default()
+    }
+    .write(output);
+
+    passed
+}
+
+/// Get the name of a GPIO pin, e.g. `PD0`.
+fn name(port: char, pin: u32) -> heapless::String<4> {
+    let mut name = heapless::String::new();
+    write!(name, "P{port}{pin}").ok();
+    name
+}
+
+/// Report an item of a test.
+fn report(output: &mut impl Write, test: &str, channel: Option<Channel>, item: &str, passed: bool) {
+    Record {
+        test,
+        channel,
+        item: Some(item),
+        passed,
+        ..Default::default()
+    }
+    .write(output);
+}
+
+/// Report that a test was refused because channels are powered.
+fn refuse(output: &mut impl Write, test: &str) {
+    Record {
+        test,
+        error: Some("Channels must be powered off"),
+        ..Default::default()
+    }
+    .write(output);
+}
+
+fn test_gpio(output: &mut impl Write) -> bool {
+    if channels_powered() {
+        refuse(output, "gpio");
+        return false;
+    }
+
+    let mut passed = true;
+
+    // Outputs: the channel enable signals, the channel ON/OFF signals, the status output, and the
+    // mainboard LEDs.
+    for pin in 0..8 {
+        let result = toggle_output!(GPIOD, pin);
+        report(output, "gpio", None, &name('D', pin), result);
+        passed &= result;
+    }
+    for pin in 7..16 {
+        let result = toggle_output!(GPIOG, pin);
+        report(output, "gpio", None, &name('G', pin), result);
+        passed &= result;
+    }
+    for pin in 8..11 {
+        let result = toggle_output!(GPIOC, pin);
+        report(output, "gpio", None, &name('C', pin), result);
+        passed &= result;
+    }
+
+    // Inputs cannot be verified without a test fixture, so their levels are reported: the channel
+    // alert signals, the overdrive signals, the gate input, the user buttons, and the hardware
+    // revision.
+    let inputs = (8..16)
+        .map(|pin| ('D', pin, read_input!(GPIOD, pin)))
+        .chain((0..16).map(|pin| ('E', pin, read_input!(GPIOE, pin))))
+        .chain(core::iter::once(6).map(|pin| ('G', pin, read_input!(GPIOG, pin))))
+        .chain(
+            [0, 1, 2, 14, 15]
+                .iter()
+                .map(|&pin| ('F', pin, read_input!(GPIOF, pin))),
+        );
+    for (port, pin, level) in inputs {
+        Record {
+            test: "gpio",
+            item: Some(&name(port, pin)),
+            level: Some(level),
+            passed: true,
+            ..Default::default()
+        }
+        .write(output);
+    }
+
+    passed
+}
+
+fn test_i2c(channels: &mut BoosterChannels, output: &mut impl Write) -> bool {
+    let mut passed = true;
+
+    for (device, address) in SHARED_DEVICES {
+        let result = channels.probe(None, address);
+        report(output, "i2c", None, device, result);
+        passed &= result;
+    }
+
+    for channel in enum_iterator::all::<Channel>() {
+        for device in CHANNEL_DEVICES {
+            let result = channels.probe(Some(channel), device.address());
+            report(output, "i2c", Some(channel), device.name(), result);
+            passed &= result;
+        }
+    }
+
+    passed
+}
+
+fn test_dac(channels: &mut BoosterChannels, output: &mut impl Write) -> bool {
+    if channels_powered() {
+        refuse(output, "dac");
+        return false;
+    }
+
+    let mut passed = true;
+    for idx in enum_iterator::all::<Channel>() {
+        let Some(channel) = channels.channel_mut(idx) else {
+            continue;
+        };
+
+        let result =
+            channel
+                .context_mut()
+                .sweep_dacs(DAC_SWEEP_STEPS, |device, nominal, readback| {
+                    let result = matches!((nominal, readback),
+                    (Some(nominal), Some(readback)) if (nominal - readback).abs() < DAC_TOLERANCE);
+                    Record {
+                        test: "dac",
+                        channel: Some(idx),
+                        item: Some(device),
+                        nominal,
+                        value: readback,
+                        passed: result,
+                        ..Default::default()
+                    }
+                    .write(output);
+                    passed &= result;
+                });
+
+        if result.is_err() {
+            report(output, "dac", Some(idx), "restore", false);
+            passed = false;
+        }
+    }
+
+    passed
+}
+
+fn test_adc(channels: &mut BoosterChannels, output: &mut impl Write) -> bool {
+    let mut passed = true;
+    for idx in enum_iterator::all::<Channel>() {
+        let Some(channel) = channels.channel_mut(idx) else {
+            continue;
+        };
+
+        let Ok(voltages) = channel.context_mut().get_raw_voltages() else {
+            report(output, "adc", Some(idx), "read", false);
+            passed = false;
+            continue;
+        };
+
+        let [monitor0, monitor1, monitor2, monitor3] = voltages.power_monitor;
+        for (item, value) in [
+            ("MCP3221", voltages.input_detector),
+            ("ADC3-OUT", voltages.output_detector),
+            ("ADC3-REFL", voltages.reflected_detector),
+            ("ADS7924-0", monitor0),
+            ("ADS7924-1", monitor1),
+            ("ADS7924-2", monitor2),
+            ("ADS7924-3", monitor3),
+        ] {
+            let result = ADC_RANGE.contains(&value);
+            Record {
+                test: "adc",
+                channel: Some(idx),
+                item: Some(item),
+                value: Some(value),
+                passed: result,
+                ..Default::default()
+            }
+            .write(output);
+            passed &= result;
+        }
+    }
+
+    passed
+}
@@ -0,0 +1,66 @@
+//! Booster memory usage monitoring
+//!
+//! # Design
+//! At startup, the unused stack is painted with a known pattern. The stack high-water mark is then
+//! found by scanning for the lowest address at which the pattern has been overwritten.
+
+/// The pattern that unused stack memory is painted with.
+const STACK_PAINT: u32 = 0xCCCC_CCCC;
+
+/// The stack memory immediately below the current stack pointer that is not painted.
+const PAINT_MARGIN: usize = 256;
+
+extern "C" {
+    // Symbols provided by the cortex-m-rt linker script.
+    static __sdata: u32;
+    static __sheap: u32;
+    static _stack_start: u32;
+}
+
+fn static_start() -> usize {
+    unsafe { &__sdata as *const u32 as usize }
+}
+
+fn stack_limit() -> usize {
+    unsafe { &__sheap as *const u32 as usize }
+}
+
+fn stack_start() -> usize {
+    unsafe { &_stack_start as *const u32 as usize }
+}
+
+/// Paint the unused stack memory.
+///
+/// # Note
+/// This must be called once at startup, before the stack grows significantly.
+pub fn paint_stack() {
+    let end = cortex_m::register::msp::read() as usize - PAINT_MARGIN;
+
+    let mut address = stack_limit();
+    while address < end {
+        unsafe { core::ptr::write_volatile(address as *mut u32, STACK_PAINT) };
+        address += core::mem::size_of::<u32>();
+    }
+}
+
+/// Get the amount of RAM used by static variables in bytes.
+pub fn static_ram_usage() -> usize {
+    stack_limit() - static_start()
+}
+
+/// Get the maximum amount of stack used since startup in bytes.
+pub fn stack_high_water() -> usize {
+    let mut address = stack_limit();
+    while address < stack_start()
+        && unsafe { core::ptr::read_volatile(address as *const u32) } == STACK_PAINT
+    {
+        address += core::mem::size_of::<u32>();
+    }
+
+    stack_start() - address
+}
+
+/// Get the total size of the stack in bytes.
+pub fn stack_size() -> usize {
+    stack_start() - stack_limit()
+}
@@ -1,24 +1,98 @@
 //! Booster run-time application metadata
 
+use core::fmt::Write;
+use core::sync::atomic::{AtomicBool, Ordering};
+use heapless::String;
 use serde::Serialize;
 
-use super::{platform, HardwareVersion};
+use super::{
+    firmware_crc, memory, platform, safe_mode, self_test::SelfTest, HardwareVersion,
+    MONOTONIC_FREQUENCY,
+};
 
 mod build_info {
     include!(concat!(env!("OUT_DIR"), "/built.rs"));
 }
 
+/// A EUI-48 identifier, serialized in the form `12-34-56-78-90-ab`.
+#[derive(Copy, Clone, Debug)]
+pub struct Eui48(pub [u8; 6]);
+
+impl Serialize for Eui48 {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut display: String<17> = String::new();
+        let [a, b, c, d, e, f] = self.0;
+        write!(
+            &mut display,
+            "{a:02x}-{b:02x}-{c:02x}-{d:02x}-{e:02x}-{f:02x}"
+        )
+        .unwrap();
+        serializer.serialize_str(&display)
+    }
+}
+
+/// Specified true once the panic message of the previous boot has been acknowledged.
+static PANIC_CLEARED: AtomicBool = AtomicBool::new(false);
+
+fn serialize_panic_info<S: serde::Serializer>(
+    info: &&'static str,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let info = if PANIC_CLEARED.load(Ordering::Relaxed) {
+        "None"
+    } else {
+        info
+    };
+    serializer.serialize_str(info)
+}
+
+/// The time since startup in seconds, sampled when serialized.
+pub struct Uptime;
+
+impl Serialize for Uptime {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let ticks = crate::app::monotonics::now().ticks();
+        serializer.serialize_u64(ticks / MONOTONIC_FREQUENCY as u64)
+    }
+}
+
+/// The maximum stack usage since startup in bytes, sampled when serialized.
+pub struct StackHighWater;
+
+impl Serialize for StackHighWater {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(memory::stack_high_water() as u32)
+    }
+}
+
 #[derive(Serialize)]
 pub struct ApplicationMetadata {
+    pub eui48: Eui48,
     pub phy: &'static str,
     pub firmware_version: &'static str,
     pub rust_version: &'static str,
     pub profile: &'static str,
     pub git_dirty: bool,
     pub features: &'static str,
-    pub panic_info: &'static str,
+    #[serde(serialize_with = "serialize_panic_info")]
+    panic_info: &'static str,
     pub watchdog: bool,
+    pub reset_cause: platform::ResetCause,
+    pub uptime_secs: Uptime,
+    pub static_ram_bytes: u32,
+    pub stack_bytes: u32,
+    pub stack_high_water_bytes: StackHighWater,
+
+    /// Specified true if the device booted into safe mode after repeated abnormal resets.
+    pub safe_mode: bool,
+    pub firmware_integrity: firmware_crc::Integrity,
     pub hardware_version: HardwareVersion,
+
+    /// The report of the startup self-test.
+    pub self_test: SelfTest,
+
+    /// The connection state of the MQTT clients.
+    pub clients: crate::net::ClientStatus,
 }
 
 impl ApplicationMetadata {
@@ -30,14 +104,19 @@ impl ApplicationMetadata {
     /// # Args
     /// * `hardware_version` - The hardware version detected.
     /// * `phy` - The identifier of the detected ethernet PHY.
+    /// * `eui48` - The EUI-48 identifier of the mainboard.
+    /// * `self_test` - The report of the startup self-test.
     ///
     /// # Returns
     /// A reference to the global metadata.
     pub fn new(
         hardware_version: HardwareVersion,
         phy: &'static str,
+        eui48: [u8; 6],
+        self_test: SelfTest,
     ) -> &'static ApplicationMetadata {
         let meta = cortex_m::singleton!(: ApplicationMetadata = ApplicationMetadata {
+            eui48: Eui48(eui48),
             phy,
             firmware_version: "Unspecified",
             rust_version: build_info::RUSTC_VERSION,
@@ -46,14 +125,30 @@ impl ApplicationMetadata {
             features: build_info::FEATURES_STR,
             panic_info: "None",
             watchdog: platform::watchdog_detected(),
+            reset_cause: platform::ResetCause::Unknown,
+            uptime_secs: Uptime,
+            static_ram_bytes: memory::static_ram_usage() as u32,
+            stack_bytes: memory::stack_size() as u32,
+            stack_high_water_bytes: StackHighWater,
+            safe_mode: safe_mode::is_active(),
+            firmware_integrity: firmware_crc::check(),
             hardware_version,
+            self_test,
+            clients: crate::net::ClientStatus,
         })
         .unwrap();
 
-        if let Some(panic_data) = panic_persist::get_panic_message_utf8() {
+        let panic_data = panic_persist::get_panic_message_utf8();
+        if let Some(panic_data) = panic_data {
             meta.panic_info = panic_data;
         }
 
+        meta.reset_cause = platform::reset_cause(panic_data.is_some());
+
+        if meta.firmware_integrity == firmware_crc::Integrity::Corrupted {
+            log::error!("Firmware image CRC mismatch. The firmware in flash is corrupted");
+        }
+
         if let Some(dirty) = build_info::GIT_DIRTY {
             meta.git_dirty = dirty;
         }
@@ -64,4 +159,21 @@ impl ApplicationMetadata {
 
         meta
     }
+
+    /// Get the panic message persisted from the previous boot.
+    ///
+    /// # Note
+    /// The persisted message is consumed at startup and is no longer reported once cleared.
+    pub fn panic_info(&self) -> &'static str {
+        if PANIC_CLEARED.load(Ordering::Relaxed) {
+            "None"
+        } else {
+            self.panic_info
+        }
+    }
+
+    /// Clear the panic message persisted from the previous boot.
+    pub fn clear_panic_info(&self) {
+        PANIC_CLEARED.store(true, Ordering::Relaxed);
+    }
 }
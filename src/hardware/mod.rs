@@ -8,15 +8,28 @@ use stm32f4xx_hal as hal;
 pub mod booster_channels;
 pub mod chassis_fans;
 pub mod delay;
+pub mod detectors;
 pub mod external_mac;
+pub mod filter;
+pub mod firmware_crc;
 pub mod flash;
+pub mod gate;
+#[cfg(feature = "manufacturing")]
+pub mod manufacturing;
+pub mod memory;
 pub mod metadata;
 pub mod net_interface;
 pub mod platform;
+pub mod power_fail;
 pub mod rf_channel;
+pub mod rtc;
+pub mod safe_mode;
+pub mod self_test;
 pub mod serial_terminal;
 pub mod setup;
+pub mod status_output;
 pub mod usb;
+pub mod usb_shell;
 pub mod user_interface;
 
 pub const MONOTONIC_FREQUENCY: u32 = 1_000;
@@ -44,6 +57,9 @@ pub enum Mac {
     Enc424j600(enc424j600::Enc424j600<Spi, SpiCs>),
 }
 
+/// The identifiers of all ethernet PHYs supported by the firmware.
+pub const SUPPORTED_PHYS: &[&str] = &["W5500", "Enc424j600"];
+
 pub type SerialTerminal = serial_settings::Runner<'static, serial_terminal::SerialSettingsPlatform>;
 
 pub type NetworkStack = smoltcp_nal::NetworkStack<'static, Mac, SystemTimer>;
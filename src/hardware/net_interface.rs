@@ -5,23 +5,90 @@ use smoltcp_nal::smoltcp;
 
 use super::Mac;
 
+/// The number of TCP sockets available in addition to those used by the firmware.
+///
+/// # Note
+/// Additional sockets are provided with the `extra-tcp-sockets` feature.
+pub const NUM_EXTRA_TCP_SOCKETS: usize = if cfg!(feature = "extra-tcp-sockets") {
+    4
+} else {
+    0
+};
+
+/// The number of TCP sockets with small buffers for the network servers.
+///
+/// # Note
+/// The metrics, SCPI, and JSON-RPC servers each use one socket to listen for and one socket to
+/// service incoming connections.
+pub const NUM_SERVER_TCP_SOCKETS: usize = 6;
+
 /// The number of TCP sockets supported in the network stack.
-const NUM_TCP_SOCKETS: usize = 4;
+///
+/// # Note
+/// Three sockets are used by the MQTT clients, in addition to the server sockets.
+pub const NUM_TCP_SOCKETS: usize = 3 + NUM_EXTRA_TCP_SOCKETS + NUM_SERVER_TCP_SOCKETS;
+
+/// The number of UDP sockets supported in the network stack.
+///
+/// # Note
+/// A single socket is used for forwarding logs to a remote syslog server.
+pub const NUM_UDP_SOCKETS: usize = 1;
+
+/// The size of the receive buffer of each TCP socket not used by the servers.
+///
+/// # Note
+/// The buffer is doubled with the `large-tcp-rx-buffers` feature.
+pub const TCP_RX_BUFFER_SIZE: usize = if cfg!(feature = "large-tcp-rx-buffers") {
+    2048
+} else {
+    1024
+};
+
+/// The size of the transmit buffer of each TCP socket not used by the servers.
+///
+/// # Note
+/// By default, this is sized to ensure that it is sufficient to contain full telemetry messages
+/// for all 8 RF channels. The buffer is halved with the `small-tcp-tx-buffers` feature.
+pub const TCP_TX_BUFFER_SIZE: usize = if cfg!(feature = "small-tcp-tx-buffers") {
+    2048
+} else {
+    4096
+};
+
+/// The size of the receive buffer of each server TCP socket.
+///
+/// # Note
+/// Requests to the servers are short, and the SCPI and JSON-RPC servers process received data in
+/// small chunks.
+pub const SERVER_TCP_RX_BUFFER_SIZE: usize = 512;
+
+/// The size of the transmit buffer of each server TCP socket.
+///
+/// # Note
+/// The servers retain their responses until fully transmitted, so responses need not fit into the
+/// buffer. This is also the smallest supported transmit buffer of the MQTT clients, so that the
+/// clients remain operational, as with the `small-tcp-tx-buffers` feature, should the network
+/// stack hand them a server socket.
+pub const SERVER_TCP_TX_BUFFER_SIZE: usize = 2048;
 
 /// Containers for smoltcp-related network configurations
 struct NetStorage {
     // Note: There is an additional socket set item required for the DHCP and DNS sockets
     // respectively.
-    pub sockets: [smoltcp::iface::SocketStorage<'static>; NUM_TCP_SOCKETS + 2],
-    pub tcp_socket_storage: [TcpSocketStorage; NUM_TCP_SOCKETS],
+    pub sockets: [smoltcp::iface::SocketStorage<'static>; NUM_TCP_SOCKETS + NUM_UDP_SOCKETS + 2],
+    pub tcp_socket_storage: [TcpSocketStorage; NUM_TCP_SOCKETS - NUM_SERVER_TCP_SOCKETS],
+    pub server_socket_storage: [ServerSocketStorage; NUM_SERVER_TCP_SOCKETS],
+    pub udp_socket_storage: [UdpSocketStorage; NUM_UDP_SOCKETS],
     pub dns_storage: [Option<smoltcp::socket::dns::DnsQuery>; 1],
 }
 
 impl NetStorage {
     const fn new() -> Self {
         NetStorage {
-            sockets: [smoltcp::iface::SocketStorage::EMPTY; NUM_TCP_SOCKETS + 2],
-            tcp_socket_storage: [TcpSocketStorage::new(); NUM_TCP_SOCKETS],
+            sockets: [smoltcp::iface::SocketStorage::EMPTY; NUM_TCP_SOCKETS + NUM_UDP_SOCKETS + 2],
+            tcp_socket_storage: [TcpSocketStorage::new(); NUM_TCP_SOCKETS - NUM_SERVER_TCP_SOCKETS],
+            server_socket_storage: [ServerSocketStorage::new(); NUM_SERVER_TCP_SOCKETS],
+            udp_socket_storage: [UdpSocketStorage::new(); NUM_UDP_SOCKETS],
             dns_storage: [None; 1],
         }
     }
@@ -29,18 +96,49 @@ impl NetStorage {
 
 #[derive(Copy, Clone)]
 struct TcpSocketStorage {
-    rx_storage: [u8; 1024],
-
-    // Note that TX storage is set to 4096 to ensure that it is sufficient to contain full
-    // telemetry messages for all 8 RF channels.
-    tx_storage: [u8; 4096],
+    rx_storage: [u8; TCP_RX_BUFFER_SIZE],
+    tx_storage: [u8; TCP_TX_BUFFER_SIZE],
 }
 
 impl TcpSocketStorage {
     const fn new() -> Self {
         Self {
-            tx_storage: [0; 4096],
-            rx_storage: [0; 1024],
+            tx_storage: [0; TCP_TX_BUFFER_SIZE],
+            rx_storage: [0; TCP_RX_BUFFER_SIZE],
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+struct ServerSocketStorage {
+    rx_storage: [u8; SERVER_TCP_RX_BUFFER_SIZE],
+    tx_storage: [u8; SERVER_TCP_TX_BUFFER_SIZE],
+}
+
+impl ServerSocketStorage {
+    const fn new() -> Self {
+        Self {
+            tx_storage: [0; SERVER_TCP_TX_BUFFER_SIZE],
+            rx_storage: [0; SERVER_TCP_RX_BUFFER_SIZE],
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+struct UdpSocketStorage {
+    rx_storage: [u8; 64],
+    tx_storage: [u8; 2048],
+    rx_metadata: [smoltcp::socket::udp::PacketMetadata; 1],
+    tx_metadata: [smoltcp::socket::udp::PacketMetadata; 8],
+}
+
+impl UdpSocketStorage {
+    const fn new() -> Self {
+        Self {
+            rx_storage: [0; 64],
+            tx_storage: [0; 2048],
+            rx_metadata: [smoltcp::socket::udp::PacketMetadata::EMPTY; 1],
+            tx_metadata: [smoltcp::socket::udp::PacketMetadata::EMPTY; 8],
         }
     }
 }
@@ -67,7 +165,7 @@ pub fn setup(
     let ip_address = settings.properties.ip_cidr();
 
     let mut config = smoltcp::iface::Config::new(smoltcp::wire::HardwareAddress::Ethernet(
-        settings.properties.mac,
+        settings.properties.mac_address(),
     ));
     config.random_seed = random_seed;
 
@@ -80,10 +178,18 @@ pub fn setup(
         .unwrap();
 
     let mut sockets = smoltcp::iface::SocketSet::new(&mut net_store.sockets[..]);
-    for storage in net_store.tcp_socket_storage[..].iter_mut() {
+    let tcp_buffers = net_store
+        .tcp_socket_storage
+        .iter_mut()
+        .map(|storage| (&mut storage.rx_storage[..], &mut storage.tx_storage[..]));
+    let server_buffers = net_store
+        .server_socket_storage
+        .iter_mut()
+        .map(|storage| (&mut storage.rx_storage[..], &mut storage.tx_storage[..]));
+    for (rx_storage, tx_storage) in tcp_buffers.chain(server_buffers) {
         let tcp_socket = {
-            let rx_buffer = smoltcp::socket::tcp::SocketBuffer::new(&mut storage.rx_storage[..]);
-            let tx_buffer = smoltcp::socket::tcp::SocketBuffer::new(&mut storage.tx_storage[..]);
+            let rx_buffer = smoltcp::socket::tcp::SocketBuffer::new(rx_storage);
+            let tx_buffer = smoltcp::socket::tcp::SocketBuffer::new(tx_storage);
 
             smoltcp::socket::tcp::Socket::new(rx_buffer, tx_buffer)
         };
@@ -91,8 +197,27 @@ pub fn setup(
         sockets.add(tcp_socket);
     }
 
+    for storage in net_store.udp_socket_storage[..].iter_mut() {
+        let udp_socket = {
+            let rx_buffer = smoltcp::socket::udp::PacketBuffer::new(
+                &mut storage.rx_metadata[..],
+                &mut storage.rx_storage[..],
+            );
+            let tx_buffer = smoltcp::socket::udp::PacketBuffer::new(
+                &mut storage.tx_metadata[..],
+                &mut storage.tx_storage[..],
+            );
+
+            smoltcp::socket::udp::Socket::new(rx_buffer, tx_buffer)
+        };
+
+        sockets.add(udp_socket);
+    }
+
+    // Note: If DHCP is used, the network stack replaces the DNS servers with those of the DHCP
+    // lease. The configured servers are then discarded, even if the lease provides no servers.
     sockets.add(smoltcp::socket::dns::Socket::new(
-        &[],
+        &settings.properties.dns_servers(),
         &mut net_store.dns_storage[..],
     ));
 
@@ -2,6 +2,7 @@
 
 use super::hal;
 
+use core::sync::atomic::{AtomicU32, Ordering};
 use hal::hal::{blocking::delay::DelayUs, digital::v2::OutputPin};
 
 // Booster hardware channels are capable of withstanding up to 1W of reflected RF power. This
@@ -15,24 +16,38 @@ pub const MAX_OUTPUT_POWER_DBM: f32 = 47.0;
 // The voltage supply to the RF transitor bias DAC.
 pub const BIAS_DAC_VCC: f32 = 3.2;
 
+// The maximum P28V drain current permitted while a channel is in diagnostic bring-up. This is well
+// below the nominal operating current so that a damaged module is powered down before it can sink
+// significant power.
+pub const DIAGNOSTIC_MAX_P28V_CURRENT: f32 = 0.050;
+
 #[panic_handler]
 fn panic(info: &core::panic::PanicInfo) -> ! {
-    use core::fmt::Write;
-    use rtt_target::{ChannelMode, UpChannel};
-
     cortex_m::interrupt::disable();
 
     // Shutdown all of the RF channels.
     shutdown_channels();
 
-    if let Some(mut channel) = unsafe { UpChannel::conjure(0) } {
-        channel.set_mode(ChannelMode::BlockIfFull);
-        writeln!(channel, "{}", info).ok();
+    #[cfg(not(feature = "defmt"))]
+    {
+        use core::fmt::Write;
+        use rtt_target::{ChannelMode, UpChannel};
+
+        if let Some(mut channel) = unsafe { UpChannel::conjure(0) } {
+            channel.set_mode(ChannelMode::BlockIfFull);
+            writeln!(channel, "{}", info).ok();
+        }
     }
 
+    #[cfg(feature = "defmt")]
+    defmt::error!("{}", defmt::Display2Format(info));
+
     // Write panic info to RAM.
     panic_persist::report_panic_info(info);
 
+    // Count the panic towards entering safe mode.
+    super::safe_mode::record_panic();
+
     // Reset the device in `release` configuration.
     #[cfg(not(debug_assertions))]
     cortex_m::peripheral::SCB::sys_reset();
@@ -41,15 +56,59 @@ fn panic(info: &core::panic::PanicInfo) -> ! {
     loop {}
 }
 
+/// Enable write access to the backup domain.
+pub fn enable_backup_access() {
+    let rcc = unsafe { &*hal::pac::RCC::ptr() };
+    let pwr = unsafe { &*hal::pac::PWR::ptr() };
+
+    rcc.apb1enr.modify(|_, w| w.pwren().set_bit());
+    pwr.cr.modify(|_, w| w.dbp().set_bit());
+}
+
+/// Read an RTC backup register.
+///
+/// # Note
+/// The backup registers are retained across resets and, if a backup battery is populated, across
+/// power cycles. Registers 0-2 are used by [super::safe_mode] and register 3 by the deployment
+/// lock.
+pub fn read_backup(register: usize) -> u32 {
+    let rtc = unsafe { &*hal::pac::RTC::ptr() };
+    rtc.bkpr[register].read().bits()
+}
+
+/// Write an RTC backup register.
+///
+/// # Note
+/// Write access to the backup domain must have been enabled with [enable_backup_access].
+pub fn write_backup(register: usize, value: u32) {
+    let rtc = unsafe { &*hal::pac::RTC::ptr() };
+    rtc.bkpr[register].write(|w| unsafe { w.bits(value) });
+}
+
+/// The target that the device is reset into.
+#[derive(Copy, Clone, Debug)]
+pub enum ResetTarget {
+    /// The Booster firmware.
+    Firmware,
+
+    /// The internal DFU bootloader.
+    Bootloader,
+}
+
 /// Unconditionally disable and power-off all channels.
 pub fn shutdown_channels() {
+    // Prevent the gate input from re-enabling any outputs.
+    super::gate::disable();
+
     let gpiod = unsafe { &*hal::pac::GPIOD::ptr() };
     let gpiog = unsafe { &*hal::pac::GPIOG::ptr() };
 
     unsafe {
-        // Disable all SIG_ON outputs. Note that the upper 16 bits of this register are the ODR
-        // reset bits.
-        gpiog.bsrr.write(|w| w.bits(0xFF00_0000));
+        // Disable all SIG_ON outputs and deassert the status output. Note that the upper 16 bits of
+        // this register are the ODR reset bits.
+        gpiog
+            .bsrr
+            .write(|w| w.bits(0xFF00_0000 | (1 << (super::status_output::STATUS_PIN + 16))));
 
         // Disable all EN_PWR outputs. Note that the upper 16 bits of this register are the ODR
         // reset bits.
@@ -99,20 +158,97 @@ pub fn i2c_bus_reset(
     delay.delay_us(5);
 }
 
+/// The window watchdog reset flag of the RCC_CSR register.
+const WWDGRSTF: u32 = 1 << 30;
+
+/// The independent watchdog reset flag of the RCC_CSR register.
+const WDGRSTF: u32 = 1 << 29;
+
+/// The software reset flag of the RCC_CSR register.
+const SFTRSTF: u32 = 1 << 28;
+
+/// The power-on reset flag of the RCC_CSR register.
+const PORRSTF: u32 = 1 << 27;
+
+/// The reset pin flag of the RCC_CSR register.
+const PINRSTF: u32 = 1 << 26;
+
+/// The brown-out reset flag of the RCC_CSR register.
+const BORRSTF: u32 = 1 << 25;
+
+/// The cause of the most recent reset.
+#[derive(serde::Serialize, Copy, Clone, Debug, PartialEq)]
+pub enum ResetCause {
+    PowerOn,
+    Watchdog,
+    Panic,
+    Network,
+    Software,
+    Pin,
+    Unknown,
+}
+
+/// The reset flags of the current boot.
+static RESET_FLAGS: AtomicU32 = AtomicU32::new(0);
+
+/// Capture the reset flags of the current boot and clear them in the device.
+///
+/// # Note
+/// The device accumulates reset flags across resets until cleared, so they are captured and
+/// cleared at startup to identify the cause of each individual reset.
+pub fn capture_reset_flags() {
+    let rcc = unsafe { &*hal::pac::RCC::ptr() };
+
+    RESET_FLAGS.store(rcc.csr.read().bits(), Ordering::Relaxed);
+    rcc.csr.modify(|_, w| w.rmvf().set_bit());
+}
+
 /// Check if a watchdog reset has been detected.
 ///
 /// # Returns
 /// True if a watchdog reset has been detected. False otherwise.
 pub fn watchdog_detected() -> bool {
-    let rcc = unsafe { &*hal::pac::RCC::ptr() };
+    RESET_FLAGS.load(Ordering::Relaxed) & WDGRSTF != 0
+}
+
+/// Get the cause of the most recent reset.
+///
+/// # Note
+/// Panics and the network watchdog reset the device via software. Panics are identified by the
+/// persisted panic message and network watchdog resets by a marker in the backup registers.
+///
+/// # Args
+/// * `panicked` - Specified true if a panic message was persisted from the previous boot.
+pub fn reset_cause(panicked: bool) -> ResetCause {
+    let flags = RESET_FLAGS.load(Ordering::Relaxed);
+
+    if panicked {
+        ResetCause::Panic
+    } else if flags & (WDGRSTF | WWDGRSTF) != 0 {
+        ResetCause::Watchdog
+    } else if flags & (PORRSTF | BORRSTF) != 0 {
+        ResetCause::PowerOn
+    } else if super::safe_mode::network_reset_detected() {
+        ResetCause::Network
+    } else if flags & SFTRSTF != 0 {
+        ResetCause::Software
+    } else if flags & PINRSTF != 0 {
+        ResetCause::Pin
+    } else {
+        ResetCause::Unknown
+    }
+}
 
-    rcc.csr.read().wdgrstf().bit_is_set()
+/// Check if the device booted from a power-on reset.
+pub fn power_on_detected() -> bool {
+    RESET_FLAGS.load(Ordering::Relaxed) & PORRSTF != 0
 }
 
 /// Clear all of the reset flags in the device.
 pub fn clear_reset_flags() {
     let rcc = unsafe { &*hal::pac::RCC::ptr() };
 
+    RESET_FLAGS.store(0, Ordering::Relaxed);
     rcc.csr.modify(|_, w| w.rmvf().set_bit());
 }
 
@@ -0,0 +1,65 @@
+//! Booster power-fail detection
+//!
+//! # Design
+//! The programmable voltage detector (PVD) of the MCU monitors the 3.3V supply. When the 12V input
+//! supply is removed, the 3.3V rail collapses shortly after and the PVD raises an interrupt (via
+//! EXTI line 16) once the supply falls below [PVD_LEVEL]. The interrupt handler immediately powers
+//! off all channels and hands pending channel settings to a software task, which flushes them to
+//! EEPROM if the supply holds up long enough.
+//!
+//! Because RF module communication may fail while the supply collapses, channels are powered off
+//! directly via their control pins. If the supply recovers, channels remain off until the device is
+//! reset.
+use super::hal;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// The PVD level selection. Level 7 corresponds to a falling threshold of approximately 2.8V.
+const PVD_LEVEL: u8 = 0b111;
+
+/// The EXTI line connected to the PVD output.
+const PVD_EXTI_LINE: u32 = 1 << 16;
+
+static DETECTED: AtomicBool = AtomicBool::new(false);
+
+/// Enable the programmable voltage detector and its interrupt.
+///
+/// # Note
+/// The interrupt is generated when the supply voltage falls below the PVD threshold.
+pub fn enable() {
+    let rcc = unsafe { &*hal::pac::RCC::ptr() };
+    let pwr = unsafe { &*hal::pac::PWR::ptr() };
+    let exti = unsafe { &*hal::pac::EXTI::ptr() };
+
+    rcc.apb1enr.modify(|_, w| w.pwren().set_bit());
+    pwr.cr
+        .modify(|_, w| unsafe { w.pls().bits(PVD_LEVEL) }.pvde().set_bit());
+
+    // The PVD output rises when the supply falls below the threshold.
+    unsafe {
+        exti.rtsr.modify(|r, w| w.bits(r.bits() | PVD_EXTI_LINE));
+        exti.imr.modify(|r, w| w.bits(r.bits() | PVD_EXTI_LINE));
+        exti.pr.write(|w| w.bits(PVD_EXTI_LINE));
+    }
+}
+
+/// Acknowledge a power-fail interrupt and record that a power failure was detected.
+pub fn acknowledge() {
+    let exti = unsafe { &*hal::pac::EXTI::ptr() };
+    exti.pr.write(|w| unsafe { w.bits(PVD_EXTI_LINE) });
+
+    DETECTED.store(true, Ordering::Relaxed);
+}
+
+/// Check if a power failure has been detected since startup.
+pub fn is_detected() -> bool {
+    DETECTED.load(Ordering::Relaxed)
+}
+
+/// Check if the supply voltage is currently above the PVD threshold.
+///
+/// # Note
+/// The PVD must already be enabled.
+pub fn supply_valid() -> bool {
+    let pwr = unsafe { &*hal::pac::PWR::ptr() };
+    pwr.csr.read().pvdo().bit_is_clear()
+}
@@ -7,12 +7,21 @@ use max6642::Max6642;
 use mcp3221::Mcp3221;
 use microchip_24aa02e48::Microchip24AA02E48;
 use minimq::embedded_time::{duration::Extensions, Clock, Instant};
+use persistence::{DeferredSave, Save};
 
-use super::{delay::AsmDelay, platform, I2cBusManager, I2cProxy, SystemTimer};
+use super::{
+    delay::AsmDelay,
+    detectors,
+    filter::{ChannelFilters, MeasurementFilter},
+    gate, platform, Channel, I2cBusManager, I2cProxy, SystemTimer,
+};
 use crate::{
+    error::Device,
     settings::{
-        channel_settings::ChannelSettings, channel_settings::ChannelState, BoosterChannelSettings,
+        channel_settings::{ChannelLabel, ChannelSettings, ChannelState, ValidationError},
+        BoosterChannelSettings,
     },
+    statistics::{self, Event},
     Error,
 };
 use stm32f4xx_hal::{
@@ -29,12 +38,206 @@ struct SupplyMeasurements {
     i_p28v0ch: f32,
 }
 
+/// A structure representing all measurements of the devices on an RF module.
+struct ModuleMeasurements {
+    supply: SupplyMeasurements,
+
+    /// The channel temperature in celsius.
+    temperature: f32,
+
+    /// The input power in dBm.
+    input_power: f32,
+}
+
+/// Raw detector and monitor voltages of a channel before calibration transforms are applied.
+#[derive(serde::Serialize)]
+pub struct RawVoltages {
+    /// The input power detector voltage sampled by the MCP3221.
+    pub input_detector: f32,
+
+    /// The output power detector voltage sampled by ADC3.
+    pub output_detector: f32,
+
+    /// The reflected power detector voltage sampled by ADC3.
+    pub reflected_detector: f32,
+
+    /// The voltages of all ADS7924 power monitor channels.
+    pub power_monitor: [f32; 4],
+}
+
+/// The number of consecutive failed or implausible RF module measurements after which a channel is
+/// quarantined.
+const QUARANTINE_ERROR_LIMIT: u8 = 3;
+
+/// The resolution of the thermal derating of the output interlock threshold in dB.
+const DERATING_STEP_DB: f32 = 0.25;
+
+/// The temperature drop in celsius required before thermal derating is relaxed, so that
+/// temperature noise does not cause repeated interlock threshold updates.
+const DERATING_HYSTERESIS: f32 = 1.0;
+
+/// The number of attempts made to write and verify the bias DAC output.
+const BIAS_WRITE_ATTEMPTS: usize = 3;
+
+/// The range of plausible channel temperatures in celsius.
+const PLAUSIBLE_TEMPERATURE: core::ops::RangeInclusive<f32> = -40.0..=150.0;
+
 /// Represents the possible channel fault conditions.
-#[derive(Debug, Copy, Clone, serde::Serialize)]
+#[derive(Debug, Copy, Clone, PartialEq, serde::Serialize)]
 pub enum ChannelFault {
     OverTemperature,
     UnderTemperature,
     SupplyAlert,
+    OverCurrent,
+
+    /// Communication with a device of the RF module failed.
+    BusError,
+
+    /// The bias DAC output could not be verified after being written.
+    BiasFault,
+}
+
+/// Represents the temperature measurement used for channel thermal protection.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Copy, Clone, PartialEq)]
+pub enum TemperatureSource {
+    /// The remote diode of the MAX6642 located at the RF amplifier.
+    Remote,
+
+    /// The die temperature of the MAX6642 sensor itself, which is located on the RF module away
+    /// from the RF amplifier.
+    SensorDie,
+
+    /// The maximum of the remote diode and sensor die temperatures. If the remote diode has failed,
+    /// the sensor die temperature is used.
+    Maximum,
+}
+
+/// The sample time of power detector conversions on ADC3.
+///
+/// # Note
+/// Longer sample times reduce the noise of power detector measurements at the cost of longer
+/// conversions.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Copy, Clone, PartialEq)]
+pub enum AdcSampleTime {
+    Cycles3,
+    Cycles15,
+    Cycles28,
+    Cycles56,
+    Cycles84,
+    Cycles112,
+    Cycles144,
+    Cycles480,
+}
+
+impl From<AdcSampleTime> for SampleTime {
+    fn from(time: AdcSampleTime) -> SampleTime {
+        match time {
+            AdcSampleTime::Cycles3 => SampleTime::Cycles_3,
+            AdcSampleTime::Cycles15 => SampleTime::Cycles_15,
+            AdcSampleTime::Cycles28 => SampleTime::Cycles_28,
+            AdcSampleTime::Cycles56 => SampleTime::Cycles_56,
+            AdcSampleTime::Cycles84 => SampleTime::Cycles_84,
+            AdcSampleTime::Cycles112 => SampleTime::Cycles_112,
+            AdcSampleTime::Cycles144 => SampleTime::Cycles_144,
+            AdcSampleTime::Cycles480 => SampleTime::Cycles_480,
+        }
+    }
+}
+
+/// Thresholds of channel measurements above which warnings are raised.
+///
+/// # Note
+/// Warnings are informational and do not affect the channel output, unlike interlocks and faults.
+#[derive(serde::Serialize, serde::Deserialize, miniconf::Tree, Debug, Copy, Clone, PartialEq)]
+pub struct WarningThresholds {
+    /// The channel temperature in celsius.
+    pub temperature: f32,
+
+    /// The P28V rail current in amperes.
+    pub p28v_current: f32,
+
+    /// The reflected power in dBm.
+    pub reflected_power: f32,
+
+    /// The input power floor in dBm. If the input power of an enabled channel is below the floor,
+    /// the channel is likely amplifying noise because there is no RF drive.
+    pub input_power_floor: f32,
+}
+
+impl Default for WarningThresholds {
+    fn default() -> Self {
+        Self {
+            // The channel is faulted at 60 C.
+            temperature: 55.0,
+            p28v_current: 3.0,
+            // The reflected power interlock trips at 30 dBm.
+            reflected_power: 27.0,
+            input_power_floor: -10.0,
+        }
+    }
+}
+
+/// A thermal derating curve of the output interlock threshold.
+///
+/// # Note
+/// Above the start temperature, the output interlock threshold is reduced linearly as the channel
+/// temperature rises. The channel is still faulted if it exceeds 60 C.
+#[derive(serde::Serialize, serde::Deserialize, miniconf::Tree, Debug, Copy, Clone, PartialEq)]
+pub struct ThermalDerating {
+    /// Specified true to derate the output interlock threshold.
+    pub enabled: bool,
+
+    /// The channel temperature in celsius above which the threshold is reduced.
+    pub start_temperature: f32,
+
+    /// The reduction of the output interlock threshold in dB per degree celsius.
+    pub slope: f32,
+}
+
+impl Default for ThermalDerating {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            start_temperature: 45.0,
+            slope: 1.0,
+        }
+    }
+}
+
+impl ThermalDerating {
+    /// Get the reduction of the output interlock threshold.
+    ///
+    /// # Args
+    /// * `temperature` - The channel temperature in celsius.
+    ///
+    /// # Returns
+    /// The reduction in dB, rounded down to a multiple of [DERATING_STEP_DB].
+    pub fn reduction(&self, temperature: f32) -> f32 {
+        if !self.enabled || temperature <= self.start_temperature {
+            return 0.0;
+        }
+
+        let reduction = (temperature - self.start_temperature) * self.slope;
+        libm::floorf(reduction / DERATING_STEP_DB) * DERATING_STEP_DB
+    }
+}
+
+/// The channel measurements that have exceeded their warning thresholds.
+#[derive(serde::Serialize, Default, Debug, Copy, Clone, PartialEq)]
+pub struct Warnings {
+    pub temperature: bool,
+    pub p28v_current: bool,
+    pub reflected_power: bool,
+
+    /// The channel is enabled, but the input power is below the configured floor.
+    pub no_input: bool,
+}
+
+impl Warnings {
+    /// Check if any warning is raised.
+    pub fn any(&self) -> bool {
+        self.temperature || self.p28v_current || self.reflected_power || self.no_input
+    }
 }
 
 /// Represents the three power interlocks present on the device.
@@ -43,6 +246,9 @@ pub enum Interlock {
     Input,
     Output,
     Reflected,
+
+    /// The interlock of a channel that this channel depends on tripped.
+    Chained,
 }
 
 /// A succinct representation of RF channel state for front panel status indication.
@@ -55,7 +261,8 @@ pub struct PowerStatus {
     /// The RF output switch is disabled. Yellow LED
     pub rf_disabled: bool,
 
-    /// The channel is in a force-disabled state due to a latched error. Red LED.
+    /// The channel is in a force-disabled state due to a latched error or a local lockout. Red
+    /// LED.
     pub blocked: bool,
 }
 
@@ -211,9 +418,6 @@ pub struct ChannelPins {
     output_overdrive: hal::gpio::EPin<Input>,
 
     signal_on: hal::gpio::EPin<Output>,
-
-    output_power: AdcPin,
-    reflected_power: AdcPin,
 }
 
 impl ChannelPins {
@@ -226,16 +430,12 @@ impl ChannelPins {
     /// * `reflected_overdrive` - An input pin that indicates an input overdrive.
     /// * `output_overdrive` - An input pin that indicates an output overdrive.
     /// * `signal_on` - An output pin that is set high to enable output signal amplification.
-    /// * `output_power` - The pin to use for measuring transmitted power.
-    /// * `reflected_power` - The pin to use for measuring reflected power.
     pub fn new(
         enable_power: hal::gpio::EPin<Output>,
         alert: hal::gpio::EPin<Input>,
         reflected_overdrive: hal::gpio::EPin<Input>,
         output_overdrive: hal::gpio::EPin<Input>,
         signal_on: hal::gpio::EPin<Output>,
-        output_power: AdcPin,
-        reflected_power: AdcPin,
     ) -> Self {
         let mut pins = Self {
             enable_power,
@@ -243,8 +443,6 @@ impl ChannelPins {
             reflected_overdrive,
             output_overdrive,
             signal_on,
-            output_power,
-            reflected_power,
         };
 
         // Power down channel.
@@ -255,28 +453,60 @@ impl ChannelPins {
 }
 
 /// Contains channel status information in SI base units.
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, Clone)]
 pub struct ChannelStatus {
-    reflected_overdrive: bool,
-    output_overdrive: bool,
-    alert: bool,
-    temperature: f32,
-    p28v_current: f32,
-    p5v_current: f32,
-    p5v_voltage: f32,
-    input_power: f32,
-    reflected_power: f32,
-    output_power: f32,
-    state: sm::States,
+    pub reflected_overdrive: bool,
+    pub output_overdrive: bool,
+    pub alert: bool,
+    pub temperature: f32,
+    pub p28v_current: f32,
+    pub p5v_current: f32,
+    pub p5v_voltage: f32,
+    pub input_power: f32,
+    pub reflected_power: f32,
+    pub output_power: f32,
+    pub state: sm::States,
+    pub label: ChannelLabel,
+    pub unsaved: bool,
+    pub warnings: Warnings,
+
+    /// The thermal derating of the output interlock threshold in dB.
+    pub interlock_derating: f32,
+
+    /// The UNIX time at which the channel was measured, if the clock has been set.
+    pub timestamp: Option<u32>,
 }
 
 /// Represents a means of interacting with an RF output channel.
 pub struct RfChannel {
+    channel: Channel,
     devices: Devices,
     pins: ChannelPins,
+    eui48: [u8; 6],
     settings: BoosterChannelSettings,
     clock: SystemTimer,
     delay: AsmDelay,
+    locked_out: bool,
+    interlock_trips: u32,
+    temperature_source: TemperatureSource,
+    warning_thresholds: WarningThresholds,
+    derating: ThermalDerating,
+    // The channels that are tripped when the interlock of this channel trips.
+    dependents: u8,
+    filter: MeasurementFilter,
+    filters: ChannelFilters,
+    // The reduction of the output interlock threshold currently applied in dB.
+    interlock_derating: f32,
+    // The warnings that were raised when last checked.
+    active_warnings: Warnings,
+    // The number of consecutive failed or implausible RF module measurements.
+    bus_errors: u8,
+    quarantined: bool,
+    // True if a bias DAC write could not be verified.
+    bias_fault: bool,
+    // True if the remote temperature diode was last reported as faulty.
+    diode_fault: bool,
+    saves: DeferredSave<Instant<SystemTimer>>,
 }
 
 impl RfChannel {
@@ -286,6 +516,7 @@ impl RfChannel {
     /// This function attempts to detect an installed RF module.
     ///
     /// # Args
+    /// * `channel` - The channel index.
     /// * `manager` - The manager that controls the shared I2C bus used for RF module devices.
     /// * `control_pins` - The control and status pins associated with the channel.
     /// * `delay` - A means of delaying during setup.
@@ -293,19 +524,42 @@ impl RfChannel {
     /// # Returns
     /// An option containing an RfChannel if a channel was discovered on the bus. None otherwise.
     pub fn new(
+        channel: Channel,
         manager: &'static I2cBusManager,
         pins: ChannelPins,
         clock: SystemTimer,
         mut delay: AsmDelay,
     ) -> Option<Self> {
         // Attempt to instantiate the I2C devices on the channel.
-        Devices::new(manager, &mut delay).map(|(devices, eeprom)| {
+        Devices::new(manager, &mut delay).map(|(devices, mut eeprom)| {
+            let mut eui48 = [0; 6];
+            eeprom
+                .read_eui48(&mut eui48)
+                .unwrap_or_else(|_| log::warn!("Failed to read RF module EUI-48"));
+
             let mut channel = Self {
+                channel,
                 devices,
                 pins,
+                eui48,
                 settings: BoosterChannelSettings::new(eeprom),
                 clock,
                 delay,
+                locked_out: false,
+                interlock_trips: 0,
+                temperature_source: TemperatureSource::Remote,
+                warning_thresholds: WarningThresholds::default(),
+                derating: ThermalDerating::default(),
+                dependents: 0,
+                filter: MeasurementFilter::default(),
+                filters: ChannelFilters::default(),
+                interlock_derating: 0.0,
+                active_warnings: Warnings::default(),
+                bus_errors: 0,
+                quarantined: false,
+                bias_fault: false,
+                diode_fault: false,
+                saves: DeferredSave::default(),
             };
 
             channel.apply_output_interlock_threshold().unwrap();
@@ -321,8 +575,102 @@ impl RfChannel {
     }
 
     /// Save the current channel configuration.
+    ///
+    /// # Note
+    /// To coalesce repeated saves into a single EEPROM write, the configuration is written when
+    /// saves are next processed.
     pub fn save_configuration(&mut self) {
-        self.settings.save()
+        self.saves.request();
+    }
+
+    /// Get the reason that the channel configuration stored in EEPROM was rejected, if any.
+    pub fn settings_error(&self) -> Option<ValidationError> {
+        self.settings.error()
+    }
+
+    /// Check if the RF module EEPROM contained a valid configuration at startup.
+    pub fn eeprom_valid(&self) -> bool {
+        self.settings.eeprom_valid()
+    }
+
+    /// Get the CRC32 of the channel configuration stored in EEPROM, if it is valid.
+    pub fn eeprom_crc32(&self) -> Option<u32> {
+        self.settings.saved_crc32()
+    }
+
+    /// Get the number of times the channel configuration has been written to EEPROM.
+    pub fn eeprom_writes(&self) -> u32 {
+        self.settings.writes()
+    }
+
+    /// Check if the channel settings have been modified since they were last saved.
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.settings.is_dirty()
+    }
+
+    /// Process requested saves and save modified channel settings once they have not changed for a
+    /// quiet period.
+    ///
+    /// # Args
+    /// * `quiet_period_secs` - The number of seconds that settings must remain unchanged before
+    ///   they are saved. If zero, settings are never saved automatically.
+    pub fn process_saves(&mut self, quiet_period_secs: u32) {
+        let quiet_period_end =
+            (quiet_period_secs != 0).then_some(|modified: Instant<SystemTimer>| {
+                modified.checked_add(quiet_period_secs.seconds())
+            });
+
+        let now = self.clock.try_now().unwrap();
+        match self
+            .saves
+            .poll(self.settings.is_dirty(), now, quiet_period_end)
+        {
+            Some(Save::Requested) => self.settings.save(),
+            Some(Save::Automatic) => {
+                log::info!("Automatically saving modified channel settings");
+                self.settings.save();
+            }
+            None => {}
+        }
+    }
+
+    /// Immediately save channel settings that have a pending save.
+    ///
+    /// # Note
+    /// This is intended for use when power is failing or the device is about to reset. Requested
+    /// saves and, if automatic saving is enabled, modified settings awaiting their quiet period are
+    /// written without delay.
+    pub fn flush_saves(&mut self) {
+        if self.saves.flush() {
+            self.settings.save();
+        }
+    }
+
+    /// Restore the default channel configuration in EEPROM.
+    ///
+    /// # Note
+    /// The restored configuration is applied once the device is reset.
+    ///
+    /// # Args
+    /// * `preserve_calibration` - Specified true if the power detector transforms should be kept.
+    pub fn factory_reset(&mut self, preserve_calibration: bool) {
+        self.settings.factory_reset(preserve_calibration)
+    }
+
+    /// Set the RF output enable switch, subject to the hardware gate input.
+    ///
+    /// # Args
+    /// * `enabled` - Specified true to enable the RF output.
+    fn set_rf_switch(&mut self, enabled: bool) {
+        // The gate input interrupt also updates the switch, so it must not preempt the update.
+        cortex_m::interrupt::free(|_| {
+            gate::request(self.channel, enabled);
+            if enabled && gate::is_passing(self.channel) {
+                self.pins.signal_on.set_high();
+            } else {
+                self.pins.signal_on.set_low();
+            }
+        })
     }
 
     /// Check if the channel RF output is enabled.
@@ -340,44 +688,143 @@ impl RfChannel {
     /// # Args
     /// * `power` - The dBm interlock threshold to configure for reflected power.
     fn set_reflected_interlock_threshold(&mut self, power: f32) -> Result<f32, Error> {
-        self.devices
+        let channel = self.channel;
+        let voltage = self
+            .devices
             .interlock_thresholds_dac
             .set_voltage(
                 self.settings
                     .settings()
                     .reflected_power_transform
-                    .invert(power),
+                    .apply_inverse(power),
                 ad5627::Dac::A,
             )
             .map_err(|e| match e {
                 ad5627::Error::Range => Error::Bounds,
-                ad5627::Error::I2c(_) => Error::Interface,
-            })
+                ad5627::Error::I2c(_) => {
+                    Error::interface(channel, Device::Ad5627, "set reflected interlock threshold")
+                }
+            })?;
+
+        detectors::set_limit(self.channel, Interlock::Reflected, voltage);
+        Ok(voltage)
     }
 
     fn apply_output_interlock_threshold(&mut self) -> Result<f32, Error> {
+        let channel = self.channel;
         let settings = self.settings.settings();
 
-        self.devices
+        // The derated threshold is bounded by the lowest threshold that a channel may be enabled
+        // with.
+        let threshold = (settings.output_interlock_threshold - self.interlock_derating)
+            .max(settings.output_power_transform.map(0.100));
+
+        let voltage = self
+            .devices
             .interlock_thresholds_dac
             .set_voltage(
-                settings
-                    .output_power_transform
-                    .invert(settings.output_interlock_threshold),
+                settings.output_power_transform.apply_inverse(threshold),
                 ad5627::Dac::B,
             )
             .map_err(|e| match e {
                 ad5627::Error::Range => Error::Bounds,
-                ad5627::Error::I2c(_) => Error::Interface,
+                ad5627::Error::I2c(_) => {
+                    Error::interface(channel, Device::Ad5627, "set output interlock threshold")
+                }
+            })?;
+
+        detectors::set_limit(self.channel, Interlock::Output, voltage);
+        Ok(voltage)
+    }
+
+    /// Read back an interlock threshold from the interlock threshold DAC.
+    ///
+    /// # Args
+    /// * `dac` - The DAC output of the interlock.
+    fn read_interlock_voltage(&mut self, dac: ad5627::Dac) -> Result<f32, Error> {
+        let channel = self.channel;
+        self.devices
+            .interlock_thresholds_dac
+            .get_voltage(dac)
+            .map_err(|e| match e {
+                ad5627::Error::Range => Error::Bounds,
+                ad5627::Error::I2c(_) => {
+                    Error::interface(channel, Device::Ad5627, "read interlock threshold")
+                }
             })
     }
 
+    /// Get the output interlock threshold programmed into the interlock threshold DAC.
+    ///
+    /// # Note
+    /// The threshold is read back from hardware, so it reflects the actual interlock level even if
+    /// it deviates from the channel settings, e.g. after a brown-out of the RF module.
+    ///
+    /// # Returns
+    /// The output interlock threshold in dBm.
+    pub fn get_output_interlock_threshold(&mut self) -> Result<f32, Error> {
+        let voltage = self.read_interlock_voltage(ad5627::Dac::B)?;
+        Ok(self.settings().output_power_transform.map(voltage))
+    }
+
+    /// Get the reflected interlock threshold programmed into the interlock threshold DAC.
+    ///
+    /// # Note
+    /// The threshold is read back from hardware, so it reflects the actual interlock level even if
+    /// it deviates from the channel settings, e.g. after a brown-out of the RF module.
+    ///
+    /// # Returns
+    /// The reflected interlock threshold in dBm.
+    pub fn get_reflected_interlock_threshold(&mut self) -> Result<f32, Error> {
+        let voltage = self.read_interlock_voltage(ad5627::Dac::A)?;
+        Ok(self.settings().reflected_power_transform.map(voltage))
+    }
+
+    /// Update the thermal derating of the output interlock threshold.
+    ///
+    /// # Args
+    /// * `temperature` - The channel temperature in celsius.
+    fn apply_derating(&mut self, temperature: f32) -> Result<(), Error> {
+        let mut derating = self.derating.reduction(temperature);
+        if derating < self.interlock_derating {
+            derating = self
+                .derating
+                .reduction(temperature + DERATING_HYSTERESIS)
+                .min(self.interlock_derating);
+        }
+
+        if derating == self.interlock_derating {
+            return Ok(());
+        }
+
+        if self.interlock_derating == 0.0 {
+            log::info!("Derating output interlock threshold at {} C", temperature);
+        }
+
+        self.interlock_derating = derating;
+        self.apply_output_interlock_threshold()?;
+        Ok(())
+    }
+
     fn check_faults(&mut self) -> Option<ChannelFault> {
-        let temperature = self.get_temperature();
-        if temperature > 60.0 {
+        let (Ok(temperature), Ok(over_current)) =
+            (self.get_temperature(), self.diagnostic_current_exceeded())
+        else {
+            return Some(ChannelFault::BusError);
+        };
+
+        if self.apply_derating(temperature).is_err() {
+            return Some(ChannelFault::BusError);
+        }
+
+        if self.bias_fault {
+            Some(ChannelFault::BiasFault)
+        } else if temperature > 60.0 {
             Some(ChannelFault::OverTemperature)
         } else if temperature < 5.0 {
             Some(ChannelFault::UnderTemperature)
+        } else if over_current {
+            Some(ChannelFault::OverCurrent)
         } else if self.pins.alert.is_low() {
             Some(ChannelFault::SupplyAlert)
         } else {
@@ -385,10 +832,43 @@ impl RfChannel {
         }
     }
 
-    fn get_overdrive_source(&mut self) -> Option<Interlock> {
+    /// Check if the drain current limit of diagnostic bring-up has been exceeded.
+    ///
+    /// # Note
+    /// The P28V current is polled from the power monitor, since the ADS7924 alarm is not reliable.
+    /// Refer to https://github.com/quartiq/booster/issues/130 for more information.
+    fn diagnostic_current_exceeded(&mut self) -> Result<bool, Error> {
+        if self.settings.settings().state != ChannelState::Diagnostic {
+            return Ok(false);
+        }
+
+        Ok(self.get_p28v_current()? > platform::DIAGNOSTIC_MAX_P28V_CURRENT)
+    }
+
+    /// Configure the channel for (or release it from) diagnostic bring-up.
+    ///
+    /// # Note
+    /// Diagnostic bring-up begins with the bias DAC in pinch-off. When released, the configured
+    /// bias voltage is applied again.
+    ///
+    /// # Args
+    /// * `diagnostic` - Specified true to configure the channel for diagnostic bring-up.
+    fn apply_diagnostic_mode(&mut self, diagnostic: bool) -> Result<(), Error> {
+        if diagnostic {
+            // Begin with minimal bias so the module draws no significant current until explicitly
+            // tuned.
+            self.set_bias_dac(platform::BIAS_DAC_VCC)?;
+        } else {
+            self.apply_bias()?;
+        }
+
+        Ok(())
+    }
+
+    fn get_overdrive_source(&mut self) -> Result<Option<Interlock>, Error> {
         // The schematic indicates the maximum input power is 25dBm. We'll use 20dBm to provide
         // a safety margin.
-        if self.get_input_power() > 20.0 {
+        let source = if self.get_input_power()? > 20.0 {
             Some(Interlock::Input)
         } else if self.pins.output_overdrive.is_high() {
             Some(Interlock::Output)
@@ -396,7 +876,9 @@ impl RfChannel {
             Some(Interlock::Reflected)
         } else {
             None
-        }
+        };
+
+        Ok(source)
     }
 
     /// Apply channel settings to the RF channel.
@@ -416,22 +898,26 @@ impl RfChannel {
         }
 
         let bias_changed = new_settings.bias_voltage != settings.bias_voltage;
+        let diagnostic = new_settings.state == ChannelState::Diagnostic;
+        let diagnostic_changed = diagnostic != (settings.state == ChannelState::Diagnostic);
+        // The interlocks are updated if the DAC voltage of their threshold changes.
         let output_interlock_updated = settings
             .output_power_transform
-            .map(settings.output_interlock_threshold)
+            .apply_inverse(settings.output_interlock_threshold)
             != new_settings
                 .output_power_transform
-                .map(new_settings.output_interlock_threshold);
+                .apply_inverse(new_settings.output_interlock_threshold);
         let reflected_interlock_updated = settings
             .reflected_power_transform
-            .map(platform::MAXIMUM_REFLECTED_POWER_DBM)
+            .apply_inverse(platform::MAXIMUM_REFLECTED_POWER_DBM)
             != new_settings
                 .reflected_power_transform
-                .map(platform::MAXIMUM_REFLECTED_POWER_DBM);
+                .apply_inverse(platform::MAXIMUM_REFLECTED_POWER_DBM);
 
         // Copy transforms before applying the interlock threshold, since the interlock DAC level
         // is calculated from the output interlock transform.
         *settings = *new_settings;
+        self.saves.modify(self.clock.try_now().unwrap());
 
         // Only update the interlock and bias DACs if they've actually changed.
         if output_interlock_updated {
@@ -440,39 +926,210 @@ impl RfChannel {
         if reflected_interlock_updated {
             self.set_reflected_interlock_threshold(platform::MAXIMUM_REFLECTED_POWER_DBM)?;
         }
-        if bias_changed {
+        if diagnostic_changed {
+            self.apply_diagnostic_mode(diagnostic)?;
+        }
+
+        // Entering or leaving diagnostic bring-up already configures the bias DAC.
+        if bias_changed && !diagnostic_changed {
             self.apply_bias()?;
         }
 
         Ok(())
     }
 
+    /// Configure the temperature measurement used for thermal protection and telemetry.
+    ///
+    /// # Args
+    /// * `source` - The temperature source to use.
+    pub fn set_temperature_source(&mut self, source: TemperatureSource) {
+        self.temperature_source = source;
+    }
+
+    /// Configure the thermal derating of the output interlock threshold.
+    ///
+    /// # Note
+    /// The derating takes effect when the channel temperature is next measured.
+    ///
+    /// # Args
+    /// * `derating` - The thermal derating curve to use.
+    pub fn set_thermal_derating(&mut self, derating: ThermalDerating) {
+        self.derating = derating;
+    }
+
+    /// Configure the time constants of the measurement filters.
+    ///
+    /// # Args
+    /// * `filter` - The filter time constants to use.
+    pub fn set_measurement_filter(&mut self, filter: MeasurementFilter) {
+        self.filter = filter;
+    }
+
+    /// Configure the channels that are tripped when the interlock of this channel trips.
+    ///
+    /// # Args
+    /// * `dependents` - A bit mask of the dependent channels. Bit 0 corresponds to channel 0.
+    pub fn set_dependents(&mut self, dependents: u8) {
+        self.dependents = dependents;
+    }
+
+    /// Get the channels that are tripped when the interlock of this channel trips.
+    pub fn dependents(&self) -> u8 {
+        self.dependents
+    }
+
+    /// Configure the thresholds of channel measurements above which warnings are raised.
+    ///
+    /// # Args
+    /// * `thresholds` - The warning thresholds to use.
+    pub fn set_warning_thresholds(&mut self, thresholds: WarningThresholds) {
+        self.warning_thresholds = thresholds;
+    }
+
+    /// Update the active warnings of the channel.
+    ///
+    /// # Note
+    /// Each warning is reported once when raised. It is reported again only after the measurement
+    /// has returned below its threshold.
+    ///
+    /// # Args
+    /// * `warnings` - The currently raised warnings.
+    ///
+    /// # Returns
+    /// The warnings that were newly raised since the previous update, if any.
+    pub fn update_warnings(&mut self, warnings: Warnings) -> Option<Warnings> {
+        let raised = Warnings {
+            temperature: warnings.temperature && !self.active_warnings.temperature,
+            p28v_current: warnings.p28v_current && !self.active_warnings.p28v_current,
+            reflected_power: warnings.reflected_power && !self.active_warnings.reflected_power,
+            no_input: warnings.no_input && !self.active_warnings.no_input,
+        };
+
+        self.active_warnings = warnings;
+
+        raised.any().then_some(raised)
+    }
+
     /// Get the temperature of the channel in celsius.
-    fn get_temperature(&mut self) -> f32 {
-        self.devices
-            .temperature_monitor
-            .get_remote_temperature()
-            .unwrap()
+    fn get_temperature(&mut self) -> Result<f32, Error> {
+        let channel = self.channel;
+        let monitor = &mut self.devices.temperature_monitor;
+        let temperature = match self.temperature_source {
+            TemperatureSource::Remote => monitor.get_remote_temperature(),
+            TemperatureSource::SensorDie => monitor.get_local_temperature(),
+            TemperatureSource::Maximum => {
+                let die = monitor
+                    .get_local_temperature()
+                    .map_err(|_| Error::interface(channel, Device::Max6642, "read temperature"))?;
+                let remote = monitor.get_remote_temperature();
+
+                // Only log changes of the diode fault, since the temperature is read periodically.
+                let diode_fault = matches!(remote, Err(max6642::Error::DiodeFault));
+                if diode_fault != self.diode_fault {
+                    if diode_fault {
+                        log::warn!("Remote temperature diode fault");
+                    } else {
+                        log::info!("Remote temperature diode fault cleared");
+                    }
+                    self.diode_fault = diode_fault;
+                }
+
+                match remote {
+                    Err(max6642::Error::DiodeFault) => Ok(die),
+                    remote => remote.map(|remote| remote.max(die)),
+                }
+            }
+        };
+
+        let temperature = temperature
+            .map_err(|_| Error::interface(channel, Device::Max6642, "read temperature"))?;
+
+        if !PLAUSIBLE_TEMPERATURE.contains(&temperature) {
+            return Err(Error::Invalid);
+        }
+
+        let now = self.clock.try_now().unwrap();
+        Ok(self
+            .filters
+            .temperature
+            .update(temperature, now, self.filter.temperature))
     }
 
     fn apply_bias(&mut self) -> Result<f32, Error> {
         // The bias voltage is the inverse of the DAC output voltage.
         let bias_voltage = -1.0 * self.settings().bias_voltage;
+        self.set_bias_dac(bias_voltage)
+    }
+
+    /// Write the bias DAC output and verify it by reading it back.
+    ///
+    /// # Note
+    /// The write is retried if verification fails. If the output still cannot be verified, the
+    /// channel is flagged with a bias fault and is blocked on the next update. As the RF amplifier
+    /// may then be biased arbitrarily, the RF output is disabled and the channel supplies are
+    /// powered down immediately.
+    ///
+    /// # Args
+    /// * `voltage` - The DAC output voltage. A voltage of [platform::BIAS_DAC_VCC] places the RF
+    ///   amplifier into pinch-off.
+    ///
+    /// # Returns
+    /// The nominal DAC output voltage.
+    fn set_bias_dac(&mut self, voltage: f32) -> Result<f32, Error> {
+        let channel = self.channel;
+        let mut error = Error::interface(channel, Device::Dac7571, "write bias");
+
+        for _ in 0..BIAS_WRITE_ATTEMPTS {
+            let nominal = match self.devices.bias_dac.set_voltage(voltage) {
+                Err(dac7571::Error::Bounds) => return Err(Error::Bounds),
+                Err(_) => {
+                    error = Error::interface(channel, Device::Dac7571, "write bias");
+                    statistics::record(Event::I2cRetry);
+                    continue;
+                }
+                Ok(nominal) => nominal,
+            };
 
-        match self.devices.bias_dac.set_voltage(bias_voltage) {
-            Err(dac7571::Error::Bounds) => Err(Error::Bounds),
-            Err(_) => panic!("Failed to set DAC bias voltage"),
-            Ok(u) => Ok(u),
+            // The readback has the same resolution as the write, so the voltages match exactly.
+            error = match self.devices.bias_dac.get_voltage() {
+                Ok(readback) if readback == nominal => return Ok(nominal),
+                Ok(readback) => Error::BiasMismatch {
+                    channel,
+                    nominal,
+                    readback,
+                },
+                Err(_) => Error::interface(channel, Device::Dac7571, "read back bias"),
+            };
+
+            statistics::record(Event::I2cRetry);
         }
+
+        log::error!("Failed to verify bias DAC output of {} V", voltage);
+        self.bias_fault = true;
+        self.set_rf_switch(false);
+        self.pins.enable_power.set_low();
+
+        Err(error)
+    }
+
+    /// Place the RF amplifier into pinch-off.
+    ///
+    /// # Note
+    /// A failure to verify pinch-off is flagged as a bias fault.
+    fn pinch_off(&mut self) {
+        self.set_bias_dac(platform::BIAS_DAC_VCC).ok();
     }
 
     /// Get current power supply measurements from the channel.
     ///
     /// # Returns
     /// The most recent power supply measurements of the channel.
-    fn get_supply_measurements(&mut self) -> SupplyMeasurements {
+    fn get_supply_measurements(&mut self) -> Result<SupplyMeasurements, Error> {
         // Read the cached (scanned) ADC measurements from the monitor.
-        let voltages = self.devices.power_monitor.get_voltages().unwrap();
+        let voltages =
+            self.devices.power_monitor.get_voltages().map_err(|_| {
+                Error::interface(self.channel, Device::Ads7924, "read supply voltages")
+            })?;
 
         // The P5V0 rail goes through a resistor divider of 15K -> 10K. This corresponds with a 2.5x
         // reduction in measured voltage.
@@ -510,69 +1167,136 @@ impl RfChannel {
         // Isns = (Vout * Rin) / Rsns / Rout
         let i_p5v0ch = voltages[1] * (100.0 / 0.100 / 6200.0);
 
-        SupplyMeasurements {
+        Ok(SupplyMeasurements {
             v_p5v0mp,
             i_p28v0ch,
             i_p5v0ch,
-        }
+        })
+    }
+
+    /// Read the measurements of all devices on the RF module.
+    ///
+    /// # Note
+    /// The channel must already be selected on the I2C multiplexer. Each device is read with a
+    /// single transaction where possible, e.g. all power monitor channels are read at once.
+    fn read_all(&mut self) -> Result<ModuleMeasurements, Error> {
+        let supply = self.get_supply_measurements()?;
+        let temperature = self.get_temperature()?;
+        let input_power = self.get_input_power()?;
+
+        let now = self.clock.try_now().unwrap();
+        let (filters, filter) = (&mut self.filters, &self.filter);
+        Ok(ModuleMeasurements {
+            supply: SupplyMeasurements {
+                v_p5v0mp: supply.v_p5v0mp,
+                i_p5v0ch: filters
+                    .p5v_current
+                    .update(supply.i_p5v0ch, now, filter.current),
+                i_p28v0ch: filters
+                    .p28v_current
+                    .update(supply.i_p28v0ch, now, filter.current),
+            },
+            temperature,
+            input_power: filters.input_power.update(input_power, now, filter.power),
+        })
     }
 
     /// Get P28V rail current.
     ///
     /// # Returns
     /// The most recent P28V rail current measurements of the channel.
-    pub fn get_p28v_current(&mut self) -> f32 {
+    pub fn get_p28v_current(&mut self) -> Result<f32, Error> {
         let p28v_rail_current_sense = self
             .devices
             .power_monitor
             .get_voltage(ads7924::Channel::Zero)
-            .unwrap();
+            .map_err(|_| Error::interface(self.channel, Device::Ads7924, "read P28V current"))?;
 
-        p28v_rail_current_sense * (100.0 / 0.100 / 4300.0)
+        Ok(p28v_rail_current_sense * (100.0 / 0.100 / 4300.0))
     }
 
     /// Get the current input power measurement.
     ///
     /// # Returns
     /// The input power in dBm.
-    fn get_input_power(&mut self) -> f32 {
-        let voltage = self.devices.input_power_adc.get_voltage().unwrap();
+    fn get_input_power(&mut self) -> Result<f32, Error> {
+        let voltage = self
+            .devices
+            .input_power_adc
+            .get_voltage()
+            .map_err(|_| Error::interface(self.channel, Device::Mcp3221, "read input power"))?;
 
-        self.settings.settings().input_power_transform.map(voltage)
+        Ok(self.settings.settings().input_power_transform.map(voltage))
     }
 
-    /// Get the current reflected power measurement.
+    /// Get the raw voltages of all detectors and monitors of the channel.
     ///
-    /// # Args
-    /// * `adc` - The ADC to use for performing the measurement.
+    /// # Note
+    /// This is intended for calibration and hardware debugging. The power detector voltages are
+    /// the most recent samples of the fast measurement path.
+    pub fn get_raw_voltages(&mut self) -> Result<RawVoltages, Error> {
+        Ok(RawVoltages {
+            input_detector: self.devices.input_power_adc.get_voltage().map_err(|_| {
+                Error::interface(self.channel, Device::Mcp3221, "read input detector")
+            })?,
+            output_detector: detectors::output_voltage(self.channel),
+            reflected_detector: detectors::reflected_voltage(self.channel),
+            power_monitor: self.devices.power_monitor.get_voltages().map_err(|_| {
+                Error::interface(self.channel, Device::Ads7924, "read power monitor")
+            })?,
+        })
+    }
+
+    /// Get the current reflected power measurement.
     ///
     /// # Returns
     /// The reflected power in dBm.
-    pub fn get_reflected_power(&mut self, adc: &mut hal::adc::Adc<hal::pac::ADC3>) -> f32 {
-        let sample = self
-            .pins
-            .reflected_power
-            .convert(adc, SampleTime::Cycles_480);
-        let voltage = adc.sample_to_millivolts(sample) as f32 / 1000.0;
-
-        self.settings
+    pub fn get_reflected_power(&mut self) -> f32 {
+        let voltage = detectors::reflected_voltage(self.channel);
+        let power = self
+            .settings
             .settings()
             .reflected_power_transform
-            .map(voltage)
+            .map(voltage);
+
+        let now = self.clock.try_now().unwrap();
+        self.filters
+            .reflected_power
+            .update(power, now, self.filter.power)
     }
 
     /// Get the current output power measurement.
     ///
-    /// # Args
-    /// * `adc` - The ADC to use for performing the measurement.
-    ///
     /// # Returns
     /// The output power in dBm.
-    pub fn get_output_power(&mut self, adc: &mut hal::adc::Adc<hal::pac::ADC3>) -> f32 {
-        let sample = self.pins.output_power.convert(adc, SampleTime::Cycles_480);
-        let voltage = adc.sample_to_millivolts(sample) as f32 / 1000.0;
+    pub fn get_output_power(&mut self) -> f32 {
+        let voltage = detectors::output_voltage(self.channel);
+        let power = self.settings.settings().output_power_transform.map(voltage);
+
+        let now = self.clock.try_now().unwrap();
+        self.filters
+            .output_power
+            .update(power, now, self.filter.power)
+    }
+
+    /// Get the EUI-48 identifier of the RF module.
+    pub fn eui48(&self) -> [u8; 6] {
+        self.eui48
+    }
+
+    /// Check if the channel is locked out from the front panel.
+    pub fn is_locked_out(&self) -> bool {
+        self.locked_out
+    }
+
+    /// Check if the channel has been quarantined due to repeated measurement errors.
+    pub fn is_quarantined(&self) -> bool {
+        self.quarantined
+    }
 
-        self.settings.settings().output_power_transform.map(voltage)
+    /// Get the number of interlock trips that have occurred on the channel since boot.
+    pub fn interlock_trips(&self) -> u32 {
+        self.interlock_trips
     }
 
     /// Get the current bias voltage programmed to the RF amplification transistor.
@@ -585,6 +1309,51 @@ impl RfChannel {
     }
 }
 
+#[cfg(feature = "manufacturing")]
+impl RfChannel {
+    /// Sweep the outputs of the bias and interlock threshold DACs and read back each step.
+    ///
+    /// # Note
+    /// This is intended for end-of-line testing of unpowered channels. Afterwards, the bias DAC is
+    /// returned to pinch-off and the interlock thresholds are restored.
+    ///
+    /// # Args
+    /// * `steps` - The number of steps of each sweep, including both end points. Must be at least 2.
+    /// * `report` - Called with the DAC name, the nominal voltage, and the read back voltage of
+    ///   each step. The voltages are None if the DAC could not be written or read.
+    pub fn sweep_dacs(
+        &mut self,
+        steps: usize,
+        mut report: impl FnMut(&'static str, Option<f32>, Option<f32>),
+    ) -> Result<(), Error> {
+        if steps < 2 {
+            return Err(Error::Invalid);
+        }
+
+        for step in 0..steps {
+            let fraction = step as f32 / (steps - 1) as f32;
+
+            let bias_dac = &mut self.devices.bias_dac;
+            let nominal = bias_dac.set_voltage(fraction * platform::BIAS_DAC_VCC).ok();
+            let readback = bias_dac.get_voltage().ok();
+            report("DAC7571", nominal, readback);
+
+            // The AD5627 full scale range is 2.5V, exclusive of the upper bound.
+            for (name, dac) in [("AD5627A", ad5627::Dac::A), ("AD5627B", ad5627::Dac::B)] {
+                let thresholds_dac = &mut self.devices.interlock_thresholds_dac;
+                let nominal = thresholds_dac.set_voltage(fraction * 2.4, dac).ok();
+                let readback = thresholds_dac.get_voltage(dac).ok();
+                report(name, nominal, readback);
+            }
+        }
+
+        self.pinch_off();
+        self.apply_output_interlock_threshold()?;
+        self.set_reflected_interlock_threshold(platform::MAXIMUM_REFLECTED_POWER_DBM)?;
+        Ok(())
+    }
+}
+
 mod sm {
     use super::{ChannelFault, Interlock};
     use crate::hardware::SystemTimer;
@@ -598,12 +1367,16 @@ mod sm {
         }
     }
 
-    impl serde::Serialize for States {
-        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-            let (idx, var) = match self {
+    impl States {
+        /// Get the index and name of the state.
+        fn variant(&self) -> (u32, &'static str) {
+            match self {
                 States::Blocked(ChannelFault::OverTemperature) => (0, "Blocked(OverTemperature)"),
                 States::Blocked(ChannelFault::UnderTemperature) => (0, "Blocked(UnderTemperature)"),
                 States::Blocked(ChannelFault::SupplyAlert) => (0, "Blocked(SupplyAlert)"),
+                States::Blocked(ChannelFault::OverCurrent) => (0, "Blocked(OverCurrent)"),
+                States::Blocked(ChannelFault::BusError) => (0, "Blocked(BusError)"),
+                States::Blocked(ChannelFault::BiasFault) => (0, "Blocked(BiasFault)"),
                 States::Off => (1, "Off"),
                 States::Powerup(_) => (2, "Powerup"),
                 States::Powered => (3, "Powered"),
@@ -612,7 +1385,19 @@ mod sm {
                 States::Tripped(Interlock::Output) => (5, "Tripped(Output)"),
                 States::Tripped(Interlock::Input) => (5, "Tripped(Input)"),
                 States::Tripped(Interlock::Reflected) => (5, "Tripped(Reflected)"),
-            };
+                States::Tripped(Interlock::Chained) => (5, "Tripped(Chained)"),
+            }
+        }
+
+        /// Get the human-readable name of the state.
+        pub fn name(&self) -> &'static str {
+            self.variant().1
+        }
+    }
+
+    impl serde::Serialize for States {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let (idx, var) = self.variant();
             serializer.serialize_unit_variant("State", idx, var)
         }
     }
@@ -638,6 +1423,7 @@ mod sm {
             Enabled + Fault(ChannelFault) / handle_fault = Blocked(ChannelFault),
 
             Tripped(Interlock) + InterlockReset = Powered,
+            Tripped(Interlock) + Rearm / rearm_interlocks = Powered,
             Tripped(Interlock) + DisableRf = Powered,
             Tripped(Interlock) + Disable / start_disable_interlock = Powerdown(Instant<SystemTimer>),
             Tripped(Interlock) + Fault(ChannelFault) / handle_fault_interlock = Blocked(ChannelFault),
@@ -646,6 +1432,7 @@ mod sm {
             Powerdown(Instant<SystemTimer>) + Fault(ChannelFault) / handle_fault_instant = Blocked(ChannelFault),
 
             Blocked(ChannelFault) + Fault(ChannelFault) / handle_recurrent_fault = Blocked(ChannelFault),
+            Blocked(ChannelFault) + Release [guard_release] = Off,
         }
     }
 }
@@ -654,12 +1441,32 @@ impl sm::StateMachineContext for RfChannel {
     /// Handle the occurrence of a tripped interlock.
     fn handle_trip(&mut self, interlock: &Interlock) -> Interlock {
         self.disable_rf_switch();
+        self.interlock_trips = self.interlock_trips.wrapping_add(1);
+        statistics::record_trip(*interlock);
         *interlock
     }
 
     /// Turn off the RF output enable switch.
     fn disable_rf_switch(&mut self) {
+        self.set_rf_switch(false);
+    }
+
+    /// Clear the latched interlock trip and re-arm the interlock comparators.
+    fn rearm_interlocks(&mut self, _: &Interlock) {
+        // Place the bias DAC into pinch-off so that toggling ON/OFF introduces no output
+        // transients on the RF connectors.
+        self.pinch_off();
+
+        // The interlocks are reset by a falling edge on ON/OFF.
+        self.pins.signal_on.set_high();
+        self.delay.delay_ms(1u32);
         self.pins.signal_on.set_low();
+
+        // Restore the bias for the powered state. The output is re-enabled on the next update if
+        // the channel settings allow it.
+        if self.settings().state != ChannelState::Diagnostic {
+            self.apply_bias().ok();
+        }
     }
 
     /// Begin the process of powering up the channel.
@@ -668,13 +1475,13 @@ impl sm::StateMachineContext for RfChannel {
     /// The time at which the powerup process can be deemed complete.
     fn start_powerup(&mut self) -> Instant<SystemTimer> {
         // Place the bias DAC to drive the RF amplifier into pinch-off during the power-up process.
-        self.devices
-            .bias_dac
-            .set_voltage(3.2)
-            .expect("Failed to disable RF bias voltage");
+        self.pinch_off();
 
-        // Start the LM3880 power supply sequencer.
-        self.pins.enable_power.set_high();
+        // Start the LM3880 power supply sequencer. The channel remains unpowered if pinch-off could
+        // not be verified and is blocked on the next update.
+        if !self.bias_fault {
+            self.pins.enable_power.set_high();
+        }
 
         // The LM3880 requires 180ms to power up all supplies on the channel. We add an additional
         // 20ms margin.
@@ -701,7 +1508,7 @@ impl sm::StateMachineContext for RfChannel {
     /// Ok if the channel can power up. Err otherwise.
     fn guard_powerup(&mut self) -> Result<(), ()> {
         let settings = self.settings.settings();
-        if settings.state == ChannelState::Off {
+        if settings.state == ChannelState::Off || self.locked_out {
             Err(())
         } else {
             Ok(())
@@ -715,7 +1522,7 @@ impl sm::StateMachineContext for RfChannel {
     fn guard_enable(&mut self) -> Result<(), ()> {
         let settings = self.settings.settings();
 
-        if platform::watchdog_detected() {
+        if platform::watchdog_detected() || self.locked_out || self.bias_fault {
             return Err(());
         }
 
@@ -749,8 +1556,11 @@ impl sm::StateMachineContext for RfChannel {
         assert!(self.pins.enable_power.is_set_high());
         assert!(settings.output_interlock_threshold > settings.output_power_transform.map(0.100));
 
-        self.apply_bias().unwrap();
-        self.pins.signal_on.set_high();
+        // The output remains disabled if the bias could not be verified. The channel is blocked on
+        // the next update.
+        if self.apply_bias().is_ok() {
+            self.set_rf_switch(true);
+        }
     }
 
     /// Begin the process of powering down the channel.
@@ -761,10 +1571,7 @@ impl sm::StateMachineContext for RfChannel {
         self.disable_rf_switch();
 
         // Set the bias DAC output into pinch-off.
-        self.devices
-            .bias_dac
-            .set_voltage(3.2)
-            .expect("Failed to disable RF bias voltage");
+        self.pinch_off();
 
         self.pins.enable_power.set_low();
 
@@ -783,6 +1590,19 @@ impl sm::StateMachineContext for RfChannel {
         self.start_disable()
     }
 
+    /// Check to see if a blocked channel may be released.
+    ///
+    /// # Returns
+    /// Ok if the channel was blocked due to RF module communication errors or an unverified bias.
+    /// Otherwise, an error.
+    fn guard_release(&mut self, fault: &ChannelFault) -> Result<(), ()> {
+        if matches!(fault, ChannelFault::BusError | ChannelFault::BiasFault) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
     /// Check if a deadline has been met.
     ///
     /// # Returns
@@ -830,15 +1650,40 @@ impl sm::StateMachine<RfChannel> {
     /// # Returns
     /// The current channel [PowerStatus]
     pub fn update(&mut self) -> PowerStatus {
+        // Quarantined channels are no longer polled.
+        if self.context().quarantined {
+            return PowerStatus {
+                powered: self.context().pins.enable_power.is_set_high(),
+                rf_disabled: self.context().pins.signal_on.is_set_low(),
+                blocked: true,
+            };
+        }
+
         // Check for channel faults.
-        if let Some(fault) = self.context_mut().check_faults() {
-            self.process_event(sm::Events::Fault(fault)).unwrap();
+        match self.context_mut().check_faults() {
+            Some(ChannelFault::BusError) => self.handle_bus_error(),
+            Some(fault) => {
+                self.context_mut().bus_errors = 0;
+                self.process_event(sm::Events::Fault(fault)).unwrap();
+            }
+            None => self.context_mut().bus_errors = 0,
         }
 
-        // Check for interlock trips.
+        // Check for interlock trips. Trips detected by the fast measurement path are processed
+        // first, as the RF output has already been disabled.
+        let trip = detectors::take_trip(self.context().channel);
         if matches!(self.state(), &sm::States::Enabled) {
-            if let Some(interlock) = self.context_mut().get_overdrive_source() {
-                self.process_event(sm::Events::Trip(interlock)).unwrap();
+            let source = match trip {
+                Some(interlock) => Ok(Some(interlock)),
+                None => self.context_mut().get_overdrive_source(),
+            };
+
+            match source {
+                Ok(Some(interlock)) => {
+                    self.process_event(sm::Events::Trip(interlock)).unwrap();
+                }
+                Ok(None) => {}
+                Err(_) => self.handle_bus_error(),
             }
         }
 
@@ -847,7 +1692,82 @@ impl sm::StateMachine<RfChannel> {
         PowerStatus {
             powered: self.context().pins.enable_power.is_set_high(),
             rf_disabled: self.context().pins.signal_on.is_set_low(),
-            blocked: matches!(self.state(), &sm::States::Blocked(_)),
+            blocked: matches!(self.state(), &sm::States::Blocked(_)) || self.context().locked_out,
+        }
+    }
+
+    /// Handle a failed or implausible measurement of the RF module.
+    ///
+    /// # Note
+    /// The channel is faulted on the first error. After repeated consecutive errors, the channel is
+    /// quarantined and no longer polled until communication is retried.
+    fn handle_bus_error(&mut self) {
+        let channel = self.context_mut();
+        channel.bus_errors = channel.bus_errors.saturating_add(1);
+        statistics::record(Event::I2cError);
+
+        if channel.bus_errors == 1 {
+            log::error!("Failed to read RF module measurements");
+        }
+
+        if channel.bus_errors >= QUARANTINE_ERROR_LIMIT && !channel.quarantined {
+            log::error!(
+                "Quarantining channel after {} consecutive measurement errors",
+                channel.bus_errors
+            );
+            channel.quarantined = true;
+        }
+
+        self.process_event(sm::Events::Fault(ChannelFault::BusError))
+            .ok();
+    }
+
+    /// Retry communication with a channel that was blocked due to RF module errors or an
+    /// unverified bias.
+    ///
+    /// # Note
+    /// If all RF module devices respond and pinch-off of the bias DAC is verified, the interlock
+    /// thresholds are reapplied, the quarantine is lifted, and the channel is powered up again if
+    /// permitted by the channel settings.
+    pub fn retry(&mut self) -> Result<(), Error> {
+        if !matches!(
+            self.state(),
+            sm::States::Blocked(ChannelFault::BusError | ChannelFault::BiasFault)
+        ) {
+            return Err(Error::InvalidState);
+        }
+
+        let channel = self.context_mut();
+        channel.read_all()?;
+
+        // The bias fault is only cleared once pinch-off has been verified again.
+        channel.bias_fault = false;
+        channel.set_bias_dac(platform::BIAS_DAC_VCC)?;
+        channel.apply_output_interlock_threshold()?;
+        channel.set_reflected_interlock_threshold(platform::MAXIMUM_REFLECTED_POWER_DBM)?;
+
+        channel.bus_errors = 0;
+        channel.quarantined = false;
+
+        self.process_event(sm::Events::Release)
+            .map_err(|_| Error::InvalidState)?;
+        self.handle_startup();
+
+        Ok(())
+    }
+
+    /// Engage or release the local front-panel lockout of the channel.
+    ///
+    /// # Note
+    /// While locked out, the channel is disabled and cannot be powered up or enabled by any means
+    /// until the lockout is released.
+    ///
+    /// # Args
+    /// * `lockout` - Specified true to engage the lockout.
+    pub fn set_lockout(&mut self, lockout: bool) {
+        self.context_mut().locked_out = lockout;
+        if lockout {
+            self.process_event(sm::Events::Disable).ok();
         }
     }
 
@@ -857,6 +1777,16 @@ impl sm::StateMachine<RfChannel> {
         Ok(())
     }
 
+    /// Re-arm the interlocks of a tripped channel.
+    ///
+    /// # Note
+    /// The output is restored on the next update if the channel settings allow it.
+    pub fn rearm(&mut self) -> Result<(), Error> {
+        self.process_event(sm::Events::Rearm)
+            .map_err(|_| Error::InvalidState)?;
+        Ok(())
+    }
+
     /// Handle the user pressing the "Standby" button.
     pub fn standby(&mut self) {
         self.process_event(sm::Events::Disable).ok();
@@ -869,6 +1799,30 @@ impl sm::StateMachine<RfChannel> {
         self.process_event(sm::Events::InterlockReset).ok();
     }
 
+    /// Check if the channel was faulted by a supply alert of the RF module.
+    pub fn is_supply_faulted(&self) -> bool {
+        matches!(self.state(), sm::States::Blocked(ChannelFault::SupplyAlert))
+    }
+
+    /// Check if an interlock of the channel is tripped.
+    pub fn is_tripped(&self) -> bool {
+        matches!(self.state(), sm::States::Tripped(_))
+    }
+
+    /// Trip the channel because the interlock of a channel that it depends on tripped.
+    ///
+    /// # Note
+    /// The trip is ignored unless the RF output of the channel is enabled.
+    pub fn trip_chained(&mut self) {
+        self.process_event(sm::Events::Trip(Interlock::Chained))
+            .ok();
+    }
+
+    /// Check if the channel RF output is enabled without a tripped interlock or fault.
+    pub fn is_output_enabled(&self) -> bool {
+        matches!(self.state(), sm::States::Enabled)
+    }
+
     /// Handle an update to channel settings.
     pub fn handle_settings(&mut self, settings: &ChannelSettings) -> Result<(), Error> {
         self.context_mut().apply_settings(settings)?;
@@ -879,13 +1833,21 @@ impl sm::StateMachine<RfChannel> {
                 self.process_event(sm::Events::Disable).ok();
             }
 
-            // For bias tuning, we may need to disable the RF switch.
-            (sm::States::Enabled | sm::States::Tripped(_), ChannelState::Powered) => {
+            // For bias tuning and diagnostics, we may need to disable the RF switch.
+            (
+                sm::States::Enabled | sm::States::Tripped(_),
+                ChannelState::Powered | ChannelState::Diagnostic,
+            ) => {
                 self.process_event(sm::Events::DisableRf).unwrap();
             }
 
-            (sm::States::Off, ChannelState::Powered | ChannelState::Enabled) => {
-                self.process_event(sm::Events::InterlockReset).unwrap();
+            (
+                sm::States::Off,
+                ChannelState::Powered | ChannelState::Enabled | ChannelState::Diagnostic,
+            ) => {
+                // Powering up is rejected while the channel is locked out.
+                self.process_event(sm::Events::InterlockReset)
+                    .map_err(|_| Error::InvalidState)?;
             }
 
             // Note: Note: Powered -> Enabled transitions are handled via the periodic `Update`
@@ -897,23 +1859,58 @@ impl sm::StateMachine<RfChannel> {
     }
 
     /// Get status information about the channel.
-    pub fn get_status(&mut self, adc: &mut hal::adc::Adc<hal::pac::ADC3>) -> ChannelStatus {
-        let channel = self.context_mut();
+    ///
+    /// # Note
+    /// If the devices of the RF module cannot be read, the channel is faulted.
+    ///
+    /// # Returns
+    /// The channel status, or an error if the RF module could not be measured.
+    pub fn get_status(&mut self) -> Result<ChannelStatus, Error> {
+        if self.context().quarantined {
+            return Err(Error::Fault);
+        }
 
-        let power_measurements = channel.get_supply_measurements();
+        let ModuleMeasurements {
+            supply: power_measurements,
+            temperature,
+            input_power,
+        } = match self.context_mut().read_all() {
+            Ok(measurements) => measurements,
+            Err(err) => {
+                self.handle_bus_error();
+                return Err(err);
+            }
+        };
 
-        ChannelStatus {
+        let channel = self.context_mut();
+        let reflected_power = channel.get_reflected_power();
+
+        let thresholds = channel.warning_thresholds;
+        let warnings = Warnings {
+            temperature: temperature > thresholds.temperature,
+            p28v_current: power_measurements.i_p28v0ch > thresholds.p28v_current,
+            reflected_power: reflected_power > thresholds.reflected_power,
+            no_input: channel.settings().state == ChannelState::Enabled
+                && input_power < thresholds.input_power_floor,
+        };
+
+        Ok(ChannelStatus {
             reflected_overdrive: channel.pins.reflected_overdrive.is_high(),
             output_overdrive: channel.pins.output_overdrive.is_high(),
             alert: channel.pins.alert.is_low(),
-            temperature: channel.get_temperature(),
+            temperature,
             p28v_current: power_measurements.i_p28v0ch,
             p5v_current: power_measurements.i_p5v0ch,
             p5v_voltage: power_measurements.v_p5v0mp,
-            input_power: channel.get_input_power(),
-            output_power: channel.get_output_power(adc),
-            reflected_power: channel.get_reflected_power(adc),
+            input_power,
+            output_power: channel.get_output_power(),
+            reflected_power,
+            label: channel.settings().label,
+            unsaved: channel.has_unsaved_changes(),
             state: *self.state(),
-        }
+            warnings,
+            interlock_derating: channel.interlock_derating,
+            timestamp: super::rtc::now(),
+        })
     }
 }
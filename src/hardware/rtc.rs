@@ -0,0 +1,266 @@
+//! Booster real-time clock
+//!
+//! # Design
+//! The RTC of the MCU is located in the backup domain, so it keeps time across resets and, if a
+//! backup battery is populated, across power cycles. The RTC is clocked by the 32.768 kHz LSE
+//! crystal if populated. Otherwise, the inaccurate LSI oscillator is used as a fallback. While the
+//! RTC runs on the LSI oscillator, the crystal is started again on every boot, which delays the
+//! boot by up to 3 seconds on hardware without the crystal.
+//!
+//! Booster has no network time source, so the time is unknown until it is set via the `clock/set`
+//! control command or the USB `time:<seconds>` command. Once set, the time is used to timestamp
+//! log messages and telemetry. The RTC calendar only covers the years 2000 to 2099.
+use super::{hal, platform, CPU_FREQ};
+use cortex_m::peripheral::DWT;
+
+pub use calendar::DateTime;
+use calendar::SECONDS_PER_DAY;
+
+/// The UNIX time of 2000-01-01T00:00:00Z, which is the earliest representable time.
+const EPOCH_2000: u32 = 946_684_800;
+
+/// The UNIX time of 2100-01-01T00:00:00Z, which is the first time that is not representable.
+const EPOCH_2100: u32 = 4_102_444_800;
+
+/// The maximum time to wait for the LSE crystal to start in milliseconds. 32.768 kHz crystals
+/// typically take up to 2 seconds to start.
+const LSE_TIMEOUT_MS: u32 = 3_000;
+
+/// The maximum time to wait for the LSI oscillator or an RTC flag in milliseconds.
+const TIMEOUT_MS: u32 = 10;
+
+/// The number of RTC backup registers.
+const NUM_BACKUP_REGISTERS: usize = 20;
+
+/// Encode a value as two BCD digits.
+fn to_bcd(value: u32) -> u32 {
+    ((value / 10) << 4) | (value % 10)
+}
+
+/// Decode two BCD digits.
+fn from_bcd(bcd: u32) -> u32 {
+    (bcd >> 4) * 10 + (bcd & 0xF)
+}
+
+/// Wait for a condition to become true.
+///
+/// # Note
+/// The DWT cycle counter must be enabled and the CPU must be clocked at [CPU_FREQ].
+///
+/// # Args
+/// * `timeout_ms` - The maximum time to wait in milliseconds.
+///
+/// # Returns
+/// True if the condition was met before the timeout.
+fn wait_for(timeout_ms: u32, mut condition: impl FnMut() -> bool) -> bool {
+    let timeout = timeout_ms * (CPU_FREQ / 1_000);
+    let start = DWT::cycle_count();
+
+    while DWT::cycle_count().wrapping_sub(start) < timeout {
+        if condition() {
+            return true;
+        }
+    }
+
+    condition()
+}
+
+/// Unlock the write protection of the RTC registers.
+fn unlock(rtc: &hal::pac::rtc::RegisterBlock) {
+    rtc.wpr.write(|w| unsafe { w.bits(0xCA) });
+    rtc.wpr.write(|w| unsafe { w.bits(0x53) });
+}
+
+/// Lock the write protection of the RTC registers.
+fn lock(rtc: &hal::pac::rtc::RegisterBlock) {
+    rtc.wpr.write(|w| unsafe { w.bits(0xFF) });
+}
+
+/// Start the LSE crystal.
+///
+/// # Returns
+/// True if the crystal started. Otherwise, the crystal is disabled again.
+fn start_lse(rcc: &hal::pac::rcc::RegisterBlock) -> bool {
+    rcc.bdcr.modify(|_, w| w.lseon().set_bit());
+    if wait_for(LSE_TIMEOUT_MS, || rcc.bdcr.read().lserdy().bit_is_set()) {
+        true
+    } else {
+        rcc.bdcr.modify(|_, w| w.lseon().clear_bit());
+        false
+    }
+}
+
+/// Select the RTC clock source and configure the prescaler for a 1 Hz calendar.
+///
+/// # Args
+/// * `lse` - Specified true to clock the RTC by the LSE crystal, which must be running.
+fn configure(lse: bool) {
+    let rcc = unsafe { &*hal::pac::RCC::ptr() };
+    let rtc = unsafe { &*hal::pac::RTC::ptr() };
+
+    let (source, prescaler) = if lse {
+        // 32.768 kHz / 128 / 256 = 1 Hz
+        (0b01, (127 << 16) | 255)
+    } else {
+        rcc.csr.modify(|_, w| w.lsion().set_bit());
+        wait_for(TIMEOUT_MS, || rcc.csr.read().lsirdy().bit_is_set());
+
+        // 32 kHz / 128 / 250 = 1 Hz
+        (0b10, (127 << 16) | 249)
+    };
+
+    rcc.bdcr
+        .modify(|_, w| unsafe { w.rtcsel().bits(source) }.rtcen().set_bit());
+
+    unlock(rtc);
+    rtc.isr.modify(|_, w| w.init().set_bit());
+    if wait_for(TIMEOUT_MS, || rtc.isr.read().initf().bit_is_set()) {
+        rtc.prer.write(|w| unsafe { w.bits(prescaler) });
+
+        // Read the calendar directly from the counters, so that reads are not affected by the
+        // shadow register synchronization.
+        rtc.cr.modify(|_, w| w.bypshad().set_bit());
+    }
+    rtc.isr.modify(|_, w| w.init().clear_bit());
+    lock(rtc);
+}
+
+/// Enable the RTC.
+///
+/// # Note
+/// This must be called after the clocks are configured and the DWT cycle counter is enabled.
+///
+/// If the RTC is already running on the LSE crystal (e.g. after a reset), it is not reconfigured.
+/// If it is running on the LSI oscillator, the LSE crystal is started again, as it may have failed
+/// to start in time on a previous boot. The RTC clock source can only be changed by resetting the
+/// backup domain, so the time and the backup registers are saved and restored around the reset.
+pub fn init() {
+    let rcc = unsafe { &*hal::pac::RCC::ptr() };
+
+    platform::enable_backup_access();
+
+    let bdcr = rcc.bdcr.read();
+    let running = bdcr.rtcen().bit_is_set();
+    if running && bdcr.rtcsel().bits() == 0b01 {
+        return;
+    }
+
+    // Prefer the LSE crystal. If it is not populated, fall back to the LSI oscillator.
+    let lse = start_lse(rcc);
+
+    if !running {
+        if !lse {
+            log::warn!("RTC crystal not detected. Using LSI oscillator");
+        }
+        configure(lse);
+        return;
+    }
+
+    if !lse {
+        return;
+    }
+
+    log::info!("RTC crystal detected. Switching from LSI oscillator");
+
+    let start = DWT::cycle_count();
+    let time = now();
+    let mut backup = [0; NUM_BACKUP_REGISTERS];
+    for (register, value) in backup.iter_mut().enumerate() {
+        *value = platform::read_backup(register);
+    }
+
+    // Resetting the backup domain also stops the LSE crystal, so it has to be started again.
+    rcc.bdcr.modify(|_, w| w.bdrst().set_bit());
+    rcc.bdcr.modify(|_, w| w.bdrst().clear_bit());
+    let lse = start_lse(rcc);
+    configure(lse);
+
+    for (register, value) in backup.iter().enumerate() {
+        platform::write_backup(register, *value);
+    }
+
+    // Account for the time spent waiting for the crystal to start.
+    if let Some(time) = time {
+        let elapsed = DWT::cycle_count().wrapping_sub(start) / CPU_FREQ;
+        set(time + elapsed).ok();
+    }
+}
+
+/// Read the raw time and date registers of the RTC.
+fn read_registers(rtc: &hal::pac::rtc::RegisterBlock) -> (u32, u32) {
+    // The counters are read directly, so the registers are read until they are consistent.
+    loop {
+        let time = rtc.tr.read().bits();
+        let date = rtc.dr.read().bits();
+        if rtc.tr.read().bits() == time {
+            return (time, date);
+        }
+    }
+}
+
+/// Get the current time.
+///
+/// # Returns
+/// The number of seconds since 1970-01-01T00:00:00Z, or None if the time has not been set.
+pub fn now() -> Option<u32> {
+    let rtc = unsafe { &*hal::pac::RTC::ptr() };
+
+    // The calendar is initialized once the year is set.
+    if rtc.isr.read().inits().bit_is_clear() {
+        return None;
+    }
+
+    let (time, date) = read_registers(rtc);
+
+    let datetime = DateTime {
+        year: 2000 + from_bcd((date >> 16) & 0xFF),
+        month: from_bcd((date >> 8) & 0x1F),
+        day: from_bcd(date & 0x3F),
+        hour: from_bcd((time >> 16) & 0x3F),
+        minute: from_bcd((time >> 8) & 0x7F),
+        second: from_bcd(time & 0x7F),
+    };
+
+    Some(datetime.to_unix())
+}
+
+/// Set the current time.
+///
+/// # Args
+/// * `time` - The number of seconds since 1970-01-01T00:00:00Z.
+pub fn set(time: u32) -> Result<(), &'static str> {
+    if !(EPOCH_2000..EPOCH_2100).contains(&time) {
+        return Err("Time out of range");
+    }
+
+    let rtc = unsafe { &*hal::pac::RTC::ptr() };
+    let datetime = DateTime::from_unix(time);
+
+    // 1970-01-01 was a Thursday. The RTC numbers weekdays from Monday (1) to Sunday (7).
+    let weekday = (time / SECONDS_PER_DAY + 3) % 7 + 1;
+
+    let tr =
+        (to_bcd(datetime.hour) << 16) | (to_bcd(datetime.minute) << 8) | to_bcd(datetime.second);
+    let dr = (to_bcd(datetime.year - 2000) << 16)
+        | (weekday << 13)
+        | (to_bcd(datetime.month) << 8)
+        | to_bcd(datetime.day);
+
+    unlock(rtc);
+    rtc.isr.modify(|_, w| w.init().set_bit());
+    let result = if wait_for(TIMEOUT_MS, || rtc.isr.read().initf().bit_is_set()) {
+        rtc.tr.write(|w| unsafe { w.bits(tr) });
+        rtc.dr.write(|w| unsafe { w.bits(dr) });
+        Ok(())
+    } else {
+        Err("RTC not responding")
+    };
+    rtc.isr.modify(|_, w| w.init().clear_bit());
+    lock(rtc);
+
+    if result.is_ok() {
+        log::info!("Clock set to {}", datetime);
+    }
+
+    result
+}
@@ -0,0 +1,106 @@
+//! Booster safe-mode boot
+//!
+//! # Design
+//! Consecutive abnormal resets (panics and watchdog resets) are counted in the RTC backup
+//! registers, which are retained across resets. Once the count reaches [SAFE_MODE_THRESHOLD], the
+//! device boots into safe mode: all channels are locked out and remain off, while networking and
+//! USB are brought up normally so that the device can be diagnosed. This ensures that a crash loop
+//! never leaves amplifiers driven.
+//!
+//! The count is reset by any normal reset, by a power cycle, or when the service state is
+//! acknowledged via the USB `service` command. Resets requested by the network watchdog are
+//! controlled software resets and are thus not counted as abnormal. They are identified by a
+//! marker in the backup registers so that the reset cause can be reported.
+use super::platform;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// The number of consecutive abnormal resets after which the device boots into safe mode.
+pub const SAFE_MODE_THRESHOLD: u32 = 3;
+
+/// The backup register that stores the number of consecutive abnormal resets.
+const RESET_COUNT_REGISTER: usize = 0;
+
+/// The backup register that indicates that the previous reset was caused by a panic.
+const PANIC_MARKER_REGISTER: usize = 1;
+
+/// The value of the panic marker register after a panic.
+const PANIC_MARKER: u32 = 0xDEAD_BEEF;
+
+/// The backup register that indicates that the previous reset was requested by the network
+/// watchdog.
+const NETWORK_MARKER_REGISTER: usize = 2;
+
+/// The value of the network marker register after a network watchdog reset.
+const NETWORK_MARKER: u32 = 0x4E45_5457;
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+static NETWORK_RESET: AtomicBool = AtomicBool::new(false);
+
+/// Count the reset that caused the current boot and determine if safe mode should be entered.
+///
+/// # Note
+/// This must be called once at startup, before any channels are enabled.
+///
+/// # Returns
+/// True if the device has booted into safe mode.
+pub fn init() -> bool {
+    platform::enable_backup_access();
+
+    let panicked = platform::read_backup(PANIC_MARKER_REGISTER) == PANIC_MARKER;
+    platform::write_backup(PANIC_MARKER_REGISTER, 0);
+
+    let network_reset = platform::read_backup(NETWORK_MARKER_REGISTER) == NETWORK_MARKER;
+    platform::write_backup(NETWORK_MARKER_REGISTER, 0);
+    NETWORK_RESET.store(network_reset, Ordering::Relaxed);
+
+    let count = if platform::power_on_detected() {
+        0
+    } else if panicked || platform::watchdog_detected() {
+        platform::read_backup(RESET_COUNT_REGISTER).saturating_add(1)
+    } else {
+        0
+    };
+    platform::write_backup(RESET_COUNT_REGISTER, count);
+
+    let active = count >= SAFE_MODE_THRESHOLD;
+    if active {
+        log::error!("{count} consecutive abnormal resets detected. Booting into safe mode");
+    } else if count > 0 {
+        log::warn!("{count} consecutive abnormal resets detected");
+    }
+
+    ACTIVE.store(active, Ordering::Relaxed);
+    active
+}
+
+/// Record that the device is about to reset due to a panic.
+pub fn record_panic() {
+    platform::enable_backup_access();
+    platform::write_backup(PANIC_MARKER_REGISTER, PANIC_MARKER);
+}
+
+/// Record that the device is about to reset because the network watchdog expired.
+pub fn record_network_reset() {
+    platform::enable_backup_access();
+    platform::write_backup(NETWORK_MARKER_REGISTER, NETWORK_MARKER);
+}
+
+/// Check if the previous reset was requested by the network watchdog.
+pub fn network_reset_detected() -> bool {
+    NETWORK_RESET.load(Ordering::Relaxed)
+}
+
+/// Check if the device has booted into safe mode.
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+/// Acknowledge the abnormal resets and reset the count.
+///
+/// # Note
+/// Channels remain locked out until the lockout is released via the front panel.
+pub fn clear() {
+    platform::write_backup(RESET_COUNT_REGISTER, 0);
+    ACTIVE.store(false, Ordering::Relaxed);
+}
@@ -0,0 +1,139 @@
+//! Booster startup self-test
+//!
+//! # Design
+//! A structured self-test of the hardware is run once during startup, after all devices have been
+//! initialized. It checks the mainboard and RF module EEPROM configurations, the presence of all I2C
+//! devices of each channel, the ADC reference, the chassis fans, and the supply.
+//!
+//! Failures do not prevent Booster from starting, so that a degraded unit remains usable. Instead,
+//! the report is logged to the console and published in the `alive/meta` message, so that degraded
+//! units are obvious immediately.
+use super::{
+    booster_channels::{BoosterChannels, MODULE_DEVICES},
+    power_fail, Channel,
+};
+use serde::Serialize;
+
+/// The nominal ADC reference voltage in millivolts.
+const NOMINAL_ADC_REFERENCE_MV: u32 = 2500;
+
+/// The maximum deviation of the calibrated ADC reference from its nominal value in millivolts.
+const ADC_REFERENCE_TOLERANCE_MV: u32 = 100;
+
+/// The result of the self-test of an RF channel.
+#[derive(Serialize, Copy, Clone, Debug, PartialEq)]
+pub enum ChannelResult {
+    /// No RF module is installed.
+    Absent,
+
+    /// The RF module enumerated and its EEPROM configuration is valid.
+    Passed,
+
+    /// The RF module EEPROM contains no valid configuration (e.g. due to a CRC error) or the
+    /// stored settings were rejected. Default settings are used.
+    InvalidEeprom,
+
+    /// Only some of the devices of the RF module responded. The channel is not used.
+    MissingDevices,
+}
+
+/// The report of the startup self-test.
+#[derive(Serialize, Copy, Clone, Debug)]
+pub struct SelfTest {
+    /// Specified true if all checks passed.
+    pub passed: bool,
+
+    /// Specified true if the mainboard EEPROM contained a valid configuration.
+    pub eeprom: bool,
+
+    /// Specified true if the calibrated ADC reference was within tolerance of its nominal value.
+    pub adc: bool,
+
+    /// The calibrated ADC reference voltage in millivolts.
+    pub adc_reference_mv: u32,
+
+    /// Specified true if the fans spun up and down as expected.
+    pub fans: bool,
+
+    /// Specified true if the supply was above the power-fail threshold.
+    pub supply: bool,
+
+    /// The result of each channel.
+    pub channels: [ChannelResult; 8],
+}
+
+impl SelfTest {
+    /// Run the self-test.
+    ///
+    /// # Args
+    /// * `channels` - The RF channels.
+    /// * `eeprom` - Specified true if the mainboard EEPROM contained a valid configuration.
+    /// * `adc_reference_mv` - The calibrated ADC reference voltage in millivolts.
+    /// * `fans` - The result of the fan self-test.
+    pub fn run(
+        channels: &mut BoosterChannels,
+        eeprom: bool,
+        adc_reference_mv: u32,
+        fans: bool,
+    ) -> Self {
+        let mut results = [ChannelResult::Absent; 8];
+        for (channel, result) in enum_iterator::all::<Channel>().zip(results.iter_mut()) {
+            *result = match channels.channel_mut(channel) {
+                Some(rf_channel) => {
+                    let rf_channel = rf_channel.context();
+                    if rf_channel.eeprom_valid() && rf_channel.settings_error().is_none() {
+                        ChannelResult::Passed
+                    } else {
+                        ChannelResult::InvalidEeprom
+                    }
+                }
+                None => {
+                    let present = MODULE_DEVICES
+                        .iter()
+                        .filter(|device| channels.probe(Some(channel), device.address()))
+                        .count();
+                    if present == 0 {
+                        ChannelResult::Absent
+                    } else {
+                        ChannelResult::MissingDevices
+                    }
+                }
+            };
+        }
+
+        let adc = NOMINAL_ADC_REFERENCE_MV.abs_diff(adc_reference_mv) <= ADC_REFERENCE_TOLERANCE_MV;
+        let supply = power_fail::supply_valid();
+
+        // Absent channels are not a failure, as units may be populated with fewer channels.
+        let passed = eeprom
+            && adc
+            && fans
+            && supply
+            && results
+                .iter()
+                .all(|result| matches!(result, ChannelResult::Passed | ChannelResult::Absent));
+
+        Self {
+            passed,
+            eeprom,
+            adc,
+            adc_reference_mv,
+            fans,
+            supply,
+            channels: results,
+        }
+    }
+
+    /// Log the self-test report to the console.
+    pub fn log(&self) {
+        if self.passed {
+            log::info!("Self-test passed");
+        } else {
+            log::error!("Self-test failed: {:?}", self);
+        }
+
+        for (index, result) in self.channels.iter().enumerate() {
+            log::info!("Channel {}: {:?}", index, result);
+        }
+    }
+}
@@ -1,15 +1,26 @@
 //! Booster NGFW Application
 use super::flash::Flash;
-use super::{platform, UsbBus};
-use crate::settings::global_settings::BoosterMainBoardData;
+use super::{
+    platform, rtc, safe_mode,
+    usb_shell::{SettingsCommand, ShellCommand},
+    user_interface, UsbBus,
+};
+use crate::settings::global_settings::{BoosterMainBoardData, IpAddr};
 use core::fmt::Write;
 use embedded_storage::nor_flash::NorFlash;
 
+/// The number of seconds that the front-panel LEDs blink when identifying the device via USB.
+const IDENTIFY_DURATION_SECS: u32 = 10;
+
 pub struct SerialSettingsPlatform {
     pub metadata: &'static crate::hardware::metadata::ApplicationMetadata,
     pub settings: BoosterMainBoardData,
     pub storage: Flash,
 
+    /// A copy of the settings that is modified instead of `settings` while the device is locked,
+    /// so that the modifications are discarded.
+    pub scratch: BoosterMainBoardData,
+
     /// The interface to read/write data to/from serially (via text) to the user.
     pub interface: serial_settings::BestEffortInterface<usbd_serial::SerialPort<'static, UsbBus>>,
 }
@@ -18,6 +29,7 @@ pub struct SerialSettingsPlatform {
 pub enum Error<F> {
     Postcard(postcard::Error),
     Flash(F),
+    Locked,
 }
 
 impl<F> From<postcard::Error> for Error<F> {
@@ -26,6 +38,62 @@ impl<F> From<postcard::Error> for Error<F> {
     }
 }
 
+impl SerialSettingsPlatform {
+    /// Print the configured network settings.
+    ///
+    /// # Note
+    /// The printed settings take effect once saved and the device is rebooted.
+    fn print_network(&mut self) {
+        let settings = &self.settings;
+        let interface = &mut self.interface;
+
+        let mode = if settings.ip.0.is_unspecified() {
+            "DHCP"
+        } else {
+            "Static"
+        };
+
+        writeln!(interface, "{:<20}: {}", "Mode", mode).ok();
+        writeln!(interface, "{:<20}: {}", "IP Address (/ip)", settings.ip).ok();
+        writeln!(
+            interface,
+            "{:<20}: {}",
+            "Netmask (/netmask)", settings.netmask
+        )
+        .ok();
+        writeln!(
+            interface,
+            "{:<20}: {}",
+            "Gateway (/gateway)", settings.gateway
+        )
+        .ok();
+        writeln!(
+            interface,
+            "{:<20}: {}:{}",
+            "Broker (/broker)", settings.broker, settings.broker_port
+        )
+        .ok();
+        writeln!(interface, "{:<20}: {}", "Identifier (/id)", settings.id).ok();
+        writeln!(
+            interface,
+            "{:<20}: {}",
+            "MAC Address",
+            settings.mac_address()
+        )
+        .ok();
+
+        if !settings.validate() {
+            writeln!(interface, "Warning: The network settings are invalid").ok();
+        }
+
+        writeln!(
+            interface,
+            "Modify with `set <path> <value>` or `platform dhcp`, then use `save` and `platform reboot` to apply"
+        )
+        .ok();
+    }
+}
+
 impl serial_settings::Platform for SerialSettingsPlatform {
     type Interface = serial_settings::BestEffortInterface<usbd_serial::SerialPort<'static, UsbBus>>;
 
@@ -34,31 +102,104 @@ impl serial_settings::Platform for SerialSettingsPlatform {
     type Error = Error<<Flash as embedded_storage::nor_flash::ErrorType>::Error>;
 
     fn save(&mut self, buffer: &mut [u8]) -> Result<(), Self::Error> {
-        let serialized = postcard::to_slice(self.settings(), buffer)?;
-        self.storage
-            .erase(0, serialized.len() as u32)
-            .map_err(Self::Error::Flash)?;
-        self.storage
-            .write(0, serialized)
-            .map_err(Self::Error::Flash)?;
-        Ok(())
+        let settings = &self.settings;
+        let storage = &mut self.storage;
+
+        persistence::write_unless_locked(
+            crate::settings::deployment_lock::is_locked(),
+            || -> Result<(), Self::Error> {
+                let serialized = postcard::to_slice(settings, buffer)?;
+                storage
+                    .erase(0, serialized.len() as u32)
+                    .map_err(Self::Error::Flash)?;
+                storage.write(0, serialized).map_err(Self::Error::Flash)?;
+                Ok(())
+            },
+        )
+        .map_err(|err| match err {
+            persistence::WriteError::Locked => Self::Error::Locked,
+            persistence::WriteError::Write(err) => err,
+        })
     }
 
     /// Execute a platform specific command.
     fn cmd(&mut self, cmd: &str) {
+        if crate::settings::deployment_lock::is_locked()
+            && matches!(
+                cmd,
+                "reboot"
+                    | "dfu"
+                    | "dhcp"
+                    | "factory-reset"
+                    | "factory-reset-keep-calibration"
+                    | "factory-reset-confirm"
+            )
+        {
+            writeln!(&mut self.interface, "Device locked").ok();
+            return;
+        }
+
         match cmd {
+            // Resets are performed by a separate task so that deferred channel settings are
+            // saved before the channels are powered off and the MCU is reset.
             "reboot" => {
-                cortex_m::interrupt::disable();
-                platform::shutdown_channels();
-                cortex_m::peripheral::SCB::sys_reset();
+                crate::app::reset::spawn(platform::ResetTarget::Firmware).ok();
             }
             "dfu" => {
-                cortex_m::interrupt::disable();
-
-                // Power off all output channels and reset the MCU.
-                platform::shutdown_channels();
-
-                platform::reset_to_dfu_bootloader();
+                crate::app::reset::spawn(platform::ResetTarget::Bootloader).ok();
+            }
+            "identify" => {
+                user_interface::identify(IDENTIFY_DURATION_SECS);
+                writeln!(
+                    &mut self.interface,
+                    "Blinking front-panel LEDs for {IDENTIFY_DURATION_SECS} seconds"
+                )
+                .ok();
+            }
+            "factory-reset" | "factory-reset-keep-calibration" => {
+                let preserve_calibration = cmd == "factory-reset-keep-calibration";
+                crate::settings::factory_reset::arm(preserve_calibration);
+                writeln!(
+                    &mut self.interface,
+                    "All settings will be erased. Confirm with `factory-reset-confirm` within 10 seconds to proceed"
+                )
+                .ok();
+            }
+            "factory-reset-confirm" => match crate::settings::factory_reset::confirm(None) {
+                Ok(preserve_calibration) => {
+                    crate::app::factory_reset::spawn(preserve_calibration).ok();
+                    writeln!(&mut self.interface, "Restoring factory defaults").ok();
+                }
+                Err(msg) => {
+                    writeln!(&mut self.interface, "{msg}").ok();
+                }
+            },
+            "network" => self.print_network(),
+            "time" => match rtc::now() {
+                Some(time) => {
+                    writeln!(&mut self.interface, "{}", rtc::DateTime::from_unix(time)).ok();
+                }
+                None => {
+                    writeln!(&mut self.interface, "Time not set").ok();
+                }
+            },
+            "dhcp" => {
+                self.settings.ip = IpAddr::new(&[0, 0, 0, 0]);
+                writeln!(
+                    &mut self.interface,
+                    "DHCP enabled. Use `save` and `platform reboot` to apply the change"
+                )
+                .ok();
+            }
+            "panic" => {
+                writeln!(
+                    &mut self.interface,
+                    "{:<20}: {}",
+                    "Panic Info",
+                    self.metadata.panic_info()
+                )
+                .ok();
+                self.metadata.clear_panic_info();
             }
             "service" => {
                 writeln!(
@@ -94,7 +235,8 @@ impl serial_settings::Platform for SerialSettingsPlatform {
                 writeln!(
                     &mut self.interface,
                     "{:<20}: {}",
-                    "Panic Info", self.metadata.panic_info
+                    "Panic Info",
+                    self.metadata.panic_info()
                 )
                 .unwrap();
                 writeln!(
@@ -103,18 +245,88 @@ impl serial_settings::Platform for SerialSettingsPlatform {
                     "Watchdog Detected", self.metadata.watchdog
                 )
                 .unwrap();
+                writeln!(
+                    &mut self.interface,
+                    "{:<20}: {:?}",
+                    "Firmware Integrity", self.metadata.firmware_integrity
+                )
+                .unwrap();
+
+                let self_test = &self.metadata.self_test;
+                writeln!(
+                    &mut self.interface,
+                    "{:<20}: {}",
+                    "Self-Test",
+                    if self_test.passed { "Passed" } else { "Failed" }
+                )
+                .unwrap();
+                writeln!(
+                    &mut self.interface,
+                    "{:<20}: EEPROM: {}, ADC: {} ({} mV), Fans: {}, Supply: {}",
+                    "",
+                    self_test.eeprom,
+                    self_test.adc,
+                    self_test.adc_reference_mv,
+                    self_test.fans,
+                    self_test.supply
+                )
+                .unwrap();
+                writeln!(&mut self.interface, "{:<20}: {:?}", "", self_test.channels).unwrap();
+
+                writeln!(
+                    &mut self.interface,
+                    "{:<20}: {}",
+                    "Safe Mode",
+                    safe_mode::is_active()
+                )
+                .unwrap();
 
                 // Use this as a mechanism for the user to "acknowledge" the service state of
-                // the device. This will allow RF channels to re-enable.
+                // the device. This resets the count of abnormal resets used to enter safe mode.
                 platform::clear_reset_flags();
+                safe_mode::clear();
             }
-            other => {
-                writeln!(
-                    self.interface_mut(),
-                    "Invalid platform command: `{other}` is not in [`dfu`, `service`, `reboot`]"
-                )
-                .ok();
+            other if other.starts_with("time:") => {
+                if crate::settings::deployment_lock::is_locked() {
+                    writeln!(&mut self.interface, "Device locked").ok();
+                    return;
+                }
+
+                let result = other["time:".len()..]
+                    .parse()
+                    .map_err(|_| "Invalid time")
+                    .and_then(rtc::set);
+                if let Err(msg) = result {
+                    writeln!(&mut self.interface, "{msg}").ok();
+                }
             }
+            other => match (ShellCommand::parse(other), SettingsCommand::parse(other)) {
+                // Channel and run-time settings commands require resources owned by other tasks,
+                // so they are executed by separate tasks.
+                (Some(command), _) => {
+                    crate::app::usb_command::spawn(command).ok();
+                }
+                (None, Some(command)) => {
+                    crate::app::usb_settings::spawn(command).ok();
+                }
+                (None, None) => {
+                    #[cfg(feature = "manufacturing")]
+                    writeln!(
+                        self.interface_mut(),
+                        "Manufacturing tests: {}",
+                        super::manufacturing::Test::USAGE
+                    )
+                    .ok();
+
+                    writeln!(
+                        self.interface_mut(),
+                        "Invalid platform command: `{other}` is not in [`dfu`, `service`, `reboot`, `identify`, `factory-reset`, `factory-reset-keep-calibration`, `factory-reset-confirm`, `network`, `dhcp`, `panic`, `time`, `time:<seconds>`, {}, {}]",
+                        ShellCommand::USAGE,
+                        SettingsCommand::USAGE,
+                    )
+                    .ok();
+                }
+            },
         }
     }
 
@@ -129,7 +341,17 @@ impl serial_settings::Platform for SerialSettingsPlatform {
     }
 
     /// Return a mutable reference to the `Settings`.
+    ///
+    /// # Note
+    /// While the device is locked, a scratch copy of the settings is returned instead, so that
+    /// any modifications are discarded.
     fn settings_mut(&mut self) -> &mut Self::Settings {
+        if crate::settings::deployment_lock::is_locked() {
+            writeln!(&mut self.interface, "Device locked, changes are discarded").ok();
+            self.scratch = self.settings.clone();
+            return &mut self.scratch;
+        }
+
         &mut self.settings
     }
 }
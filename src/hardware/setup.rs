@@ -4,10 +4,15 @@ use super::{
     booster_channels::BoosterChannels,
     chassis_fans::ChassisFans,
     delay::AsmDelay,
+    detectors::{self, Detectors},
     flash::Flash,
+    gate, memory,
     metadata::ApplicationMetadata,
-    net_interface, platform,
+    net_interface, platform, power_fail,
     rf_channel::{AdcPin, ChannelPins as RfChannelPins},
+    rtc, safe_mode,
+    self_test::SelfTest,
+    status_output::StatusOutput,
     usb,
     user_interface::{UserButtons, UserLeds},
     HardwareVersion, Mac, NetworkStack, SerialTerminal, SystemTimer, Systick, UsbBus, CPU_FREQ,
@@ -37,22 +42,17 @@ use usb_device::prelude::*;
 /// * `reflected_overdrive` - The pin ID of the input overdrive pin in GPIOE.
 /// * `output_overdrive` - The pin ID of the output overdrive pin in GPIOE.
 /// * `signal_on` - The pin ID of the signal on pin in GPIOG.
-/// * `gpioa` - The GPIO port used to instantiate analog pins.
-/// * `tx_power` - The name of the pin to instantiate for the TX power measurement.
-/// * `reflected_power` - The name of the pin to instantiate for the reflected power measurement.
 ///
 /// # Returns
 /// An option containing the RfChannelPins structure.
 macro_rules! channel_pins {
     ($gpiod:ident, $gpioe:ident, $gpiog:ident, $enable:ident, $alert:ident, $reflected_overdrive:ident,
-     $output_overdrive:ident, $signal_on:ident, $gpioa:ident, $tx_power:ident, $reflected_power:ident) => {{
+     $output_overdrive:ident, $signal_on:ident) => {{
         let enable_power = $gpiod.$enable.into_push_pull_output().erase();
         let alert = $gpiod.$alert.into_floating_input().erase();
         let reflected_overdrive = $gpioe.$reflected_overdrive.into_floating_input().erase();
         let output_overdrive = $gpioe.$output_overdrive.into_pull_down_input().erase();
         let signal_on = $gpiog.$signal_on.into_push_pull_output().erase();
-        let tx_power = AdcPin::$tx_power($gpioa.$tx_power.into_analog());
-        let reflected_power = AdcPin::$reflected_power($gpioa.$reflected_power.into_analog());
 
         RfChannelPins::new(
             enable_power,
@@ -60,16 +60,39 @@ macro_rules! channel_pins {
             reflected_overdrive,
             output_overdrive,
             signal_on,
-            tx_power,
-            reflected_power,
         )
     }};
 }
 
+/// Macro for generating the power detector pins of a channel.
+///
+/// # Args
+/// * `gpio` - The GPIO port used to instantiate analog pins.
+/// * `tx_power` - The name of the pin to instantiate for the TX power measurement.
+/// * `reflected_power` - The name of the pin to instantiate for the reflected power measurement.
+///
+/// # Returns
+/// The output and reflected power detector pins.
+macro_rules! detector_pins {
+    ($gpio:ident, $tx_power:ident, $reflected_power:ident) => {
+        (
+            AdcPin::$tx_power($gpio.$tx_power.into_analog()),
+            AdcPin::$reflected_power($gpio.$reflected_power.into_analog()),
+        )
+    };
+}
+
 /// Container method for all devices on the main I2C bus.
 pub struct MainBus {
     pub channels: BoosterChannels,
     pub fans: ChassisFans,
+    pub status_output: StatusOutput,
+
+    /// Application metadata, which is reported by control handlers.
+    pub metadata: &'static ApplicationMetadata,
+
+    /// The CRC32 of the mainboard settings loaded at startup.
+    pub settings_crc32: u32,
 }
 
 /// Configured Booster hardware devices.
@@ -77,6 +100,7 @@ pub struct BoosterDevices {
     pub leds: UserLeds,
     pub buttons: UserButtons,
     pub main_bus: MainBus,
+    pub detectors: Detectors,
     pub network_stack: NetworkStack,
     pub watchdog: hal::watchdog::IndependentWatchdog,
     pub usb_device: usb::UsbDevice,
@@ -104,6 +128,8 @@ pub fn setup(
 ) -> BoosterDevices {
     // Configure RTT logging.
     device.DBGMCU.cr.modify(|_, w| w.dbg_sleep().set_bit());
+    // Note: When using defmt, the RTT channel is provided by `defmt-rtt` instead.
+    #[cfg(not(feature = "defmt"))]
     rtt_target::rtt_init_print!();
 
     // Install the logger
@@ -111,8 +137,15 @@ pub fn setup(
         .map(|()| log::set_max_level(log::LevelFilter::Info))
         .unwrap();
 
+    // Paint the stack early to measure the stack usage of the application.
+    memory::paint_stack();
+
     log::info!("Starting initialization");
 
+    // Identify the cause of the reset before any channels are enabled.
+    platform::capture_reset_flags();
+    safe_mode::init();
+
     core.DWT.enable_cycle_counter();
     core.DCB.enable_trace();
 
@@ -130,6 +163,10 @@ pub fn setup(
         .require_pll48clk()
         .freeze();
 
+    // The RTC is started after the clocks are configured, as the oscillator start-up timeouts are
+    // measured with the cycle counter.
+    rtc::init();
+
     let systick = Systick::new(core.SYST, clocks.sysclk().to_Hz());
 
     // Start the watchdog during the initialization process.
@@ -198,16 +235,27 @@ pub fn setup(
 
     // Instantiate the I2C interface to the I2C mux. Use a shared-bus so we can share the I2C
     // bus with all of the Booster peripheral devices.
-    let channels = {
+    let (mut channels, detectors, adc_reference_mv) = {
         let pins = [
-            channel_pins!(gpiod, gpioe, gpiog, pd0, pd8, pe8, pe0, pg8, gpioa, pa0, pa1),
-            channel_pins!(gpiod, gpioe, gpiog, pd1, pd9, pe9, pe1, pg9, gpioa, pa2, pa3),
-            channel_pins!(gpiod, gpioe, gpiog, pd2, pd10, pe10, pe2, pg10, gpiof, pf6, pf7),
-            channel_pins!(gpiod, gpioe, gpiog, pd3, pd11, pe11, pe3, pg11, gpiof, pf8, pf9),
-            channel_pins!(gpiod, gpioe, gpiog, pd4, pd12, pe12, pe4, pg12, gpiof, pf10, pf3),
-            channel_pins!(gpiod, gpioe, gpiog, pd5, pd13, pe13, pe5, pg13, gpioc, pc0, pc1),
-            channel_pins!(gpiod, gpioe, gpiog, pd6, pd14, pe14, pe6, pg14, gpioc, pc2, pc3),
-            channel_pins!(gpiod, gpioe, gpiog, pd7, pd15, pe15, pe7, pg15, gpiof, pf4, pf5),
+            channel_pins!(gpiod, gpioe, gpiog, pd0, pd8, pe8, pe0, pg8),
+            channel_pins!(gpiod, gpioe, gpiog, pd1, pd9, pe9, pe1, pg9),
+            channel_pins!(gpiod, gpioe, gpiog, pd2, pd10, pe10, pe2, pg10),
+            channel_pins!(gpiod, gpioe, gpiog, pd3, pd11, pe11, pe3, pg11),
+            channel_pins!(gpiod, gpioe, gpiog, pd4, pd12, pe12, pe4, pg12),
+            channel_pins!(gpiod, gpioe, gpiog, pd5, pd13, pe13, pe5, pg13),
+            channel_pins!(gpiod, gpioe, gpiog, pd6, pd14, pe14, pe6, pg14),
+            channel_pins!(gpiod, gpioe, gpiog, pd7, pd15, pe15, pe7, pg15),
+        ];
+
+        let detector_pins = [
+            detector_pins!(gpioa, pa0, pa1),
+            detector_pins!(gpioa, pa2, pa3),
+            detector_pins!(gpiof, pf6, pf7),
+            detector_pins!(gpiof, pf8, pf9),
+            detector_pins!(gpiof, pf10, pf3),
+            detector_pins!(gpioc, pc0, pc1),
+            detector_pins!(gpioc, pc2, pc3),
+            detector_pins!(gpiof, pf4, pf5),
         ];
 
         let mut mux = {
@@ -222,13 +270,40 @@ pub fn setup(
         // Test scanning and reading back MUX channels.
         assert!(mux.self_test().unwrap());
 
+        // The ADCs share the nominally 2.5V VREF+ reference. The actual reference voltage is
+        // calibrated by measuring the internal VREFINT reference (which is only connected to
+        // ADC1) against its factory calibration value, correcting for the reference tolerance
+        // of each unit.
         let config = hal::adc::config::AdcConfig::default().reference_voltage(2500);
+        let reference_voltage = {
+            let mut adc1 = hal::adc::Adc::adc1(device.ADC1, true, config);
+            adc1.calibrate();
+            adc1.reference_voltage()
+        };
+        log::info!("Calibrated ADC reference: {} mV", reference_voltage);
 
+        let config = config.reference_voltage(reference_voltage);
         let adc = hal::adc::Adc::adc3(device.ADC3, true, config);
 
-        BoosterChannels::new(mux, adc, i2c_bus_manager, pins, clock, delay.clone())
+        (
+            BoosterChannels::new(mux, i2c_bus_manager, pins, clock, delay.clone()),
+            Detectors::new(adc, detector_pins),
+            reference_voltage,
+        )
     };
 
+    // Start sampling the power detectors in the fast measurement path.
+    detectors::start(clocks.timclk1().to_Hz());
+
+    // Shut down channels if the supply fails.
+    power_fail::enable();
+
+    // The gate input is pulled down, so that an unconnected input closes an active-high gate.
+    gpiog.pg6.into_pull_down_input();
+    gate::enable();
+
+    let status_output = StatusOutput::new(gpiog.pg7.into_push_pull_output().erase());
+
     let buttons = {
         let button1 = gpiof.pf14.into_floating_input();
         let button2 = gpiof.pf15.into_floating_input();
@@ -291,6 +366,17 @@ pub fn setup(
     // Read the EUI48 identifier and configure the ethernet MAC address.
     let mut settings = BoosterSettings::new(eeprom);
 
+    let mut flash = {
+        let flash = stm32f4xx_hal::flash::LockedFlash::new(device.FLASH);
+        const SECTOR_SIZE: usize = 128 * 1024;
+        Flash::new(flash, 7 * SECTOR_SIZE)
+    };
+
+    // Attempt to load flash settings. These must be loaded before the network is configured.
+    settings.properties.reload(&mut flash);
+
+    let ethernet_address = settings.properties.mac_address().0;
+
     let mut mac = {
         let mut spi = {
             let mode = hal::spi::Mode {
@@ -352,7 +438,7 @@ pub fn setup(
 
             let w5500 = w5500::UninitializedDevice::new(w5500::bus::FourWire::new(spi, cs))
                 .initialize_macraw(w5500::MacAddress {
-                    octets: mac_address,
+                    octets: ethernet_address,
                 })
                 .unwrap();
 
@@ -360,43 +446,12 @@ pub fn setup(
         } else {
             let mut mac = enc424j600::Enc424j600::new(spi, cs).cpu_freq_mhz(CPU_FREQ / 1_000_000);
             mac.init(&mut delay).expect("PHY initialization failed");
-            mac.write_mac_addr(&mac_address).unwrap();
+            mac.write_mac_addr(&ethernet_address).unwrap();
 
             Mac::Enc424j600(mac)
         }
     };
 
-    let metadata = {
-        // Read the hardware version pins.
-        let hardware_version = {
-            let hwrev0 = gpiof.pf0.into_pull_down_input();
-            let hwrev1 = gpiof.pf1.into_pull_down_input();
-            let hwrev2 = gpiof.pf2.into_pull_down_input();
-
-            HardwareVersion::from(
-                *0u8.set_bit(0, hwrev0.is_high())
-                    .set_bit(1, hwrev1.is_high())
-                    .set_bit(2, hwrev2.is_high()),
-            )
-        };
-
-        let phy_string = match mac {
-            Mac::W5500(_) => "W5500",
-            Mac::Enc424j600(_) => "Enc424j600",
-        };
-
-        ApplicationMetadata::new(hardware_version, phy_string)
-    };
-
-    let mut rng = device.RNG.constrain(&clocks);
-
-    let (interface, sockets) = net_interface::setup(&mut mac, &settings, rng.next_u64());
-    let mut network_stack = smoltcp_nal::NetworkStack::new(interface, mac, sockets, clock);
-
-    let mut seed_bytes = [0; 8];
-    rng.fill_bytes(&mut seed_bytes);
-    network_stack.seed_random_port(&seed_bytes);
-
     let mut fans = {
         let main_board_leds = {
             let mut led1 = gpioc.pc8.into_push_pull_output();
@@ -426,7 +481,46 @@ pub fn setup(
         )
     };
 
-    assert!(fans.self_test(&mut delay));
+    let fans_passed = fans.self_test(&mut delay);
+
+    let self_test = SelfTest::run(
+        &mut channels,
+        settings.eeprom_valid(),
+        adc_reference_mv,
+        fans_passed,
+    );
+    self_test.log();
+
+    let metadata = {
+        // Read the hardware version pins.
+        let hardware_version = {
+            let hwrev0 = gpiof.pf0.into_pull_down_input();
+            let hwrev1 = gpiof.pf1.into_pull_down_input();
+            let hwrev2 = gpiof.pf2.into_pull_down_input();
+
+            HardwareVersion::from(
+                *0u8.set_bit(0, hwrev0.is_high())
+                    .set_bit(1, hwrev1.is_high())
+                    .set_bit(2, hwrev2.is_high()),
+            )
+        };
+
+        let phy_string = match mac {
+            Mac::W5500(_) => "W5500",
+            Mac::Enc424j600(_) => "Enc424j600",
+        };
+
+        ApplicationMetadata::new(hardware_version, phy_string, mac_address, self_test)
+    };
+
+    let mut rng = device.RNG.constrain(&clocks);
+
+    let (interface, sockets) = net_interface::setup(&mut mac, &settings, rng.next_u64());
+    let mut network_stack = smoltcp_nal::NetworkStack::new(interface, mac, sockets, clock);
+
+    let mut seed_bytes = [0; 8];
+    rng.fill_bytes(&mut seed_bytes);
+    network_stack.seed_random_port(&seed_bytes);
 
     // Set up the USB bus.
     let (usb_device, usb_serial) = {
@@ -450,6 +544,10 @@ pub fn setup(
 
         usb_bus.replace(hal::otg_fs::UsbBus::new(usb, &mut endpoint_memory[..]));
 
+        // Note: Logs share the single serial port with the terminal. Each CDC-ACM interface
+        // requires a notification and a data IN endpoint, but the OTG_FS peripheral only provides
+        // three IN endpoints in addition to the control endpoint, so a second port dedicated to
+        // logs cannot be enumerated.
         let usb_serial = usbd_serial::SerialPort::new(usb_bus.as_ref().unwrap());
 
         // Generate a device serial number from the MAC address.
@@ -486,15 +584,6 @@ pub fn setup(
     };
 
     let serial_terminal = {
-        let mut flash = {
-            let flash = stm32f4xx_hal::flash::LockedFlash::new(device.FLASH);
-            const SECTOR_SIZE: usize = 128 * 1024;
-            Flash::new(flash, 7 * SECTOR_SIZE)
-        };
-
-        // Attempt to load flash settings
-        settings.properties.reload(&mut flash);
-
         let input_buffer = cortex_m::singleton!(:[u8; 256] = [0u8; 256]).unwrap();
         let serialize_buffer = cortex_m::singleton!(:[u8; 512] = [0u8; 512]).unwrap();
 
@@ -504,6 +593,7 @@ pub fn setup(
                 interface: serial_settings::BestEffortInterface::new(usb_serial),
                 storage: flash,
                 settings: settings.properties.clone(),
+                scratch: settings.properties.clone(),
             },
             input_buffer,
             serialize_buffer,
@@ -518,7 +608,14 @@ pub fn setup(
         buttons,
         // Note: These devices are within a containing structure because they exist on the same
         // shared I2C bus.
-        main_bus: MainBus { channels, fans },
+        main_bus: MainBus {
+            channels,
+            fans,
+            status_output,
+            metadata,
+            settings_crc32: settings.properties.crc32(),
+        },
+        detectors,
         network_stack,
         settings,
         usb_device,
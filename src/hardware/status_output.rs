@@ -0,0 +1,74 @@
+//! Booster channel status output
+//!
+//! # Design
+//! The spare PG7 GPIO is driven as a status output, so that external safety equipment (e.g. a PLC)
+//! can monitor Booster with a single wire. The output is asserted (driven high) only while all
+//! selected channels are enabled, i.e. powered with their RF output enabled and without a tripped
+//! interlock or fault. It is never asserted if no channels are selected.
+//!
+//! The output is deasserted when channels are shut down (e.g. on a power failure or panic) and is
+//! high-impedance during reset, so it should be pulled down externally.
+use super::{hal, power_fail, Channel};
+use hal::gpio::Output;
+
+/// The GPIOG pin used as the status output.
+pub const STATUS_PIN: u32 = 7;
+
+/// Provides control of the channel status output.
+pub struct StatusOutput {
+    pin: hal::gpio::EPin<Output>,
+    channels: u8,
+    enabled: u8,
+}
+
+impl StatusOutput {
+    /// Create a new status output.
+    ///
+    /// # Args
+    /// * `pin` - The status output pin.
+    pub fn new(mut pin: hal::gpio::EPin<Output>) -> Self {
+        pin.set_low();
+        Self {
+            pin,
+            channels: 0,
+            enabled: 0,
+        }
+    }
+
+    /// Configure the channels that are monitored by the status output.
+    ///
+    /// # Args
+    /// * `channels` - A bit mask of the monitored channels. Bit 0 corresponds to channel 0.
+    pub fn set_channels(&mut self, channels: u8) {
+        self.channels = channels;
+        self.apply();
+    }
+
+    /// Update the status output with the state of a channel.
+    ///
+    /// # Args
+    /// * `channel` - The channel whose state was updated.
+    /// * `enabled` - Specified true if the channel is enabled.
+    pub fn update(&mut self, channel: Channel, enabled: bool) {
+        let mask = 1 << channel as u8;
+        if enabled {
+            self.enabled |= mask;
+        } else {
+            self.enabled &= !mask;
+        }
+
+        self.apply();
+    }
+
+    fn apply(&mut self) {
+        // Channels are powered off directly on a power failure, so their state is no longer valid.
+        if self.channels != 0
+            && self.enabled & self.channels == self.channels
+            && !power_fail::is_detected()
+        {
+            self.pin.set_high();
+        } else {
+            self.pin.set_low();
+        }
+    }
+}
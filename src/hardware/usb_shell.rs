@@ -0,0 +1,479 @@
+//! Booster USB command shell
+//!
+//! # Design
+//! The USB serial terminal provides line editing, the `get`/`set`/`save` settings commands, and
+//! dispatches `platform <command>` requests to the platform. Commands that require access to the
+//! RF channels are parsed here and executed from a separate task that owns the main bus.
+//!
+//! Because the terminal splits arguments on whitespace, channel commands use `:` to separate
+//! their arguments, e.g. `platform bias:0:-1.5`.
+use super::{setup::MainBus, Channel};
+use crate::{
+    net::SettingsClient,
+    settings::{
+        channel_settings::ChannelState, deployment_lock, runtime_settings::RuntimeSettings,
+    },
+};
+use core::{
+    fmt::Write,
+    str::FromStr,
+    sync::atomic::{AtomicU32, Ordering},
+};
+use heapless::String;
+use miniconf::{JsonCoreSlash, TreeKey};
+use serde::Serialize;
+
+/// The minimum period of streamed telemetry in milliseconds.
+const MIN_STREAM_PERIOD_MS: u32 = 100;
+
+/// The period of streamed telemetry in milliseconds. Telemetry is not streamed if zero.
+static STREAM_PERIOD_MS: AtomicU32 = AtomicU32::new(0);
+
+/// A telemetry record streamed via USB.
+#[derive(Serialize)]
+struct StreamRecord {
+    /// The time since startup in milliseconds.
+    time: u64,
+    channel: Channel,
+    status: super::rf_channel::ChannelStatus,
+}
+
+/// The commands of the USB shell that operate on the RF channels.
+#[derive(Copy, Clone, Debug)]
+pub enum ShellCommand {
+    /// Display a table of the state and measurements of all channels.
+    Status,
+
+    /// Enable the output of a channel.
+    Enable(Channel),
+
+    /// Disable the output of a channel.
+    Disable(Channel),
+
+    /// Set the bias voltage of a channel.
+    Bias(Channel, f32),
+
+    /// Save the settings of a channel to EEPROM.
+    Save(Channel),
+
+    /// Stream telemetry with the specified period in milliseconds. Streaming stops if zero.
+    Stream(u32),
+
+    /// Scan the shared I2C bus and the bus segment of each channel for responding devices.
+    I2cScan,
+
+    /// Execute a manufacturing test.
+    #[cfg(feature = "manufacturing")]
+    Manufacturing(super::manufacturing::Test),
+}
+
+impl ShellCommand {
+    /// The usage of all shell commands.
+    pub const USAGE: &'static str =
+        "status, enable:<channel>, disable:<channel>, bias:<channel>:<volts>, save:<channel>, stream:<ms>, i2c-scan";
+
+    /// Parse a shell command.
+    ///
+    /// # Args
+    /// * `command` - The command provided to the platform, e.g. `bias:0:-1.5`.
+    ///
+    /// # Returns
+    /// The command if it is a valid shell command. None otherwise.
+    pub fn parse(command: &str) -> Option<Self> {
+        #[cfg(feature = "manufacturing")]
+        if let Some(test) = super::manufacturing::Test::parse(command) {
+            return Some(Self::Manufacturing(test));
+        }
+
+        if let Some(period) = command.strip_prefix("stream:") {
+            return period.parse().ok().map(Self::Stream);
+        }
+
+        let mut args = command.split(':');
+        let name = args.next()?;
+        let channel = args.next().map(parse_channel);
+        let value = args.next();
+
+        if args.next().is_some() {
+            return None;
+        }
+
+        let command = match (name, channel, value) {
+            ("status", None, None) => Self::Status,
+            ("i2c-scan", None, None) => Self::I2cScan,
+            ("enable", Some(channel), None) => Self::Enable(channel?),
+            ("disable", Some(channel), None) => Self::Disable(channel?),
+            ("bias", Some(channel), Some(voltage)) => Self::Bias(channel?, voltage.parse().ok()?),
+            ("save", Some(channel), None) => Self::Save(channel?),
+            _ => return None,
+        };
+
+        Some(command)
+    }
+
+    /// Check if the command modifies the device configuration.
+    fn is_state_changing(&self) -> bool {
+        !matches!(self, Self::Status | Self::Stream(_) | Self::I2cScan)
+    }
+}
+
+/// The commands of the USB shell that operate on the run-time settings.
+///
+/// # Note
+/// The full settings JSON exceeds the terminal line length, so settings are dumped as one command
+/// per setting that restores the setting when entered into the terminal again.
+#[derive(Clone, Debug)]
+pub enum SettingsCommand {
+    /// Print the JSON value of all run-time settings.
+    Dump,
+
+    /// Set the JSON value of a single run-time setting.
+    Load {
+        path: String<64>,
+        value: String<192>,
+    },
+}
+
+impl SettingsCommand {
+    /// The usage of all settings commands.
+    pub const USAGE: &'static str = "settings-dump, settings-load:<path>=<json>";
+
+    /// Parse a settings command.
+    ///
+    /// # Args
+    /// * `command` - The command provided to the platform, e.g. `settings-load:/fan_speed=0.3`.
+    ///
+    /// # Returns
+    /// The command if it is a valid settings command. None otherwise.
+    pub fn parse(command: &str) -> Option<Self> {
+        if command == "settings-dump" {
+            return Some(Self::Dump);
+        }
+
+        let (path, value) = command.strip_prefix("settings-load:")?.split_once('=')?;
+        Some(Self::Load {
+            path: String::from_str(path).ok()?,
+            value: String::from_str(value).ok()?,
+        })
+    }
+}
+
+/// Execute a settings command.
+///
+/// # Args
+/// * `settings` - The run-time settings client.
+/// * `command` - The command to execute.
+/// * `output` - The terminal to write the command output to.
+///
+/// # Returns
+/// True if the run-time settings were modified.
+pub fn execute_settings(
+    settings: &mut SettingsClient,
+    command: SettingsCommand,
+    output: &mut impl Write,
+) -> bool {
+    let (path, value) = match command {
+        SettingsCommand::Dump => {
+            let mut buffer = [0u8; 192];
+            for path in RuntimeSettings::iter_paths::<String<64>>("/") {
+                let Ok(path) = path else {
+                    continue;
+                };
+
+                // Settings that are absent (e.g. for channels that are not installed) are omitted.
+                if let Ok(len) = settings.settings().get_json(&path, &mut buffer) {
+                    let value = core::str::from_utf8(&buffer[..len]).unwrap_or_default();
+                    writeln!(output, "platform settings-load:{path}={value}").ok();
+                }
+            }
+            return false;
+        }
+        SettingsCommand::Load { path, value } => (path, value),
+    };
+
+    if deployment_lock::is_locked() {
+        writeln!(output, "Device locked").ok();
+        return false;
+    }
+
+    let mut new_settings = settings.settings().clone();
+    if new_settings.set_json(&path, value.as_bytes()).is_err() {
+        writeln!(output, "Invalid setting path or value: {path}").ok();
+        return false;
+    }
+
+    if let Err(err) = RuntimeSettings::handle_update(&path, settings.settings_mut(), &new_settings)
+    {
+        writeln!(output, "Failed to update {path}: {err}").ok();
+        return false;
+    }
+
+    // Ensure that the settings reported via MQTT reflect the update.
+    settings.force_republish();
+
+    writeln!(output, "Updated {path}").ok();
+    true
+}
+
+/// Parse a channel index.
+fn parse_channel(index: &str) -> Option<Channel> {
+    let index: usize = index.trim().parse().ok()?;
+    enum_iterator::all::<Channel>().nth(index)
+}
+
+/// Execute a shell command.
+///
+/// # Args
+/// * `main_bus` - The main I2C bus to communicate with RF channels.
+/// * `settings` - The run-time settings client.
+/// * `command` - The command to execute.
+/// * `output` - The terminal to write the command output to.
+///
+/// # Returns
+/// True if the run-time settings were modified.
+pub fn execute(
+    main_bus: &mut MainBus,
+    settings: &mut SettingsClient,
+    command: ShellCommand,
+    output: &mut impl Write,
+) -> bool {
+    if command.is_state_changing() && deployment_lock::is_locked() {
+        writeln!(output, "Device locked").ok();
+        return false;
+    }
+
+    let channel = match command {
+        ShellCommand::Status => {
+            print_status(main_bus, output);
+            return false;
+        }
+        ShellCommand::I2cScan => {
+            print_i2c_scan(main_bus, output);
+            return false;
+        }
+        ShellCommand::Stream(period) => {
+            if period == 0 {
+                STREAM_PERIOD_MS.store(0, Ordering::Relaxed);
+                writeln!(output, "Telemetry streaming stopped").ok();
+                return false;
+            }
+
+            let period = period.max(MIN_STREAM_PERIOD_MS);
+            STREAM_PERIOD_MS.store(period, Ordering::Relaxed);
+            writeln!(output, "Streaming telemetry every {period} ms").ok();
+
+            // Note: If streaming is already active, the pending stream task applies the new period.
+            crate::app::usb_stream::spawn().ok();
+            return false;
+        }
+        #[cfg(feature = "manufacturing")]
+        ShellCommand::Manufacturing(test) => {
+            super::manufacturing::execute(&mut main_bus.channels, test, output);
+            return false;
+        }
+        ShellCommand::Save(channel) => {
+            let Some(channel) = main_bus.channels.channel_mut(channel) else {
+                writeln!(output, "Channel not found").ok();
+                return false;
+            };
+
+            channel.context_mut().save_configuration();
+            writeln!(output, "Saving channel settings").ok();
+            return false;
+        }
+        ShellCommand::Enable(channel)
+        | ShellCommand::Disable(channel)
+        | ShellCommand::Bias(channel, _) => channel,
+    };
+
+    // Channel settings are updated via the run-time settings so that they are validated and rate
+    // limited in the same manner as updates via the network.
+    let mut new_settings = settings.settings().clone();
+    let Some(channel_settings) = new_settings.channel[channel as usize].as_mut() else {
+        writeln!(output, "Channel not found").ok();
+        return false;
+    };
+
+    match command {
+        ShellCommand::Enable(_) => channel_settings.state = ChannelState::Enabled,
+        ShellCommand::Disable(_) => channel_settings.state = ChannelState::Off,
+        ShellCommand::Bias(_, voltage) => channel_settings.bias_voltage = voltage,
+        ShellCommand::Status
+        | ShellCommand::Stream(_)
+        | ShellCommand::I2cScan
+        | ShellCommand::Save(_) => unreachable!(),
+        #[cfg(feature = "manufacturing")]
+        ShellCommand::Manufacturing(_) => unreachable!(),
+    }
+
+    if let Err(err) =
+        RuntimeSettings::handle_update("/channel", settings.settings_mut(), &new_settings)
+    {
+        writeln!(output, "Failed: {err}").ok();
+        return false;
+    }
+
+    // Ensure that the settings reported via MQTT reflect the update.
+    settings.force_republish();
+
+    writeln!(output, "OK").ok();
+    true
+}
+
+/// Stream the telemetry of all installed channels as newline-delimited JSON.
+///
+/// # Args
+/// * `main_bus` - The main I2C bus to communicate with RF channels.
+/// * `output` - The terminal to write the telemetry to.
+///
+/// # Returns
+/// The period in milliseconds after which telemetry should be streamed again. None if streaming
+/// has stopped.
+pub fn stream_telemetry(main_bus: &mut MainBus, output: &mut impl Write) -> Option<u32> {
+    let period = STREAM_PERIOD_MS.load(Ordering::Relaxed);
+    if period == 0 {
+        return None;
+    }
+
+    let time = crate::app::monotonics::now().ticks();
+    let mut buffer = [0u8; 768];
+
+    for idx in enum_iterator::all::<Channel>() {
+        let Some(channel) = main_bus.channels.channel_mut(idx) else {
+            continue;
+        };
+
+        let Ok(status) = channel.get_status() else {
+            writeln!(output, "[ERROR] Failed to measure channel {}", idx as usize).ok();
+            continue;
+        };
+
+        let record = StreamRecord {
+            time,
+            channel: idx,
+            status,
+        };
+
+        if let Ok(len) = serde_json_core::to_slice(&record, &mut buffer) {
+            let line = core::str::from_utf8(&buffer[..len]).unwrap_or_default();
+            writeln!(output, "{line}").ok();
+        }
+    }
+
+    Some(period)
+}
+
+/// Print a table of the status of all channels.
+///
+/// # Note
+/// All measurements are sampled when the table is printed.
+///
+/// # Args
+/// * `main_bus` - The main I2C bus to communicate with RF channels.
+/// * `output` - The terminal to write the table to.
+fn print_status(main_bus: &mut MainBus, output: &mut impl Write) {
+    writeln!(
+        output,
+        "{:<3} {:<24} {:>7} {:>8} {:>8} {:>8} {:>7} {:>7} {:>8} {:>8} {:>6} {:<5}",
+        "Ch",
+        "State",
+        "Temp",
+        "Input",
+        "Output",
+        "Refl",
+        "I(28V)",
+        "I(5V)",
+        "OThresh",
+        "RThresh",
+        "Trips",
+        "Flags"
+    )
+    .ok();
+    writeln!(
+        output,
+        "{:<3} {:<24} {:>7} {:>8} {:>8} {:>8} {:>7} {:>7} {:>8} {:>8} {:>6}",
+        "", "", "[C]", "[dBm]", "[dBm]", "[dBm]", "[A]", "[A]", "[dBm]", "[dBm]", ""
+    )
+    .ok();
+
+    for (idx, channel) in enum_iterator::all::<Channel>().enumerate() {
+        let Some(channel) = main_bus.channels.channel_mut(channel) else {
+            writeln!(output, "{idx:<3} {:<24}", "Not installed").ok();
+            continue;
+        };
+
+        let Ok(status) = channel.get_status() else {
+            writeln!(output, "{idx:<3} {:<24}", channel.state().name()).ok();
+            continue;
+        };
+        let context = channel.context_mut();
+
+        // The interlock thresholds are read back from the DAC so that they reflect the hardware
+        // state. NaN is displayed if the DAC cannot be read.
+        let output_threshold = context.get_output_interlock_threshold().unwrap_or(f32::NAN);
+        let reflected_threshold = context
+            .get_reflected_interlock_threshold()
+            .unwrap_or(f32::NAN);
+
+        // Flags indicate the overdrive detector and supply alert states, which identify the
+        // cause of interlock trips.
+        let mut flags: heapless::String<5> = heapless::String::new();
+        for (set, flag) in [
+            (status.output_overdrive, 'O'),
+            (status.reflected_overdrive, 'R'),
+            (status.alert, 'A'),
+            (status.unsaved, '*'),
+        ] {
+            if set {
+                flags.push(flag).ok();
+            }
+        }
+
+        writeln!(
+            output,
+            "{:<3} {:<24} {:>7.1} {:>8.2} {:>8.2} {:>8.2} {:>7.3} {:>7.3} {:>8.2} {:>8.2} {:>6} {:<5}",
+            idx,
+            status.state.name(),
+            status.temperature,
+            status.input_power,
+            status.output_power,
+            status.reflected_power,
+            status.p28v_current,
+            status.p5v_current,
+            output_threshold,
+            reflected_threshold,
+            context.interlock_trips(),
+            flags,
+        )
+        .ok();
+    }
+
+    writeln!(
+        output,
+        "Flags: O = output overdrive, R = reflected overdrive, A = supply alert, * = unsaved"
+    )
+    .ok();
+}
+
+/// Print the I2C addresses of the devices responding on the shared bus and each bus segment.
+///
+/// # Args
+/// * `main_bus` - The main I2C bus to communicate with RF channels.
+/// * `output` - The terminal to write the scan to.
+fn print_i2c_scan(main_bus: &mut MainBus, output: &mut impl Write) {
+    let scan = main_bus.channels.scan_i2c();
+
+    write!(output, "{:<8}:", "Shared").ok();
+    for address in scan.shared.iter() {
+        write!(output, " 0x{address:02x}").ok();
+    }
+    writeln!(output).ok();
+
+    for (idx, addresses) in scan.channels.iter().enumerate() {
+        write!(output, "{:<8}:", idx).ok();
+        for address in addresses.iter() {
+            write!(output, " 0x{address:02x}").ok();
+        }
+        writeln!(output).ok();
+    }
+}
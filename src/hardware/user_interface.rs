@@ -2,15 +2,50 @@
 
 use super::Channel;
 use bit_field::BitField;
+use core::sync::atomic::{AtomicU32, Ordering};
 use hal::hal::digital::v2::InputPin;
 use stm32f4xx_hal as hal;
 
 use debounced_pin::{Debounce, DebounceState, DebouncedInputPin};
 
+/// The number of consecutive button updates a button must be held active to register a long
+/// press. Buttons are updated every 3ms, so this corresponds with approximately 3 seconds.
+const LONG_PRESS_UPDATES: u32 = 1000;
+
+/// The number of LED updates per second. LEDs are updated by the channel monitor at 10Hz.
+const LED_UPDATES_PER_SECOND: u32 = 10;
+
+/// The number of remaining LED updates during which the front panel identifies the device.
+static IDENTIFY_UPDATES: AtomicU32 = AtomicU32::new(0);
+
+/// Blink all front-panel LEDs to identify the device.
+///
+/// # Args
+/// * `duration_secs` - The number of seconds to blink the LEDs for. Zero stops identification.
+pub fn identify(duration_secs: u32) {
+    IDENTIFY_UPDATES.store(
+        duration_secs.saturating_mul(LED_UPDATES_PER_SECOND),
+        Ordering::Relaxed,
+    );
+}
+
 /// Represents an event indicated through the GPIO buttons.
 pub enum ButtonEvent {
     InterlockReset,
     Standby,
+
+    /// The "Standby" button was held. All channels should be disabled and locked out from being
+    /// re-enabled until the lockout is released locally.
+    LockoutEngage,
+
+    /// The "Interlock Reset" button was held. Any local lockout should be released.
+    LockoutRelease,
+}
+
+/// Represents the type of activation detected on a button.
+enum Press {
+    Short,
+    Long,
 }
 
 type Button1 = hal::gpio::gpiof::PF14<hal::gpio::Input>;
@@ -45,15 +80,17 @@ impl UserButtons {
     /// An option containing any event that is indicated by the button update.
     pub fn update(&mut self) -> Option<ButtonEvent> {
         // Prioritize entering standby.
-        if self.button2.update() {
-            return Some(ButtonEvent::Standby);
+        match self.button2.update() {
+            Some(Press::Short) => return Some(ButtonEvent::Standby),
+            Some(Press::Long) => return Some(ButtonEvent::LockoutEngage),
+            None => {}
         }
 
-        if self.button1.update() {
-            return Some(ButtonEvent::InterlockReset);
+        match self.button1.update() {
+            Some(Press::Short) => Some(ButtonEvent::InterlockReset),
+            Some(Press::Long) => Some(ButtonEvent::LockoutRelease),
+            None => None,
         }
-
-        None
     }
 }
 
@@ -65,6 +102,7 @@ where
 {
     button: DebouncedInputPin<INPUT, debounced_pin::ActiveLow>,
     was_active: bool,
+    held_updates: u32,
 }
 
 impl<INPUT, E> InputButton<INPUT, E>
@@ -76,26 +114,40 @@ where
     pub fn new(button: INPUT) -> Self {
         InputButton {
             was_active: false,
+            held_updates: 0,
             button: DebouncedInputPin::new(button, debounced_pin::ActiveLow),
         }
     }
 
     /// Periodically check the state of the input button.
     ///
+    /// # Note
+    /// A short press is indicated as soon as the button activates. If the button remains held, a
+    /// long press is additionally indicated once.
+    ///
     /// # Returns
-    /// True if the debounced button state has encountered an activation.
-    pub fn update(&mut self) -> bool {
+    /// The type of activation the debounced button state has encountered, if any.
+    pub fn update(&mut self) -> Option<Press> {
         match self.button.update().unwrap() {
             DebounceState::Active => {
-                let result = !self.was_active;
-                self.was_active = true;
-                result
+                if !self.was_active {
+                    self.was_active = true;
+                    self.held_updates = 0;
+                    return Some(Press::Short);
+                }
+
+                self.held_updates = self.held_updates.saturating_add(1);
+                if self.held_updates == LONG_PRESS_UPDATES {
+                    Some(Press::Long)
+                } else {
+                    None
+                }
             }
             DebounceState::NotActive => {
                 self.was_active = false;
-                false
+                None
             }
-            _ => false,
+            _ => None,
         }
     }
 }
@@ -141,11 +193,24 @@ impl UserLeds {
     }
 
     /// Write the LED state to the LED outputs.
+    ///
+    /// # Note
+    /// While the device is being identified, all LEDs blink at 1Hz instead of reflecting the LED
+    /// state.
     pub fn update(&mut self) {
+        let identify =
+            IDENTIFY_UPDATES.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |updates| {
+                updates.checked_sub(1)
+            });
+
+        let leds = match identify {
+            Ok(updates) if (updates / (LED_UPDATES_PER_SECOND / 2)) % 2 == 0 => [0xFF; 3],
+            Ok(_) => [0; 3],
+            Err(_) => [self.green, self.yellow, self.red],
+        };
+
         self.spi_csn.set_low();
-        self.spi
-            .write(&[self.green, self.yellow, self.red])
-            .unwrap();
+        self.spi.write(&leds).unwrap();
         self.spi_csn.set_high();
     }
 
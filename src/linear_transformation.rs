@@ -22,6 +22,31 @@ impl LinearTransformation {
         LinearTransformation { slope, offset }
     }
 
+    /// Get the inverse transformation, which maps values from the Y-domain into the X-domain.
+    ///
+    /// # Note
+    /// The transformation must be invertible, see [LinearTransformation::is_valid].
+    pub fn invert(&self) -> Self {
+        LinearTransformation {
+            slope: 1.0 / self.slope,
+            offset: -self.offset / self.slope,
+        }
+    }
+
+    /// Chain two transformations.
+    ///
+    /// # Note
+    /// The resulting transformation is equivalent to mapping a value by `self` followed by `next`.
+    ///
+    /// # Args
+    /// * `next` - The transformation applied to the output of this transformation.
+    pub fn compose(&self, next: &Self) -> Self {
+        LinearTransformation {
+            slope: self.slope * next.slope,
+            offset: self.offset * next.slope + next.offset,
+        }
+    }
+
     /// Convert a value from the Y-domain into the X-domain.
     ///
     /// # Note
@@ -29,7 +54,7 @@ impl LinearTransformation {
     ///
     /// # Args
     /// * `vertical` - The Y-axis value to convert into the X-axis.
-    pub fn invert(&self, vertical: f32) -> f32 {
+    pub fn apply_inverse(&self, vertical: f32) -> f32 {
         (vertical - self.offset) / self.slope
     }
 
@@ -43,4 +68,9 @@ impl LinearTransformation {
     pub fn map(&self, horizontal: f32) -> f32 {
         horizontal * self.slope + self.offset
     }
+
+    /// Check that the transformation is finite and invertible.
+    pub fn is_valid(&self) -> bool {
+        self.slope.is_finite() && self.offset.is_finite() && self.slope != 0.0
+    }
 }
@@ -3,15 +3,57 @@ use heapless::String;
 
 use super::SerialTerminal;
 use core::fmt::Write;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use serde::{Deserialize, Serialize};
+
+/// The maximum level of log messages written to the RTT output.
+const LOCAL_LEVEL: log::LevelFilter = log::LevelFilter::Info;
+
+/// Specifies the maximum level of log messages forwarded to a log sink.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for log::LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Off => log::LevelFilter::Off,
+            LogLevel::Error => log::LevelFilter::Error,
+            LogLevel::Warn => log::LevelFilter::Warn,
+            LogLevel::Info => log::LevelFilter::Info,
+            LogLevel::Debug => log::LevelFilter::Debug,
+            LogLevel::Trace => log::LevelFilter::Trace,
+        }
+    }
+}
+
+/// A log record pending transmission to the remote syslog server.
+pub struct SyslogRecord {
+    pub level: log::Level,
+    pub message: String<256>,
+
+    /// The UNIX time at which the message was logged, if the clock has been set.
+    pub timestamp: Option<u32>,
+}
 
 /// A logging buffer for storing serialized logs pending transmission.
 ///
 /// # Notes
 /// The BufferedLog contains a character buffer of the log data waiting to be written. It is
 /// intended to be consumed asynchronously. In the case of booster, this log data is consumed in the
-/// USB task.
+/// USB task. Logging never blocks - records are dropped if the buffer is full.
 pub struct BufferedLog {
     logs: heapless::mpmc::Q16<heapless::String<256>>,
+    syslog: heapless::mpmc::Q8<SyslogRecord>,
+    syslog_level: AtomicUsize,
+    usb_level: AtomicUsize,
+    #[cfg(not(feature = "defmt"))]
     rtt_logger: rtt_logger::RTTLogger,
 }
 
@@ -20,10 +62,60 @@ impl BufferedLog {
     pub const fn new() -> Self {
         Self {
             logs: heapless::mpmc::Q16::new(),
-            rtt_logger: rtt_logger::RTTLogger::new(log::LevelFilter::Info),
+            syslog: heapless::mpmc::Q8::new(),
+            syslog_level: AtomicUsize::new(log::LevelFilter::Off as usize),
+            usb_level: AtomicUsize::new(LOCAL_LEVEL as usize),
+            #[cfg(not(feature = "defmt"))]
+            rtt_logger: rtt_logger::RTTLogger::new(LOCAL_LEVEL),
         }
     }
 
+    /// Configure the maximum level of log messages forwarded to the remote syslog server.
+    ///
+    /// # Args
+    /// * `level` - The maximum level of forwarded log messages.
+    pub fn set_syslog_level(&self, level: LogLevel) {
+        let level = log::LevelFilter::from(level);
+        self.syslog_level.store(level as usize, Ordering::Relaxed);
+        self.update_max_level();
+    }
+
+    /// Configure the maximum level of log messages written to the USB serial port.
+    ///
+    /// # Args
+    /// * `level` - The maximum level of log messages written to USB.
+    pub fn set_usb_level(&self, level: LogLevel) {
+        let level = log::LevelFilter::from(level);
+        if self.usb_level.swap(level as usize, Ordering::Relaxed) != level as usize {
+            self.update_max_level();
+        }
+    }
+
+    /// Update the global log level filter to the most verbose level of all log outputs.
+    fn update_max_level(&self) {
+        let level = self
+            .syslog_level
+            .load(Ordering::Relaxed)
+            .max(self.usb_level.load(Ordering::Relaxed))
+            .max(LOCAL_LEVEL as usize);
+
+        let level = match level {
+            0 => log::LevelFilter::Off,
+            1 => log::LevelFilter::Error,
+            2 => log::LevelFilter::Warn,
+            3 => log::LevelFilter::Info,
+            4 => log::LevelFilter::Debug,
+            _ => log::LevelFilter::Trace,
+        };
+
+        log::set_max_level(level);
+    }
+
+    /// Get the next log record pending transmission to the remote syslog server.
+    pub fn dequeue_syslog(&self) -> Option<SyslogRecord> {
+        self.syslog.dequeue()
+    }
+
     /// Process all of the available log data.
     ///
     /// # Args
@@ -45,10 +137,43 @@ impl log::Log for BufferedLog {
     }
 
     fn log(&self, record: &log::Record) {
-        self.rtt_logger.log(record);
         let source_file = record.file().unwrap_or("Unknown");
         let source_line = record.line().unwrap_or(u32::MAX);
 
+        if record.level() as usize <= self.syslog_level.load(Ordering::Relaxed) {
+            let mut message: String<256> = String::new();
+
+            // Messages that do not fit are forwarded truncated.
+            write!(
+                &mut message,
+                "{}:{} - {}",
+                source_file,
+                source_line,
+                record.args()
+            )
+            .ok();
+
+            self.syslog
+                .enqueue(SyslogRecord {
+                    level: record.level(),
+                    message,
+                    timestamp: crate::hardware::rtc::now(),
+                })
+                .ok();
+        }
+
+        if record.level() <= LOCAL_LEVEL {
+            #[cfg(not(feature = "defmt"))]
+            self.rtt_logger.log(record);
+
+            #[cfg(feature = "defmt")]
+            log_defmt(record, source_file, source_line);
+        }
+
+        if record.level() as usize > self.usb_level.load(Ordering::Relaxed) {
+            return;
+        }
+
         // Print the record into the buffer.
         let mut string: String<256> = String::new();
         if writeln!(
@@ -73,3 +198,20 @@ impl log::Log for BufferedLog {
     // The log is not capable of being flushed as it does not own the data consumer.
     fn flush(&self) {}
 }
+
+/// Write a log record to the defmt RTT output.
+///
+/// # Note
+/// The source location is transferred as interned strings, and only the message arguments are
+/// formatted on the device.
+#[cfg(feature = "defmt")]
+fn log_defmt(record: &log::Record, source_file: &str, source_line: u32) {
+    let args = defmt::Display2Format(record.args());
+    match record.level() {
+        log::Level::Error => defmt::error!("{=str}:{=u32} - {}", source_file, source_line, args),
+        log::Level::Warn => defmt::warn!("{=str}:{=u32} - {}", source_file, source_line, args),
+        log::Level::Info => defmt::info!("{=str}:{=u32} - {}", source_file, source_line, args),
+        log::Level::Debug => defmt::debug!("{=str}:{=u32} - {}", source_file, source_line, args),
+        log::Level::Trace => defmt::trace!("{=str}:{=u32} - {}", source_file, source_line, args),
+    }
+}
@@ -9,37 +9,55 @@ extern crate log;
 
 use panic_persist as _;
 
+#[cfg(feature = "defmt")]
+use defmt_rtt as _;
+
+mod error;
 mod hardware;
 mod linear_transformation;
 mod logger;
 mod net;
+mod protection;
+mod scheduler;
 mod settings;
+mod statistics;
+mod task_timing;
+mod telemetry;
 mod watchdog;
 
+pub use error::Error;
 use logger::BufferedLog;
 use settings::BoosterSettings;
 use systick_monotonic::fugit::ExtU64;
 
 use hardware::{
+    platform::ResetCause,
     setup::MainBus,
     usb::UsbDevice,
     user_interface::{ButtonEvent, Color, UserButtons, UserLeds},
     Channel, SerialTerminal, SystemTimer,
 };
 
-use settings::runtime_settings::RuntimeSettings;
+use net::mqtt_control::TelemetryLayout;
+use settings::{channel_settings::ChannelState, runtime_settings::RuntimeSettings};
+use task_timing::{TaskTimer, TimedTask};
+use telemetry::{ChannelTelemetry, Maintenance, TelemetrySnapshot};
 use watchdog::{WatchdogClient, WatchdogManager};
 
-/// An enumeration of possible errors with the device.
-#[derive(Debug, Copy, Clone, serde::Serialize)]
-pub enum Error {
-    Invalid,
-    InvalidState,
-    Interface,
-    Foldback,
-    Bounds,
-    Fault,
-}
+/// The interval between servicing consecutive RF channels in milliseconds, such that each channel
+/// is serviced at approximately 10Hz.
+const CHANNEL_MONITOR_INTERVAL_MS: u64 = 100 / 8;
+
+/// The interval between acquiring the telemetry of consecutive RF channels in milliseconds, such
+/// that the telemetry of each channel is refreshed every 400 ms.
+const TELEMETRY_ACQUISITION_INTERVAL_MS: u64 = 50;
+
+/// The interval between updates of the operating hour counters in seconds. Counters are
+/// accumulated in minutes and persisted once per hour.
+const OPERATING_HOURS_INTERVAL_SECS: u64 = 60;
+
+/// The interval between evaluations of the channel schedules in seconds.
+const SCHEDULE_INTERVAL_SECS: u64 = 10;
 
 static LOGGER: BufferedLog = BufferedLog::new();
 
@@ -52,6 +70,11 @@ mod app {
         main_bus: MainBus,
         net_devices: net::NetworkDevices,
         watchdog: WatchdogManager,
+        telemetry_snapshot: TelemetrySnapshot,
+        #[lock_free]
+        usb_terminal: SerialTerminal,
+        #[lock_free]
+        mainboard_settings: BoosterSettings,
     }
 
     #[local]
@@ -59,7 +82,7 @@ mod app {
         buttons: UserButtons,
         leds: UserLeds,
         usb: UsbDevice,
-        usb_terminal: SerialTerminal,
+        detectors: hardware::detectors::Detectors,
     }
 
     #[monotonic(binds = SysTick, default = true, priority = 4)]
@@ -71,7 +94,9 @@ mod app {
         let clock = SystemTimer::new(|| monotonics::now().ticks() as u32);
         let mut booster = hardware::setup::setup(c.core, c.device, clock);
 
-        let mut settings = RuntimeSettings::default();
+        let mut settings = RuntimeSettings::new(&booster.settings.properties);
+
+        settings::deployment_lock::init(&booster.settings.properties.lock_key);
 
         // Load the default fan speed
         settings.fan_speed = booster.settings.properties.fan_speed;
@@ -81,107 +106,738 @@ mod app {
                 .main_bus
                 .channels
                 .channel_mut(idx)
-                .map(|(channel, _)| *channel.context().settings())
+                .map(|channel| *channel.context().settings())
+        }
+
+        let mut net_devices = net::NetworkDevices::new(
+            &booster.settings.properties.broker,
+            booster.settings.properties.broker_port,
+            booster.network_stack,
+            &booster.settings.properties.id,
+            &booster.settings.properties.control_token,
+            settings,
+            clock,
+            booster.metadata,
+        );
+        net_devices.protect_mqtt_settings = booster.settings.properties.protect_mqtt_settings;
+
+        // Report any channels that ignored invalid stored settings.
+        for idx in enum_iterator::all::<Channel>() {
+            if let Some(error) = booster
+                .main_bus
+                .channels
+                .channel_mut(idx)
+                .and_then(|channel| channel.context().settings_error())
+            {
+                net_devices.telemetry.report_settings_error(idx, error);
+            }
+        }
+
+        // Report RF modules that were moved or replaced since they were last recorded.
+        let lock_swapped = booster.settings.properties.lock_swapped_modules;
+        for idx in enum_iterator::all::<Channel>() {
+            let Some(channel) = booster.main_bus.channels.channel_mut(idx) else {
+                continue;
+            };
+
+            let eui48 = channel.context().eui48();
+            if let Some(change) = booster.settings.modules.detect_change(idx, &eui48) {
+                log::warn!("RF module of {:?} changed: {:?}", idx, change);
+                net_devices.telemetry.report_module_change(idx, change);
+
+                // The module is recorded once the lockout is released.
+                if lock_swapped {
+                    channel.set_lockout(true);
+                }
+            }
         }
+        record_modules::spawn().unwrap();
 
         let watchdog_manager = WatchdogManager::new(booster.watchdog);
 
         // Kick-start the periodic software tasks.
         channel_monitor::spawn().unwrap();
-        telemetry::spawn().unwrap();
+        acquire_telemetry::spawn().unwrap();
+
+        // Note: Telemetry is first published once all channels have been acquired.
+        telemetry::spawn_after(1u64.secs()).unwrap();
         button::spawn().unwrap();
         usb::spawn().unwrap();
+        ethernet_link::spawn().unwrap();
+        save_channels::spawn().unwrap();
+
+        // After a watchdog or panic reset, enabled channels are optionally kept off until the
+        // operator has inspected the device.
+        let reset_cause = booster.metadata.reset_cause;
+        if booster.settings.properties.restore_after_fault
+            || !matches!(reset_cause, ResetCause::Watchdog | ResetCause::Panic)
+        {
+            // Outputs are inhibited after a watchdog reset until the reset is acknowledged. The
+            // reset is implicitly acknowledged when channels are configured to be restored.
+            hardware::platform::clear_reset_flags();
+            start_channels::spawn(0, booster.settings.properties.startup_delay_ms).unwrap();
+        } else {
+            log::warn!("Channels remain off after {:?} reset", reset_cause);
+        }
+
+        operating_hours::spawn_after(OPERATING_HOURS_INTERVAL_SECS.secs()).unwrap();
+        run_schedules::spawn().unwrap();
 
         (
             SharedResources {
                 main_bus: booster.main_bus,
-                net_devices: net::NetworkDevices::new(
-                    &booster.settings.properties.broker,
-                    booster.network_stack,
-                    &booster.settings.properties.id,
-                    settings,
-                    clock,
-                    booster.metadata,
-                ),
+                net_devices,
                 watchdog: watchdog_manager,
+                telemetry_snapshot: TelemetrySnapshot::default(),
+                usb_terminal: booster.usb_serial,
+                mainboard_settings: booster.settings,
             },
             LocalResources {
                 buttons: booster.buttons,
                 leds: booster.leds,
                 usb: booster.usb_device,
-                usb_terminal: booster.usb_serial,
+                detectors: booster.detectors,
             },
             init::Monotonics(booster.systick),
         )
     }
 
-    #[task(priority = 3, local=[leds], shared=[main_bus, watchdog])]
+    #[task(priority = 3, local=[leds, monitor_index: usize = 0, powered: [bool; 8] = [false; 8]], shared=[main_bus, watchdog])]
     fn channel_monitor(mut c: channel_monitor::Context) {
+        let _timer = TaskTimer::start(TimedTask::ChannelMonitor);
+
         // Check in with the watchdog.
         c.shared
             .watchdog
             .lock(|watchdog| watchdog.check_in(WatchdogClient::Monitor));
 
-        // Check all of the channels.
-        let mut fans_enabled = false;
+        // Channels are serviced round-robin, one channel per invocation, so that lower-priority
+        // tasks are not blocked by the I2C transactions of all channels at once.
+        let index = *c.local.monitor_index;
+
+        // Note(unwrap): The index is always a valid channel index.
+        let idx = enum_iterator::all::<Channel>().nth(index).unwrap();
+
+        let powered = &mut c.local.powered[index];
+        let status = c.shared.main_bus.lock(|main_bus| {
+            let mut enabled = false;
+            let mut tripped = false;
+            let status = main_bus
+                .channels
+                .channel_mut(idx)
+                .map(|channel| {
+                    *powered = channel.context().is_powered();
+                    let was_tripped = channel.is_tripped();
+                    let status = channel.update();
+                    enabled = channel.is_output_enabled();
+                    tripped = !was_tripped && channel.is_tripped();
+                    status
+                })
+                // Clear all LEDs for this channel.
+                .unwrap_or_default();
+
+            // Disable the channels that depend on a newly tripped channel.
+            if tripped {
+                main_bus.channels.trip_dependents(idx);
+            }
 
+            main_bus.status_output.update(idx, enabled);
+            status
+        });
+
+        // Echo the measured values to the LEDs on the user interface for this channel.
         let leds = c.local.leds;
-        for idx in enum_iterator::all::<Channel>() {
-            let status = c.shared.main_bus.lock(|main_bus| {
+        leds.set_led(Color::Green, idx, status.powered);
+        leds.set_led(Color::Yellow, idx, status.rf_disabled);
+        leds.set_led(Color::Red, idx, status.blocked);
+
+        // Propagate the updated LED values to the user interface.
+        leds.update();
+
+        *c.local.monitor_index = (index + 1) % c.local.powered.len();
+
+        // Update the fan speeds once all channels have been serviced.
+        if *c.local.monitor_index == 0 {
+            if c.local.powered.iter().any(|&powered| powered) {
+                c.shared.main_bus.lock(|main_bus| main_bus.fans.turn_on());
+            } else {
+                c.shared.main_bus.lock(|main_bus| main_bus.fans.turn_off());
+            }
+        }
+
+        // Schedule to run this task periodically so that each channel is serviced at 10Hz.
+        channel_monitor::spawn_after(CHANNEL_MONITOR_INTERVAL_MS.millis()).unwrap();
+    }
+
+    #[task(priority = 2, local=[acquisition_index: usize = 0], shared=[main_bus, telemetry_snapshot])]
+    fn acquire_telemetry(mut c: acquire_telemetry::Context) {
+        let _timer = TaskTimer::start(TimedTask::TelemetryAcquisition);
+
+        // Channels are measured round-robin, one channel per invocation, so that other tasks are
+        // not blocked by the I2C transactions of all channels at once.
+        let index = *c.local.acquisition_index;
+
+        // Note(unwrap): The index is always a valid channel index.
+        let idx = enum_iterator::all::<Channel>().nth(index).unwrap();
+
+        let (status, raised) = c.shared.main_bus.lock(|main_bus| {
+            main_bus
+                .channels
+                .channel_mut(idx)
+                .and_then(|ch| match ch.get_status() {
+                    Ok(status) => {
+                        let raised = ch.context_mut().update_warnings(status.warnings);
+                        Some((Some(ChannelTelemetry::Status(status)), raised))
+                    }
+                    Err(_) if ch.context().is_quarantined() => {
+                        let status = ChannelTelemetry::Quarantined {
+                            state: ch.state().name(),
+                            quarantined: true,
+                        };
+                        Some((Some(status), None))
+                    }
+                    // Note: If the channel cannot be measured, it is faulted and omitted from
+                    // telemetry.
+                    Err(_) => None,
+                })
+                .unwrap_or_default()
+        });
+
+        c.shared
+            .telemetry_snapshot
+            .lock(|snapshot| snapshot.update(idx, status, raised));
+
+        *c.local.acquisition_index = (index + 1) % 8;
+
+        // Check the chassis protection once all channels have been measured.
+        if *c.local.acquisition_index == 0 {
+            shed_channels::spawn().ok();
+        }
+
+        acquire_telemetry::spawn_after(TELEMETRY_ACQUISITION_INTERVAL_MS.millis()).unwrap();
+    }
+
+    #[task(priority = 2, shared=[main_bus, net_devices, telemetry_snapshot])]
+    fn shed_channels(mut c: shed_channels::Context) {
+        let (foldback, load_shedding, priority) = c.shared.net_devices.lock(|net_devices| {
+            let settings = net_devices.settings.settings();
+            (settings.foldback, settings.load_shedding, settings.priority)
+        });
+
+        let shed = c.shared.telemetry_snapshot.lock(|snapshot| {
+            let channels = snapshot.channels();
+            protection::check(channels, &foldback, &load_shedding).and_then(|reason| {
+                protection::lowest_priority(channels, &priority).map(|idx| (idx, reason))
+            })
+        });
+
+        if let Some((idx, reason)) = shed {
+            log::warn!("Shedding {:?} ({:?})", idx, reason);
+            c.shared.main_bus.lock(|main_bus| {
                 main_bus
                     .channels
                     .channel_mut(idx)
-                    .map(|(channel, _)| {
-                        if channel.context().is_powered() {
-                            fans_enabled = true;
-                        }
-
-                        channel.update()
-                    })
-                    // Clear all LEDs for this channel.
-                    .unwrap_or_default()
+                    .map(|channel| channel.standby())
+            });
+            c.shared.net_devices.lock(|net_devices| {
+                // Record the shed channel as off so that the next settings update does not enable
+                // it again and the settings reported via MQTT reflect the actual state.
+                if let Some(channel) =
+                    net_devices.settings.settings_mut().channel[idx as usize].as_mut()
+                {
+                    channel.state = ChannelState::Off;
+                }
+                net_devices.settings.force_republish();
+                net_devices.telemetry.report_shed(idx, reason);
             });
 
-            // Echo the measured values to the LEDs on the user interface for this channel.
-            leds.set_led(Color::Green, idx, status.powered);
-            leds.set_led(Color::Yellow, idx, status.rf_disabled);
-            leds.set_led(Color::Red, idx, status.blocked);
+            // Note: The settings update may already be pending, so it's acceptable if the spawn
+            // fails.
+            update_settings::spawn().ok();
         }
+    }
 
-        // Update the fan speeds.
-        if fans_enabled {
-            c.shared.main_bus.lock(|main_bus| main_bus.fans.turn_on());
-        } else {
-            c.shared.main_bus.lock(|main_bus| main_bus.fans.turn_off());
+    #[task(priority = 1, shared=[net_devices, telemetry_snapshot])]
+    fn telemetry(mut c: telemetry::Context) {
+        let _timer = TaskTimer::start(TimedTask::Telemetry);
+
+        // Publish from a copy of the snapshot so that telemetry acquisition is not blocked while
+        // messages are serialized.
+        let (mut channels, warnings) = c
+            .shared
+            .telemetry_snapshot
+            .lock(|snapshot| (snapshot.channels().clone(), snapshot.take_warnings()));
+
+        c.shared.net_devices.lock(|net_devices| {
+            for (idx, raised) in enum_iterator::all::<Channel>().zip(warnings) {
+                if let Some(raised) = raised {
+                    net_devices.telemetry.report_warning(idx, raised);
+                }
+            }
+
+            let power_unit = net_devices.telemetry.power_unit();
+            let precision = net_devices.telemetry.precision();
+            for telemetry in channels.iter_mut().flatten() {
+                telemetry.convert_power(power_unit);
+                telemetry.round(&precision);
+            }
+
+            match net_devices.telemetry.layout() {
+                TelemetryLayout::PerChannel => {
+                    for (idx, status) in enum_iterator::all::<Channel>().zip(channels.iter()) {
+                        if let Some(status) = status {
+                            net_devices.telemetry.report_telemetry(idx, status);
+                        }
+                    }
+                }
+                TelemetryLayout::Combined => {
+                    net_devices.telemetry.report_combined_telemetry(&channels)
+                }
+                TelemetryLayout::PerField => {
+                    for (idx, status) in enum_iterator::all::<Channel>().zip(channels.iter()) {
+                        if let Some(status) = status {
+                            net_devices.telemetry.report_field_telemetry(idx, status);
+                        }
+                    }
+                }
+            }
+        });
+
+        // Report the task timing measured since the previous telemetry.
+        let timing = task_timing::report(monotonics::now().ticks() as u32);
+        let telemetry_period = c.shared.net_devices.lock(|net_devices| {
+            net_devices.telemetry.report_timing(&timing);
+            net_devices
+                .telemetry
+                .report_statistics(&statistics::Statistics::read());
+            net_devices.telemetry.refresh_metadata();
+            net_devices.telemetry.telemetry_period_secs()
+        });
+
+        telemetry::spawn_after(telemetry_period.secs()).unwrap();
+    }
+
+    #[task(priority = 1, shared=[net_devices])]
+    fn ethernet_link(mut c: ethernet_link::Context) {
+        c.shared
+            .net_devices
+            .lock(|net_devices| net_devices.handle_link());
+
+        ethernet_link::spawn_after(1u64.secs()).unwrap();
+    }
+
+    #[task(priority = 1, shared=[main_bus])]
+    fn start_channels(mut c: start_channels::Context, index: usize, delay_ms: u32) {
+        // Abort the sequence if the supply sagged while powering up the previous channels.
+        if hardware::power_fail::is_detected()
+            || c.shared
+                .main_bus
+                .lock(|main_bus| main_bus.channels.supply_faulted())
+        {
+            log::error!("Supply fault during channel power-up. Remaining channels stay off");
+            return;
         }
 
-        // Propagate the updated LED values to the user interface.
-        leds.update();
+        let Some(idx) = enum_iterator::all::<Channel>().nth(index) else {
+            return;
+        };
+
+        let powered = c.shared.main_bus.lock(|main_bus| {
+            main_bus
+                .channels
+                .channel_mut(idx)
+                .map(|channel| {
+                    channel.handle_startup();
+                    channel.context().is_powered()
+                })
+                .unwrap_or(false)
+        });
 
-        // Schedule to run this task periodically at 10Hz.
-        channel_monitor::spawn_after(100u64.millis()).unwrap();
+        // Only wait before powering up the next channel if this channel was powered up.
+        let delay = if powered { delay_ms } else { 0 };
+        start_channels::spawn_after(u64::from(delay).millis(), index + 1, delay_ms).unwrap();
     }
 
     #[task(priority = 1, shared=[main_bus, net_devices])]
-    fn telemetry(mut c: telemetry::Context) {
-        // Gather telemetry for all of the channels.
-        // And broadcast the measured data over the telemetry interface.
+    fn save_channels(mut c: save_channels::Context) {
+        // Pending saves are flushed when power fails. Further EEPROM writes are avoided since the
+        // supply may not be stable.
+        if hardware::power_fail::is_detected() {
+            return;
+        }
+
+        let delay = c
+            .shared
+            .net_devices
+            .lock(|net_devices| net_devices.settings.settings().auto_save_delay);
+
         for idx in enum_iterator::all::<Channel>() {
-            (&mut c.shared.main_bus, &mut c.shared.net_devices).lock(|main_bus, net_devices| {
-                main_bus.channels.channel_mut(idx).map(|(ch, adc)| {
-                    net_devices
-                        .telemetry
-                        .report_telemetry(idx, &ch.get_status(adc))
-                })
+            c.shared.main_bus.lock(|main_bus| {
+                main_bus
+                    .channels
+                    .channel_mut(idx)
+                    .map(|channel| channel.context_mut().process_saves(delay))
             });
         }
 
-        let telemetry_period = c
+        save_channels::spawn_after(1u64.secs()).unwrap();
+    }
+
+    #[task(priority = 1, local=[device_minutes: u32 = 0, fan_minutes: u32 = 0], shared=[main_bus, net_devices, mainboard_settings])]
+    fn operating_hours(mut c: operating_hours::Context) {
+        let fans_running = c
             .shared
+            .main_bus
+            .lock(|main_bus| main_bus.fans.is_running());
+
+        *c.local.device_minutes += 1;
+        if fans_running {
+            *c.local.fan_minutes += 1;
+        }
+
+        let mainboard = c.shared.mainboard_settings;
+        let mut modified = false;
+
+        if *c.local.device_minutes >= 60 {
+            *c.local.device_minutes = 0;
+            mainboard.operating_hours.device += 1;
+            modified = true;
+        }
+
+        if *c.local.fan_minutes >= 60 {
+            *c.local.fan_minutes = 0;
+            mainboard.operating_hours.fan += 1;
+            modified = true;
+        }
+
+        let hours = mainboard.operating_hours;
+        let maintenance = c.shared.net_devices.lock(|net_devices| {
+            let maintenance =
+                Maintenance::new(&hours, net_devices.settings.settings().fan_service_hours);
+            net_devices.telemetry.report_maintenance(&maintenance);
+            maintenance
+        });
+
+        // Counters are only persisted once per hour to limit EEPROM wear.
+        if modified {
+            if maintenance.fan_service_due {
+                log::warn!(
+                    "Fan service due: {} hours since last service",
+                    maintenance.fan_hours_since_service
+                );
+            }
+
+            if !hardware::power_fail::is_detected() {
+                mainboard.save_operating_hours();
+            }
+        }
+
+        operating_hours::spawn_after(OPERATING_HOURS_INTERVAL_SECS.secs()).unwrap();
+    }
+
+    #[task(priority = 1, local=[active: [Option<bool>; 8] = [None; 8]], shared=[net_devices])]
+    fn run_schedules(mut c: run_schedules::Context) {
+        // Schedules are only evaluated once the clock has been set.
+        if let Some(time) = hardware::rtc::now() {
+            let active = c.local.active;
+            let updated = c.shared.net_devices.lock(|net_devices| {
+                let settings = net_devices.settings.settings_mut();
+                let mut updated = false;
+
+                for idx in enum_iterator::all::<Channel>() {
+                    let schedule = &settings.schedule[idx as usize];
+                    let last = &mut active[idx as usize];
+                    if !schedule.enabled || scheduler::is_held(idx) {
+                        *last = None;
+                        continue;
+                    }
+
+                    // The channel state is only updated when a scheduled window starts or ends.
+                    let scheduled = schedule.is_active(time);
+                    if last.replace(scheduled) == Some(scheduled) {
+                        continue;
+                    }
+
+                    let state = if scheduled {
+                        ChannelState::Enabled
+                    } else {
+                        ChannelState::Off
+                    };
+
+                    if let Some(channel) = settings.channel[idx as usize].as_mut() {
+                        if channel.state != state {
+                            log::info!("Schedule: {:?} {:?}", idx, state);
+                            channel.state = state;
+                            updated = true;
+                        }
+                    }
+                }
+
+                // Ensure that the settings reported via MQTT reflect the update.
+                if updated {
+                    net_devices.settings.force_republish();
+                }
+
+                updated
+            });
+
+            if updated {
+                update_settings::spawn().ok();
+            }
+        }
+
+        run_schedules::spawn_after(SCHEDULE_INTERVAL_SECS.secs()).unwrap();
+    }
+
+    #[task(priority = 1, shared=[main_bus, mainboard_settings])]
+    fn record_modules(mut c: record_modules::Context) {
+        let mainboard = c.shared.mainboard_settings;
+        let mut modules = mainboard.modules;
+
+        // Modules of locked out channels are only recorded once the lockout is released.
+        for idx in enum_iterator::all::<Channel>() {
+            c.shared
+                .main_bus
+                .lock(|main_bus| match main_bus.channels.channel_mut(idx) {
+                    Some(channel) if channel.context().is_locked_out() => {}
+                    Some(channel) => modules.record(idx, Some(&channel.context().eui48())),
+                    None => modules.record(idx, None),
+                });
+        }
+
+        if modules != mainboard.modules && !hardware::power_fail::is_detected() {
+            mainboard.modules = modules;
+            mainboard.save_modules();
+        }
+    }
+
+    #[task(priority = 1, shared=[mainboard_settings])]
+    fn clear_maintenance(c: clear_maintenance::Context) {
+        let mainboard = c.shared.mainboard_settings;
+        let hours = &mut mainboard.operating_hours;
+        hours.fan_serviced = hours.fan;
+        log::info!("Fan service acknowledged at {} fan hours", hours.fan);
+
+        mainboard.save_operating_hours();
+    }
+
+    #[task(binds = PVD, priority = 4)]
+    fn power_fail(_: power_fail::Context) {
+        hardware::power_fail::acknowledge();
+
+        // Power off all channels immediately via their control pins. The main bus is not used here,
+        // since sharing it with this handler would mask the monotonic and the RF gate whenever it
+        // is locked.
+        hardware::platform::shutdown_channels();
+
+        flush_channel_saves::spawn().ok();
+
+        log::warn!("Power failure detected. Channels have been shut down");
+    }
+
+    #[task(priority = 3, shared=[main_bus])]
+    fn flush_channel_saves(mut c: flush_channel_saves::Context) {
+        c.shared.main_bus.lock(|main_bus| {
+            for idx in enum_iterator::all::<Channel>() {
+                if let Some(channel) = main_bus.channels.channel_mut(idx) {
+                    channel.context_mut().flush_saves();
+                }
+            }
+        });
+    }
+
+    #[task(binds = TIM7, priority = 5, local=[detectors])]
+    fn fast_path(c: fast_path::Context) {
+        hardware::detectors::acknowledge();
+        c.local.detectors.poll();
+    }
+
+    #[task(binds = EXTI9_5, priority = 4)]
+    fn rf_gate(_: rf_gate::Context) {
+        hardware::gate::acknowledge();
+        hardware::gate::update();
+    }
+
+    #[task(priority = 1, shared=[main_bus])]
+    fn reset(mut c: reset::Context, target: hardware::platform::ResetTarget) {
+        cortex_m::interrupt::disable();
+
+        // Power off all output channels before resetting.
+        hardware::platform::shutdown_channels();
+
+        // Write any deferred channel settings to EEPROM so that they are not lost by the reset.
+        c.shared.main_bus.lock(|main_bus| {
+            for idx in enum_iterator::all::<Channel>() {
+                if let Some(channel) = main_bus.channels.channel_mut(idx) {
+                    channel.context_mut().flush_saves();
+                }
+            }
+        });
+
+        match target {
+            hardware::platform::ResetTarget::Firmware => cortex_m::peripheral::SCB::sys_reset(),
+            hardware::platform::ResetTarget::Bootloader => {
+                hardware::platform::reset_to_dfu_bootloader()
+            }
+        }
+    }
+
+    #[task(priority = 1, shared=[net_devices])]
+    fn clone_channel(mut c: clone_channel::Context, source: Channel, destination: Option<Channel>) {
+        let result = c.shared.net_devices.lock(|net_devices| {
+            let mut new_settings = net_devices.settings.settings().clone();
+            new_settings.clone_channel(source, destination)?;
+            RuntimeSettings::handle_update(
+                "/channel",
+                net_devices.settings.settings_mut(),
+                &new_settings,
+            )?;
+
+            // Ensure that the settings reported via MQTT reflect the update.
+            net_devices.settings.force_republish();
+            Ok::<(), &'static str>(())
+        });
+
+        match result {
+            // Note: The settings update may already be pending, in which case the pending update
+            // will apply the latest settings.
+            Ok(()) => {
+                update_settings::spawn().ok();
+            }
+            Err(err) => log::warn!("Failed to clone {:?}: {}", source, err),
+        }
+    }
+
+    #[task(priority = 1, shared=[net_devices])]
+    fn write_channel(
+        mut c: write_channel::Context,
+        channel: Channel,
+        properties: net::mqtt_control::ChannelProperties,
+    ) {
+        let result = c.shared.net_devices.lock(|net_devices| {
+            let mut new_settings = net_devices.settings.settings().clone();
+            new_settings.channel[channel as usize]
+                .as_mut()
+                .ok_or("Channel not found")?
+                .write_properties(&properties)?;
+            RuntimeSettings::handle_update(
+                "/channel",
+                net_devices.settings.settings_mut(),
+                &new_settings,
+            )?;
+
+            // Ensure that the settings reported via MQTT reflect the update.
+            net_devices.settings.force_republish();
+            Ok::<(), &'static str>(())
+        });
+
+        match result {
+            // Note: The settings update may already be pending, in which case the pending update
+            // will apply the latest settings.
+            Ok(()) => {
+                update_settings::spawn().ok();
+            }
+            Err(err) => log::warn!("Failed to write {:?}: {}", channel, err),
+        }
+    }
+
+    #[task(priority = 1, shared=[net_devices])]
+    fn republish_settings(mut c: republish_settings::Context) {
+        c.shared
             .net_devices
-            .lock(|net_devices| net_devices.telemetry.telemetry_period_secs());
+            .lock(|net_devices| net_devices.settings.force_republish());
+    }
 
-        telemetry::spawn_after(telemetry_period.secs()).unwrap();
+    #[task(priority = 2, shared=[net_devices, usb_terminal])]
+    fn apply_network(mut c: apply_network::Context) {
+        let network = c
+            .shared
+            .net_devices
+            .lock(|net_devices| net_devices.settings.settings().network.clone());
+
+        // Persist the network settings to flash. They take effect once the device is reset.
+        let platform = c.shared.usb_terminal.platform_mut();
+        network.apply(&mut platform.settings);
+
+        let mut buffer = [0u8; 512];
+        if let Err(err) = serial_settings::Platform::save(platform, &mut buffer) {
+            log::error!("Failed to save network settings: {:?}", err);
+            return;
+        }
+
+        log::warn!("Network settings saved. Resetting device");
+        reset::spawn_after(
+            net::mqtt_control::RESET_DELAY_MS.millis(),
+            hardware::platform::ResetTarget::Firmware,
+        )
+        .ok();
+    }
+
+    #[task(priority = 2, shared=[main_bus, net_devices, usb_terminal])]
+    fn usb_command(mut c: usb_command::Context, command: hardware::usb_shell::ShellCommand) {
+        let interface = &mut c.shared.usb_terminal.platform_mut().interface;
+        let main_bus = &mut c.shared.main_bus;
+        let updated = c.shared.net_devices.lock(|net| {
+            main_bus.lock(|main_bus| {
+                hardware::usb_shell::execute(main_bus, &mut net.settings, command, interface)
+            })
+        });
+
+        if updated {
+            update_settings::spawn().ok();
+        }
+    }
+
+    #[task(priority = 2, shared=[main_bus, usb_terminal])]
+    fn usb_stream(mut c: usb_stream::Context) {
+        let interface = &mut c.shared.usb_terminal.platform_mut().interface;
+        let period = c
+            .shared
+            .main_bus
+            .lock(|main_bus| hardware::usb_shell::stream_telemetry(main_bus, interface));
+
+        if let Some(period) = period {
+            usb_stream::spawn_after(u64::from(period).millis()).ok();
+        }
+    }
+
+    #[task(priority = 2, shared=[net_devices, usb_terminal])]
+    fn usb_settings(mut c: usb_settings::Context, command: hardware::usb_shell::SettingsCommand) {
+        let interface = &mut c.shared.usb_terminal.platform_mut().interface;
+        let updated = c.shared.net_devices.lock(|net| {
+            hardware::usb_shell::execute_settings(&mut net.settings, command, interface)
+        });
+
+        if updated {
+            update_settings::spawn().ok();
+        }
+    }
+
+    #[task(priority = 2, shared=[main_bus, usb_terminal])]
+    fn factory_reset(mut c: factory_reset::Context, preserve_calibration: bool) {
+        for idx in enum_iterator::all::<Channel>() {
+            c.shared.main_bus.lock(|main_bus| {
+                main_bus
+                    .channels
+                    .channel_mut(idx)
+                    .map(|channel| channel.context_mut().factory_reset(preserve_calibration))
+            });
+        }
+
+        let platform = c.shared.usb_terminal.platform_mut();
+        serial_settings::Settings::reset(&mut platform.settings);
+
+        let mut buffer = [0u8; 512];
+        if let Err(err) = serial_settings::Platform::save(platform, &mut buffer) {
+            log::error!("Failed to save default settings: {:?}", err);
+        }
+
+        log::warn!("Factory defaults restored. Resetting device");
+        reset::spawn_after(
+            net::mqtt_control::RESET_DELAY_MS.millis(),
+            hardware::platform::ResetTarget::Firmware,
+        )
+        .ok();
     }
 
     #[task(priority = 2, local=[buttons], shared=[main_bus, watchdog])]
@@ -192,12 +848,18 @@ mod app {
             .lock(|watchdog| watchdog.check_in(WatchdogClient::Button));
 
         if let Some(event) = c.local.buttons.update() {
+            // Pressing the "Interlock Reset" button acknowledges a watchdog reset, which otherwise
+            // inhibits the outputs.
+            if matches!(event, ButtonEvent::InterlockReset) {
+                hardware::platform::clear_reset_flags();
+            }
+
             for idx in enum_iterator::all::<Channel>() {
                 c.shared.main_bus.lock(|main_bus| {
                     main_bus
                         .channels
                         .channel_mut(idx)
-                        .map(|(channel, _)| match event {
+                        .map(|channel| match event {
                             ButtonEvent::InterlockReset => {
                                 // It is possible to attempt to re-enable the channel before it was
                                 // fully disabled. Ignore this transient error - the user may need
@@ -205,16 +867,23 @@ mod app {
                                 channel.interlock_reset().ok();
                             }
                             ButtonEvent::Standby => channel.standby(),
+                            ButtonEvent::LockoutEngage => channel.set_lockout(true),
+                            ButtonEvent::LockoutRelease => channel.set_lockout(false),
                         })
                 });
             }
+
+            // Record the modules of released channels.
+            if matches!(event, ButtonEvent::LockoutRelease) {
+                record_modules::spawn().ok();
+            }
         }
 
         // Schedule to run this task every 3ms.
         button::spawn_after(3u64.millis()).unwrap();
     }
 
-    #[task(priority = 1, shared=[net_devices, main_bus])]
+    #[task(priority = 1, shared=[net_devices, main_bus, watchdog])]
     fn update_settings(mut c: update_settings::Context) {
         let all_settings = c
             .shared
@@ -227,7 +896,22 @@ mod app {
                     .channels
                     .channel_mut(idx)
                     .zip(all_settings.channel[idx as usize].as_ref().as_ref())
-                    .map(|((channel, _), settings)| {
+                    .map(|(channel, settings)| {
+                        channel
+                            .context_mut()
+                            .set_temperature_source(all_settings.temperature_source);
+                        channel
+                            .context_mut()
+                            .set_warning_thresholds(all_settings.warning);
+                        channel
+                            .context_mut()
+                            .set_thermal_derating(all_settings.derating[idx as usize]);
+                        channel
+                            .context_mut()
+                            .set_dependents(all_settings.dependents[idx as usize]);
+                        channel
+                            .context_mut()
+                            .set_measurement_filter(all_settings.filter);
                         channel.handle_settings(settings).unwrap_or_else(|err| {
                             log::warn!("Settings failure on {:?}: {:?}", idx, err)
                         })
@@ -235,31 +919,64 @@ mod app {
             });
         }
 
-        // Update the fan speed.
-        c.shared
-            .main_bus
-            .lock(|main_bus| main_bus.fans.set_default_duty_cycle(all_settings.fan_speed));
+        // Apply the hardware gate configuration and the detector sample time.
+        hardware::gate::configure(&all_settings.gate);
+        hardware::detectors::set_sample_time(all_settings.adc_sample_time);
 
-        // Update the telemetry rate.
+        // Update the fan speed and the channels monitored by the status output.
+        c.shared.main_bus.lock(|main_bus| {
+            main_bus.fans.set_default_duty_cycle(all_settings.fan_speed);
+            main_bus
+                .status_output
+                .set_channels(all_settings.status_channels);
+        });
+
+        // Update the telemetry rate, layout, publication options, power unit, and precision.
         c.shared.net_devices.lock(|net_devices| {
             net_devices
                 .telemetry
-                .set_telemetry_period(all_settings.telemetry_period)
+                .set_telemetry_period(all_settings.telemetry_period);
+            net_devices
+                .telemetry
+                .set_layout(all_settings.telemetry_layout);
+            net_devices
+                .telemetry
+                .set_publication(all_settings.publication);
+            net_devices
+                .telemetry
+                .set_power_unit(all_settings.power_unit);
+            net_devices.telemetry.set_precision(all_settings.precision);
         });
+
+        // Update the remote syslog configuration.
+        c.shared
+            .net_devices
+            .lock(|net_devices| net_devices.syslog.set_server(all_settings.syslog_server));
+        LOGGER.set_syslog_level(all_settings.syslog_level);
+
+        // Update the network liveness criterion of the watchdog.
+        c.shared
+            .watchdog
+            .lock(|watchdog| watchdog.set_network_timeout(all_settings.network_watchdog));
     }
 
-    #[task(priority = 2, shared=[watchdog], local=[usb, usb_terminal])]
+    #[task(priority = 2, shared=[watchdog, usb_terminal], local=[usb])]
     fn usb(mut c: usb::Context) {
+        let _timer = TaskTimer::start(TimedTask::Usb);
+
         // Check in with the watchdog.
         c.shared
             .watchdog
             .lock(|watchdog| watchdog.check_in(WatchdogClient::Usb));
 
-        c.local.usb.process(c.local.usb_terminal);
-        c.local.usb_terminal.process().unwrap();
+        let usb_terminal = c.shared.usb_terminal;
+        c.local.usb.process(usb_terminal);
+        usb_terminal.process().unwrap();
 
-        // Process any log output.
-        LOGGER.process(c.local.usb_terminal);
+        // Process any log output. The USB log level is applied here so that changes made via the
+        // USB terminal take effect immediately.
+        LOGGER.set_usb_level(usb_terminal.platform_mut().settings.usb_log_level);
+        LOGGER.process(usb_terminal);
 
         // Schedule to run this task every 10ms.
         usb::spawn_after(10u64.millis()).unwrap();
@@ -268,6 +985,8 @@ mod app {
     #[idle(shared=[main_bus, net_devices, watchdog])]
     fn idle(mut c: idle::Context) -> ! {
         loop {
+            let _timer = TaskTimer::start(TimedTask::Network);
+
             // Check in with the watchdog.
             c.shared
                 .watchdog
@@ -275,15 +994,27 @@ mod app {
 
             // Handle the Miniconf settings interface.
             let mut republish = false;
-            match c.shared.net_devices.lock(|net| {
+            let result = c.shared.net_devices.lock(|net| {
+                let protected = net.protect_mqtt_settings && !net.control_token.is_empty();
                 net.settings.handled_update(|path, old, new| {
-                    let result = RuntimeSettings::handle_update(path, old, new);
+                    // Settings updates via MQTT cannot carry the access-control token, so they
+                    // are only rejected when protection of the settings topics is enabled.
+                    let result = if settings::deployment_lock::is_locked() {
+                        Err("Device locked")
+                    } else if protected {
+                        log::warn!("Rejected unauthorized settings update of {}", path);
+                        Err("Unauthorized, use the JSON-RPC `set` method")
+                    } else {
+                        RuntimeSettings::handle_update(path, old, new)
+                    };
                     if result.is_err() {
                         republish = true;
                     }
                     result
                 })
-            }) {
+            });
+            net::report_client(net::Client::Settings, result.is_ok());
+            match result {
                 Ok(true) => update_settings::spawn().unwrap(),
                 Ok(false) => {}
                 Err(minimq::Error::Network(smoltcp_nal::NetworkError::TcpConnectionFailure(
@@ -303,9 +1034,21 @@ mod app {
             c.shared
                 .net_devices
                 .lock(|net| {
-                    match net.control.poll(|handler, topic, data, output| {
-                        main_bus.lock(|bus| handler(bus, topic, data, output))
-                    }) {
+                    let net::NetworkDevices {
+                        control,
+                        control_token,
+                        ..
+                    } = net;
+
+                    let result = control.poll(|handler, topic, data, output| {
+                        let result = net::mqtt_control::check_version(data)
+                            .and_then(|_| net::mqtt_control::authorize(control_token, topic, data))
+                            .and_then(|_| main_bus.lock(|bus| handler(bus, topic, data, output)));
+                        statistics::record_command(result.is_ok());
+                        result
+                    });
+                    net::report_client(net::Client::Control, result.is_ok());
+                    match result {
                         Err(minireq::Error::Mqtt(minireq::minimq::Error::Network(
                             smoltcp_nal::NetworkError::TcpConnectionFailure(
                                 smoltcp_nal::smoltcp::socket::tcp::ConnectError::Unaddressable,
@@ -316,6 +1059,67 @@ mod app {
                 })
                 .unwrap();
 
+            // Handle the Prometheus metrics interface. The measurements are taken without holding
+            // the network devices, and the main bus is only locked for one device at a time.
+            let pending = c.shared.net_devices.lock(|net| {
+                net.metrics.poll();
+                net.metrics.pending()
+            });
+            if pending {
+                let mut metrics = net::metrics::Metrics::default();
+                for idx in enum_iterator::all::<Channel>() {
+                    main_bus.lock(|bus| metrics.measure_channel(bus, idx));
+                }
+                main_bus.lock(|bus| metrics.measure_fans(bus));
+                c.shared
+                    .net_devices
+                    .lock(|net| net.metrics.respond(&metrics));
+            }
+
+            // Handle the SCPI command interface.
+            c.shared.net_devices.lock(|net| {
+                let net::NetworkDevices {
+                    scpi,
+                    settings,
+                    control_token,
+                    ..
+                } = net;
+
+                scpi.poll(|command, output| {
+                    main_bus.lock(|bus| {
+                        net::scpi::execute(bus, settings, control_token, command, output)
+                    })
+                })
+            });
+
+            // Handle the JSON-RPC control interface.
+            let mut settings_updated = false;
+            c.shared.net_devices.lock(|net| {
+                let net::NetworkDevices {
+                    json_rpc,
+                    settings,
+                    control_token,
+                    ..
+                } = net;
+
+                json_rpc.poll(|method, params, output| {
+                    let result = main_bus.lock(|bus| {
+                        net::json_rpc::execute(bus, settings, control_token, method, params, output)
+                    });
+                    statistics::record_command(result.is_ok());
+
+                    let response = result?;
+                    settings_updated |= response.settings_updated;
+                    Ok(response.len)
+                })
+            });
+
+            if settings_updated {
+                // Note: The settings update may already be pending from the Miniconf interface,
+                // in which case the pending update will apply the latest settings.
+                update_settings::spawn().ok();
+            }
+
             // Handle the network stack processing if needed.
             c.shared.net_devices.lock(|net| net.process());
         }
@@ -0,0 +1,247 @@
+//! Home Assistant MQTT discovery
+//!
+//! # Design
+//! Booster publishes retained [Home Assistant MQTT discovery](https://www.home-assistant.io/integrations/mqtt/#mqtt-discovery)
+//! configurations for each installed channel once connected to the broker. Each channel exposes
+//! sensors for output power, reflected power, and temperature sourced from channel telemetry, and
+//! a switch controlling the channel state via the run-time settings interface.
+
+use super::mqtt_control::TelemetryLayout;
+use crate::{hardware::metadata::ApplicationMetadata, telemetry::PowerUnit};
+
+use core::fmt::Write;
+use heapless::String;
+use serde::Serialize;
+
+/// The topic prefix that Home Assistant monitors for discovery configurations.
+const DISCOVERY_PREFIX: &str = "homeassistant";
+
+/// An entity exposed for each channel.
+#[derive(Copy, Clone)]
+pub enum Entity {
+    OutputPower,
+    ReflectedPower,
+    Temperature,
+    Enable,
+}
+
+/// All entities exposed for each channel.
+pub const ENTITIES: [Entity; 4] = [
+    Entity::OutputPower,
+    Entity::ReflectedPower,
+    Entity::Temperature,
+    Entity::Enable,
+];
+
+impl Entity {
+    /// Get the Home Assistant component type of the entity.
+    fn component(&self) -> &'static str {
+        match self {
+            Entity::Enable => "switch",
+            _ => "sensor",
+        }
+    }
+
+    /// Get the identifier of the entity. For sensors, this is the name of the telemetry field.
+    fn key(&self) -> &'static str {
+        match self {
+            Entity::OutputPower => "output_power",
+            Entity::ReflectedPower => "reflected_power",
+            Entity::Temperature => "temperature",
+            Entity::Enable => "enable",
+        }
+    }
+
+    /// Get the human-readable name of the entity.
+    fn name(&self) -> &'static str {
+        match self {
+            Entity::OutputPower => "Output Power",
+            Entity::ReflectedPower => "Reflected Power",
+            Entity::Temperature => "Temperature",
+            Entity::Enable => "Enable",
+        }
+    }
+
+    /// Get the unit of measurement of sensor entities.
+    ///
+    /// # Args
+    /// * `power_unit` - The unit of power measurements in telemetry.
+    fn unit(&self, power_unit: PowerUnit) -> Option<&'static str> {
+        match (self, power_unit) {
+            (Entity::OutputPower | Entity::ReflectedPower, PowerUnit::Dbm) => Some("dBm"),
+            (Entity::OutputPower | Entity::ReflectedPower, PowerUnit::Watts) => Some("W"),
+            (Entity::Temperature, _) => Some("°C"),
+            (Entity::Enable, _) => None,
+        }
+    }
+
+    /// Get the Home Assistant device class of the entity.
+    ///
+    /// # Args
+    /// * `power_unit` - The unit of power measurements in telemetry.
+    fn device_class(&self, power_unit: PowerUnit) -> Option<&'static str> {
+        match (self, power_unit) {
+            (Entity::OutputPower | Entity::ReflectedPower, PowerUnit::Dbm) => {
+                Some("signal_strength")
+            }
+            (Entity::OutputPower | Entity::ReflectedPower, PowerUnit::Watts) => Some("power"),
+            (Entity::Temperature, _) => Some("temperature"),
+            (Entity::Enable, _) => None,
+        }
+    }
+}
+
+/// The device that all entities belong to.
+#[derive(Serialize)]
+struct Device<'a> {
+    identifiers: [&'a str; 1],
+    name: &'a str,
+    manufacturer: &'static str,
+    model: &'static str,
+    sw_version: &'a str,
+}
+
+/// A Home Assistant discovery configuration of a single entity.
+#[derive(Serialize)]
+pub struct Config<'a> {
+    name: String<32>,
+    unique_id: String<64>,
+    state_topic: String<160>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value_template: Option<String<64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unit_of_measurement: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    device_class: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command_topic: Option<String<160>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    payload_on: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    payload_off: Option<&'static str>,
+    device: Device<'a>,
+}
+
+impl<'a> Config<'a> {
+    /// Construct the discovery configuration of a channel entity.
+    ///
+    /// # Args
+    /// * `entity` - The entity to configure.
+    /// * `channel` - The index of the channel.
+    /// * `identifier` - The unique identifier of this device.
+    /// * `prefix` - The MQTT topic prefix of this device.
+    /// * `layout` - The active telemetry layout.
+    /// * `power_unit` - The unit of power measurements in telemetry.
+    /// * `metadata` - The application metadata.
+    pub fn new(
+        entity: Entity,
+        channel: usize,
+        identifier: &'a str,
+        prefix: &str,
+        layout: TelemetryLayout,
+        power_unit: PowerUnit,
+        metadata: &'a ApplicationMetadata,
+    ) -> Result<Self, core::fmt::Error> {
+        let mut name = String::new();
+        write!(&mut name, "CH{} {}", channel, entity.name())?;
+
+        let mut unique_id = String::new();
+        write!(
+            &mut unique_id,
+            "{}_ch{}_{}",
+            identifier,
+            channel,
+            entity.key()
+        )?;
+
+        let mut config = Self {
+            name,
+            unique_id,
+            state_topic: String::new(),
+            value_template: None,
+            unit_of_measurement: entity.unit(power_unit),
+            device_class: entity.device_class(power_unit),
+            command_topic: None,
+            payload_on: None,
+            payload_off: None,
+            device: Device {
+                identifiers: [identifier],
+                name: identifier,
+                manufacturer: "Sinara",
+                model: "Booster",
+                sw_version: metadata.firmware_version,
+            },
+        };
+
+        if let Entity::Enable = entity {
+            // The channel state is controlled directly via its run-time setting.
+            write!(
+                &mut config.state_topic,
+                "{}/settings/channel/{}/state",
+                prefix, channel
+            )?;
+            config.command_topic = Some(config.state_topic.clone());
+            config.payload_on = Some("\"Enabled\"");
+            config.payload_off = Some("\"Off\"");
+            return Ok(config);
+        }
+
+        let mut template = String::new();
+        match layout {
+            TelemetryLayout::PerChannel => {
+                write!(
+                    &mut config.state_topic,
+                    "{}/telemetry/ch{}",
+                    prefix, channel
+                )?;
+                write!(&mut template, "{{{{ value_json.{} }}}}", entity.key())?;
+            }
+            TelemetryLayout::Combined => {
+                write!(&mut config.state_topic, "{}/telemetry", prefix)?;
+                write!(
+                    &mut template,
+                    "{{{{ value_json[{}].{} }}}}",
+                    channel,
+                    entity.key()
+                )?;
+            }
+            TelemetryLayout::PerField => {
+                write!(
+                    &mut config.state_topic,
+                    "{}/telemetry/ch{}/{}",
+                    prefix,
+                    channel,
+                    entity.key()
+                )?;
+                write!(&mut template, "{{{{ value_json }}}}")?;
+            }
+        }
+        config.value_template = Some(template);
+
+        Ok(config)
+    }
+}
+
+/// Get the discovery topic of a channel entity.
+///
+/// # Args
+/// * `entity` - The entity to get the topic of.
+/// * `channel` - The index of the channel.
+/// * `identifier` - The unique identifier of this device.
+pub fn topic(
+    entity: Entity,
+    channel: usize,
+    identifier: &str,
+) -> Result<String<128>, core::fmt::Error> {
+    let mut topic = String::new();
+    write!(
+        &mut topic,
+        "{}/{}/{}/ch{}_{}/config",
+        DISCOVERY_PREFIX,
+        entity.component(),
+        identifier,
+        channel,
+        entity.key()
+    )?;
+    Ok(topic)
+}
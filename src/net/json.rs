@@ -0,0 +1,147 @@
+//! Booster NGFW raw JSON scanning routines
+//!
+//! # Design
+//! `serde_json_core` cannot deserialize values of unknown type. These routines locate the raw
+//! serialized values within JSON documents, such that they can be forwarded to the handler of the
+//! value, e.g. a run-time setting.
+
+/// Find the length of the JSON value at the start of the provided data.
+///
+/// # Note
+/// The value is not validated. The returned length may include trailing whitespace for scalar
+/// values.
+pub fn value_len(json: &[u8]) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (index, byte) in json.iter().enumerate() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if *byte == b'\\' {
+                escaped = true;
+            } else if *byte == b'"' {
+                in_string = false;
+                if depth == 0 {
+                    return Some(index + 1);
+                }
+            }
+            continue;
+        }
+
+        match byte {
+            b'"' => in_string = true,
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' if depth == 0 => return Some(index),
+            b'}' | b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index + 1);
+                }
+            }
+            b',' if depth == 0 => return Some(index),
+            _ => {}
+        }
+    }
+
+    (depth == 0 && !in_string).then_some(json.len())
+}
+
+/// Remove leading JSON whitespace.
+pub fn trim_start(json: &[u8]) -> &[u8] {
+    let start = json
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(json.len());
+    &json[start..]
+}
+
+/// Remove trailing JSON whitespace.
+fn trim_end(json: &[u8]) -> &[u8] {
+    let end = json
+        .iter()
+        .rposition(|byte| !byte.is_ascii_whitespace())
+        .map_or(0, |index| index + 1);
+    &json[..end]
+}
+
+/// An iterator over the members of a JSON object.
+pub struct Members<'a> {
+    remainder: Option<&'a [u8]>,
+}
+
+impl<'a> Members<'a> {
+    fn parse(&mut self) -> Option<(&'a [u8], &'a [u8])> {
+        let mut remainder = trim_start(self.remainder?);
+        if remainder.starts_with(b"}") {
+            return None;
+        }
+
+        let key_len = value_len(remainder)?;
+        let key = remainder[..key_len]
+            .strip_prefix(b"\"")?
+            .strip_suffix(b"\"")?;
+
+        remainder = trim_start(&remainder[key_len..]).strip_prefix(b":")?;
+        remainder = trim_start(remainder);
+
+        let value_len = value_len(remainder)?;
+        let value = trim_end(&remainder[..value_len]);
+
+        remainder = trim_start(&remainder[value_len..]);
+        self.remainder = match remainder.first()? {
+            b',' => Some(&remainder[1..]),
+            b'}' => Some(remainder),
+            _ => return None,
+        };
+
+        Some((key, value))
+    }
+}
+
+impl<'a> Iterator for Members<'a> {
+    type Item = (&'a [u8], &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let member = self.parse();
+
+        // Iteration ends on the end of the object or on malformed data.
+        if member.is_none() {
+            self.remainder = None;
+        }
+
+        member
+    }
+}
+
+/// Iterate over the members of a JSON object.
+///
+/// # Note
+/// Keys containing escape sequences are not supported. Iteration ends if malformed data is
+/// encountered.
+///
+/// # Args
+/// * `object` - The serialized JSON object.
+///
+/// # Returns
+/// An iterator over the raw keys (without quotes) and serialized values of the members.
+pub fn members(object: &[u8]) -> Members<'_> {
+    Members {
+        remainder: trim_start(object).strip_prefix(b"{"),
+    }
+}
+
+/// Find the raw value of a member of a JSON object.
+///
+/// # Args
+/// * `object` - The serialized JSON object.
+/// * `key` - The key of the member to find. Keys containing escape sequences are not supported.
+///
+/// # Returns
+/// The serialized value of the member, if it was found. Otherwise, None.
+pub fn member<'a>(object: &'a [u8], key: &str) -> Option<&'a [u8]> {
+    members(object)
+        .find(|(member_key, _)| *member_key == key.as_bytes())
+        .map(|(_, value)| value)
+}
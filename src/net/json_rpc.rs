@@ -0,0 +1,297 @@
+//! Booster JSON-RPC control interface
+//!
+//! # Design
+//! For setups without an MQTT broker, the control and settings functionality of the MQTT interface
+//! is also offered via newline-delimited [JSON-RPC 2.0](https://www.jsonrpc.org/specification) on
+//! a dedicated TCP port. Each request is a single JSON object terminated by a newline, and each
+//! response is likewise terminated by a newline. Notifications (requests without an `id`) are
+//! executed without generating a response. Batch requests are not supported.
+//!
+//! Every command of the MQTT control interface (see [`mqtt_control::COMMANDS`]) is offered as a
+//! method of the same name, with the request payload of the command as its parameters. Commands
+//! are authorized in the same manner as via MQTT. Additionally, the following methods are supported:
+//! * `get` - Read a run-time setting. Params: `{"path": <path>}`
+//! * `set` - Write a run-time setting. Params: `{"path": <path>, "value": <value>}`
+
+use super::{
+    json::{member, trim_start, value_len},
+    line_server::{Line, LineServer},
+    mqtt_control::{self, Error},
+    NetworkStackProxy, SettingsClient,
+};
+use crate::{hardware::setup::MainBus, settings::runtime_settings::RuntimeSettings};
+
+use core::fmt::Write;
+use heapless::{String, Vec};
+use miniconf::JsonCoreSlash;
+
+/// The TCP port that the JSON-RPC server listens on.
+pub const JSON_RPC_PORT: u16 = 5026;
+
+/// The maximum size of the response to a single request.
+pub const RESPONSE_SIZE: usize = 1024;
+
+/// The maximum size of a single request.
+const LINE_SIZE: usize = 512;
+
+/// The maximum size of the result of a single method call.
+const RESULT_SIZE: usize = 512;
+
+/// Standard JSON-RPC error codes.
+const PARSE_ERROR: i32 = -32700;
+const INVALID_REQUEST: i32 = -32600;
+const METHOD_NOT_FOUND: i32 = -32601;
+const INVALID_PARAMS: i32 = -32602;
+const SERVER_ERROR: i32 = -32000;
+
+/// The result of a successful method call.
+pub struct Response {
+    /// The length of the serialized result.
+    pub len: usize,
+
+    /// Specified true if run-time settings were modified by the call.
+    pub settings_updated: bool,
+}
+
+impl From<usize> for Response {
+    fn from(len: usize) -> Self {
+        Self {
+            len,
+            settings_updated: false,
+        }
+    }
+}
+
+/// Indicates why a method call failed.
+pub enum CallError {
+    /// The requested method does not exist.
+    MethodNotFound,
+
+    /// The method failed to execute.
+    Method(Error),
+}
+
+impl From<Error> for CallError {
+    fn from(error: Error) -> Self {
+        CallError::Method(error)
+    }
+}
+
+/// The JSON-RPC error object.
+#[derive(serde::Serialize)]
+struct ErrorObject<'a> {
+    code: i32,
+    message: &'a str,
+}
+
+/// Parameters of settings requests.
+#[derive(serde::Deserialize)]
+struct SettingsRequest<'a> {
+    path: &'a str,
+}
+
+/// Execute a JSON-RPC method call.
+///
+/// # Args
+/// * `main_bus` - The main I2C bus to communicate with RF channels.
+/// * `settings` - The run-time settings client.
+/// * `token` - The configured access-control token.
+/// * `method` - The name of the method to call.
+/// * `params` - The serialized parameters of the call.
+/// * `output` - The location to serialize the result into.
+pub fn execute(
+    main_bus: &mut MainBus,
+    settings: &mut SettingsClient,
+    token: &str,
+    method: &str,
+    params: &[u8],
+    output: &mut [u8],
+) -> Result<Response, CallError> {
+    mqtt_control::check_version(params)?;
+
+    if let Some((_, handler)) = mqtt_control::COMMANDS
+        .iter()
+        .find(|(command, _)| *command == method)
+    {
+        mqtt_control::authorize(token, method, params)?;
+        return Ok(handler(main_bus, method, params, output)?.into());
+    }
+
+    match method {
+        "get" => {
+            let request: SettingsRequest =
+                serde_json_core::from_slice(params).map_err(Error::from)?.0;
+            let len = settings
+                .settings()
+                .get_json(request.path, output)
+                .map_err(|_| Error::Other("Invalid setting path"))?;
+            Ok(len.into())
+        }
+        "set" => {
+            mqtt_control::authorize(token, method, params)?;
+
+            let request: SettingsRequest =
+                serde_json_core::from_slice(params).map_err(Error::from)?.0;
+            let value = member(params, "value").ok_or(Error::Other("Missing value"))?;
+
+            let mut new_settings = settings.settings().clone();
+            new_settings
+                .set_json(request.path, value)
+                .map_err(|_| Error::Other("Invalid setting path or value"))?;
+            RuntimeSettings::handle_update(request.path, settings.settings_mut(), &new_settings)
+                .map_err(Error::from)?;
+
+            // Ensure that the settings reported via MQTT reflect the update.
+            settings.force_republish();
+
+            Ok(Response {
+                len: 0,
+                settings_updated: true,
+            })
+        }
+        _ => Err(CallError::MethodNotFound),
+    }
+}
+
+/// A TCP server for the JSON-RPC control interface.
+pub struct JsonRpcServer {
+    server: LineServer<LINE_SIZE, RESPONSE_SIZE>,
+}
+
+impl JsonRpcServer {
+    /// Construct the JSON-RPC server.
+    ///
+    /// # Args
+    /// * `stack` - The network stack to use for serving requests.
+    pub fn new(stack: NetworkStackProxy) -> Self {
+        Self {
+            server: LineServer::new(stack, JSON_RPC_PORT),
+        }
+    }
+
+    /// Handle the JSON-RPC server.
+    ///
+    /// # Note
+    /// This function must be called periodically to service incoming connections.
+    ///
+    /// # Args
+    /// * `call` - A function that executes a method call with the provided method name and
+    ///   parameters, and serializes the result into the provided buffer.
+    pub fn poll(
+        &mut self,
+        mut call: impl FnMut(&str, &[u8], &mut [u8]) -> Result<usize, CallError>,
+    ) {
+        self.server.poll(|line, response| {
+            let request = match line {
+                Line::Received(line) if line.trim().is_empty() => return,
+                Line::Received(line) => line.as_bytes(),
+                Line::Overflow | Line::Invalid => {
+                    respond_error(response, b"null", PARSE_ERROR, "Parse error");
+                    return;
+                }
+            };
+
+            if value_len(trim_start(request)).is_none() {
+                respond_error(response, b"null", PARSE_ERROR, "Parse error");
+                return;
+            }
+
+            let id = member(request, "id");
+            let method = member(request, "method")
+                .and_then(|method| serde_json_core::from_slice::<&str>(method).ok())
+                .map(|(method, _)| method);
+
+            let Some(method) = method else {
+                respond_error(
+                    response,
+                    id.unwrap_or(b"null"),
+                    INVALID_REQUEST,
+                    "Invalid request",
+                );
+                return;
+            };
+
+            let params = member(request, "params").unwrap_or(b"null");
+            let mut result = [0u8; RESULT_SIZE];
+            let outcome = call(method, params, &mut result);
+
+            // Notifications do not generate a response.
+            let Some(id) = id else {
+                return;
+            };
+
+            match outcome {
+                Ok(len) => {
+                    let result = if len == 0 {
+                        b"null".as_slice()
+                    } else {
+                        &result[..len]
+                    };
+                    respond(response, id, b"result", result);
+                }
+                Err(CallError::MethodNotFound) => {
+                    respond_error(response, id, METHOD_NOT_FOUND, "Method not found")
+                }
+                Err(CallError::Method(error)) => {
+                    let code = match error {
+                        Error::JsonDe(_) => INVALID_PARAMS,
+                        _ => SERVER_ERROR,
+                    };
+
+                    let mut message: String<96> = String::new();
+                    write!(&mut message, "{}", error).ok();
+                    respond_error(response, id, code, &message);
+                }
+            }
+        });
+    }
+}
+
+/// Append a JSON-RPC response to the response buffer.
+///
+/// # Note
+/// Responses that do not fit into the buffer are discarded.
+///
+/// # Args
+/// * `response` - The buffer to append the response to.
+/// * `id` - The serialized ID of the request.
+/// * `kind` - The name of the response member, either `result` or `error`.
+/// * `value` - The serialized value of the response member.
+fn respond(response: &mut Vec<u8, RESPONSE_SIZE>, id: &[u8], kind: &[u8], value: &[u8]) {
+    let len = response.len();
+
+    let parts: [&[u8]; 7] = [
+        b"{\"jsonrpc\":\"2.0\",\"id\":",
+        id,
+        b",\"",
+        kind,
+        b"\":",
+        value,
+        b"}\n",
+    ];
+
+    if parts
+        .iter()
+        .try_for_each(|part| response.extend_from_slice(part))
+        .is_err()
+    {
+        log::warn!("JSON-RPC response overflow");
+        response.truncate(len);
+    }
+}
+
+/// Append a JSON-RPC error response to the response buffer.
+///
+/// # Args
+/// * `response` - The buffer to append the response to.
+/// * `id` - The serialized ID of the request.
+/// * `code` - The JSON-RPC error code.
+/// * `message` - A description of the error.
+fn respond_error(response: &mut Vec<u8, RESPONSE_SIZE>, id: &[u8], code: i32, message: &str) {
+    let mut error = [0u8; 128];
+    let Ok(len) = serde_json_core::to_slice(&ErrorObject { code, message }, &mut error) else {
+        return;
+    };
+
+    respond(response, id, b"error", &error[..len]);
+}
@@ -0,0 +1,163 @@
+//! Newline-delimited TCP command server
+//!
+//! # Design
+//! The line server accepts a single TCP client connection at a time on a fixed port. Received data
+//! is split into newline-terminated lines, which are handed to a handler along with a buffer for
+//! the response. Responses are fully transmitted before any further lines are received.
+
+use super::NetworkStackProxy;
+
+use heapless::Vec;
+use smoltcp_nal::embedded_nal::{nb, TcpClientStack, TcpFullStack};
+
+type TcpSocket = <NetworkStackProxy as TcpClientStack>::TcpSocket;
+
+/// A line received from the client.
+pub enum Line<'a> {
+    /// A complete line was received. The line terminator is not included.
+    Received(&'a str),
+
+    /// A line was received that did not fit into the line buffer and was discarded.
+    Overflow,
+
+    /// A line was received that was not valid UTF-8.
+    Invalid,
+}
+
+/// A TCP server handling newline-delimited requests.
+pub struct LineServer<const LINE_SIZE: usize, const RESPONSE_SIZE: usize> {
+    stack: NetworkStackProxy,
+    port: u16,
+    listener: Option<TcpSocket>,
+    connection: Option<TcpSocket>,
+    line: Vec<u8, LINE_SIZE>,
+    overflowed: bool,
+    response: Vec<u8, RESPONSE_SIZE>,
+    sent: usize,
+}
+
+impl<const LINE_SIZE: usize, const RESPONSE_SIZE: usize> LineServer<LINE_SIZE, RESPONSE_SIZE> {
+    /// Construct the line server.
+    ///
+    /// # Args
+    /// * `stack` - The network stack to use for serving requests.
+    /// * `port` - The TCP port to listen on.
+    pub fn new(stack: NetworkStackProxy, port: u16) -> Self {
+        Self {
+            stack,
+            port,
+            listener: None,
+            connection: None,
+            line: Vec::new(),
+            overflowed: false,
+            response: Vec::new(),
+            sent: 0,
+        }
+    }
+
+    /// Open a socket listening for incoming connections.
+    fn listen(&mut self) -> Option<TcpSocket> {
+        let mut socket = self.stack.socket().ok()?;
+
+        if self.stack.bind(&mut socket, self.port).is_err()
+            || self.stack.listen(&mut socket).is_err()
+        {
+            self.stack.close(socket).ok();
+            return None;
+        }
+
+        Some(socket)
+    }
+
+    /// Close the active client connection.
+    fn disconnect(&mut self) {
+        if let Some(socket) = self.connection.take() {
+            self.stack.close(socket).ok();
+        }
+    }
+
+    /// Handle the line server.
+    ///
+    /// # Note
+    /// This function must be called periodically to service incoming connections.
+    ///
+    /// # Args
+    /// * `handler` - A function that handles a received line and appends any response to the
+    ///   provided buffer. The buffer may already contain responses to previous lines.
+    pub fn poll(&mut self, mut handler: impl FnMut(Line<'_>, &mut Vec<u8, RESPONSE_SIZE>)) {
+        if self.listener.is_none() {
+            self.listener = self.listen();
+        }
+
+        let Some(listener) = self.listener.as_mut() else {
+            return;
+        };
+
+        if self.connection.is_none() {
+            match self.stack.accept(listener) {
+                Ok((socket, _)) => {
+                    self.line.clear();
+                    self.overflowed = false;
+                    self.response.clear();
+                    self.sent = 0;
+                    self.connection.replace(socket);
+                }
+                Err(nb::Error::WouldBlock) => return,
+                Err(nb::Error::Other(_)) => {
+                    // Re-open the listening socket on the next poll.
+                    if let Some(listener) = self.listener.take() {
+                        self.stack.close(listener).ok();
+                    }
+                    return;
+                }
+            }
+        }
+
+        let Some(socket) = self.connection.as_mut() else {
+            return;
+        };
+
+        // Finish transmitting any pending responses before handling further requests.
+        if self.sent < self.response.len() {
+            match self.stack.send(socket, &self.response[self.sent..]) {
+                Ok(len) => self.sent += len,
+                Err(nb::Error::WouldBlock) => {}
+                Err(nb::Error::Other(_)) => self.disconnect(),
+            }
+            return;
+        }
+
+        self.response.clear();
+        self.sent = 0;
+
+        let mut buffer = [0u8; 64];
+        let len = match self.stack.receive(socket, &mut buffer) {
+            Ok(len) => len,
+            Err(nb::Error::WouldBlock) => return,
+            Err(nb::Error::Other(_)) => {
+                self.disconnect();
+                return;
+            }
+        };
+
+        for byte in buffer[..len].iter().copied() {
+            if byte != b'\n' {
+                if self.line.push(byte).is_err() {
+                    self.overflowed = true;
+                }
+                continue;
+            }
+
+            let line = if core::mem::take(&mut self.overflowed) {
+                Line::Overflow
+            } else if let Ok(line) = core::str::from_utf8(&self.line) {
+                Line::Received(line.trim_end_matches('\r'))
+            } else {
+                Line::Invalid
+            };
+
+            handler(line, &mut self.response);
+            self.line.clear();
+        }
+    }
+}
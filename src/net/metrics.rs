@@ -0,0 +1,341 @@
+//! Booster Prometheus metrics exposition
+//!
+//! # Design
+//! A minimal HTTP/1.0 server is run on the spare TCP sockets of the network stack. It serves a
+//! single `GET /metrics` endpoint containing channel and chassis measurements in the Prometheus
+//! text exposition format. Only a single connection is serviced at a time and each connection is
+//! closed once the response has been sent.
+
+use super::NetworkStackProxy;
+use crate::hardware::{rf_channel::ChannelStatus, setup::MainBus, Channel};
+
+use core::fmt::Write;
+use heapless::{String, Vec};
+use smoltcp_nal::embedded_nal::{nb, TcpClientStack, TcpFullStack};
+
+/// The TCP port that the metrics server listens on.
+pub const METRICS_PORT: u16 = 80;
+
+/// The maximum size of a rendered metrics response, including the HTTP header.
+pub const RESPONSE_SIZE: usize = 6144;
+
+const HEADER_OK: &str =
+    "HTTP/1.0 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nConnection: close\r\n\r\n";
+const HEADER_NOT_FOUND: &str = "HTTP/1.0 404 Not Found\r\nConnection: close\r\n\r\n";
+const HEADER_ERROR: &str = "HTTP/1.0 500 Internal Server Error\r\nConnection: close\r\n\r\n";
+
+type TcpSocket = <NetworkStackProxy as TcpClientStack>::TcpSocket;
+
+/// The state of a client connection to the server.
+enum State {
+    /// The HTTP request is being received.
+    Receiving,
+
+    /// The HTTP request has been received and is waiting for its response.
+    Pending,
+
+    /// The response is being transmitted. The number of bytes already sent is stored.
+    Sending(usize),
+}
+
+/// A minimal HTTP server for exposing Prometheus metrics.
+pub struct MetricsServer {
+    stack: NetworkStackProxy,
+    listener: Option<TcpSocket>,
+    connection: Option<(TcpSocket, State)>,
+    request: Vec<u8, 256>,
+    response: &'static mut String<RESPONSE_SIZE>,
+}
+
+impl MetricsServer {
+    /// Construct the metrics server.
+    ///
+    /// # Note
+    /// This may only be called once.
+    ///
+    /// # Args
+    /// * `stack` - The network stack to use for serving requests.
+    pub fn new(stack: NetworkStackProxy) -> Self {
+        Self {
+            stack,
+            listener: None,
+            connection: None,
+            request: Vec::new(),
+            response: cortex_m::singleton!(: String<RESPONSE_SIZE> = String::new()).unwrap(),
+        }
+    }
+
+    /// Open a socket listening for incoming metrics connections.
+    fn listen(&mut self) -> Option<TcpSocket> {
+        let mut socket = self.stack.socket().ok()?;
+
+        if self.stack.bind(&mut socket, METRICS_PORT).is_err()
+            || self.stack.listen(&mut socket).is_err()
+        {
+            self.stack.close(socket).ok();
+            return None;
+        }
+
+        Some(socket)
+    }
+
+    /// Check if a received request is waiting for its response.
+    ///
+    /// # Returns
+    /// True if the response must be provided with [MetricsServer::respond].
+    pub fn pending(&self) -> bool {
+        matches!(self.connection, Some((_, State::Pending)))
+    }
+
+    /// Provide the response to a received request.
+    ///
+    /// # Note
+    /// Requests for paths other than `/metrics` are answered without the metrics.
+    ///
+    /// # Args
+    /// * `metrics` - The metrics measured after the request was received.
+    pub fn respond(&mut self, metrics: &Metrics) {
+        let Some((_, state)) = self.connection.as_mut() else {
+            return;
+        };
+
+        if !matches!(state, State::Pending) {
+            return;
+        }
+
+        *state = State::Sending(0);
+        self.response.clear();
+
+        let request = core::str::from_utf8(&self.request).unwrap_or("");
+        if !request.starts_with("GET /metrics ") {
+            self.response.push_str(HEADER_NOT_FOUND).ok();
+            return;
+        }
+
+        self.response.push_str(HEADER_OK).ok();
+        if metrics.render(self.response).is_err() {
+            log::warn!("Metrics response overflow");
+            self.response.clear();
+            self.response.push_str(HEADER_ERROR).ok();
+        }
+    }
+
+    /// Handle the metrics server.
+    ///
+    /// # Note
+    /// This function must be called periodically to service incoming connections. Once a request
+    /// has been received, [MetricsServer::pending] is true until the response is provided.
+    pub fn poll(&mut self) {
+        if self.listener.is_none() {
+            self.listener = self.listen();
+        }
+
+        let Some(listener) = self.listener.as_mut() else {
+            return;
+        };
+
+        if self.connection.is_none() {
+            match self.stack.accept(listener) {
+                Ok((socket, _)) => {
+                    self.request.clear();
+                    self.connection.replace((socket, State::Receiving));
+                }
+                Err(nb::Error::WouldBlock) => return,
+                Err(nb::Error::Other(_)) => {
+                    // Re-open the listening socket on the next poll.
+                    if let Some(listener) = self.listener.take() {
+                        self.stack.close(listener).ok();
+                    }
+                    return;
+                }
+            }
+        }
+
+        let Some((socket, state)) = self.connection.as_mut() else {
+            return;
+        };
+
+        let finished = match state {
+            State::Receiving => {
+                let mut buffer = [0u8; 64];
+                let space = (self.request.capacity() - self.request.len()).min(buffer.len());
+
+                let failed = match self.stack.receive(socket, &mut buffer[..space]) {
+                    Ok(len) => {
+                        self.request.extend_from_slice(&buffer[..len]).ok();
+                        false
+                    }
+                    Err(nb::Error::WouldBlock) => false,
+                    Err(nb::Error::Other(_)) => true,
+                };
+
+                // Only the request line is of interest, so a request that doesn't fit into the
+                // buffer is still processed.
+                let complete = self.request.is_full()
+                    || self.request.windows(4).any(|window| window == b"\r\n\r\n");
+
+                if complete && !failed {
+                    *state = State::Pending;
+                }
+
+                failed
+            }
+            State::Pending => false,
+            State::Sending(sent) => {
+                match self.stack.send(socket, &self.response.as_bytes()[*sent..]) {
+                    Ok(len) => {
+                        *sent += len;
+                        *sent >= self.response.len()
+                    }
+                    Err(nb::Error::WouldBlock) => false,
+                    Err(nb::Error::Other(_)) => true,
+                }
+            }
+        };
+
+        if finished {
+            if let Some((socket, _)) = self.connection.take() {
+                self.stack.close(socket).ok();
+            }
+        }
+    }
+}
+
+/// A snapshot of the Booster measurements exposed as metrics.
+#[derive(Default)]
+pub struct Metrics {
+    channels: [Option<(ChannelStatus, u32, u32)>; 8],
+    fans: Option<[u16; 6]>,
+}
+
+impl Metrics {
+    /// Measure a single RF channel.
+    ///
+    /// # Note
+    /// Channels are measured individually so that the main bus is only locked briefly. Channels
+    /// that cannot be measured are omitted.
+    ///
+    /// # Args
+    /// * `main_bus` - The main I2C bus to communicate with RF channels.
+    /// * `idx` - The channel to measure.
+    pub fn measure_channel(&mut self, main_bus: &mut MainBus, idx: Channel) {
+        self.channels[idx as usize] = main_bus.channels.channel_mut(idx).and_then(|channel| {
+            let trips = channel.context().interlock_trips();
+            let writes = channel.context().eeprom_writes();
+            Some((channel.get_status().ok()?, trips, writes))
+        });
+    }
+
+    /// Measure the chassis fans.
+    ///
+    /// # Note
+    /// The fan speeds are omitted if they cannot be read.
+    ///
+    /// # Args
+    /// * `main_bus` - The main I2C bus to communicate with the fans.
+    pub fn measure_fans(&mut self, main_bus: &mut MainBus) {
+        self.fans = main_bus.fans.read_rpms().ok();
+    }
+
+    /// Render the metrics in the Prometheus text exposition format.
+    ///
+    /// # Args
+    /// * `output` - The location to write the metrics into.
+    fn render(&self, output: &mut impl Write) -> core::fmt::Result {
+        let channels = &self.channels;
+        let gauges: [(&str, &str, fn(&ChannelStatus) -> f32); 8] = [
+            (
+                "booster_temperature_celsius",
+                "Channel temperature",
+                |status| status.temperature,
+            ),
+            (
+                "booster_p28v_current_amperes",
+                "Channel P28V rail current",
+                |status| status.p28v_current,
+            ),
+            (
+                "booster_p5v_current_amperes",
+                "Channel P5V rail current",
+                |status| status.p5v_current,
+            ),
+            (
+                "booster_p5v_voltage_volts",
+                "Channel P5V rail voltage",
+                |status| status.p5v_voltage,
+            ),
+            ("booster_input_power_dbm", "Channel input power", |status| {
+                status.input_power
+            }),
+            (
+                "booster_output_power_dbm",
+                "Channel output power",
+                |status| status.output_power,
+            ),
+            (
+                "booster_reflected_power_dbm",
+                "Channel reflected power",
+                |status| status.reflected_power,
+            ),
+            (
+                "booster_alert",
+                "Channel supply alert is active",
+                |status| status.alert as u8 as f32,
+            ),
+        ];
+
+        for (name, help, value) in gauges.iter() {
+            writeln!(output, "# HELP {name} {help}.")?;
+            writeln!(output, "# TYPE {name} gauge")?;
+            for (idx, (status, _, _)) in channels
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, channel)| channel.as_ref().map(|channel| (idx, channel)))
+            {
+                writeln!(output, "{name}{{channel=\"{idx}\"}} {}", value(status))?;
+            }
+        }
+
+        writeln!(
+            output,
+            "# HELP booster_interlock_trips_total Channel interlock trips since boot."
+        )?;
+        writeln!(output, "# TYPE booster_interlock_trips_total counter")?;
+        for (idx, (_, trips, _)) in channels
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, channel)| channel.as_ref().map(|channel| (idx, channel)))
+        {
+            writeln!(
+                output,
+                "booster_interlock_trips_total{{channel=\"{idx}\"}} {trips}"
+            )?;
+        }
+
+        writeln!(
+            output,
+            "# HELP booster_eeprom_writes_total Channel settings writes to the RF module EEPROM."
+        )?;
+        writeln!(output, "# TYPE booster_eeprom_writes_total counter")?;
+        for (idx, (_, _, writes)) in channels
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, channel)| channel.as_ref().map(|channel| (idx, channel)))
+        {
+            writeln!(
+                output,
+                "booster_eeprom_writes_total{{channel=\"{idx}\"}} {writes}"
+            )?;
+        }
+
+        if let Some(fans) = &self.fans {
+            writeln!(output, "# HELP booster_fan_speed_rpm Chassis fan speed.")?;
+            writeln!(output, "# TYPE booster_fan_speed_rpm gauge")?;
+            for (idx, rpms) in fans.iter().enumerate() {
+                writeln!(output, "booster_fan_speed_rpm{{fan=\"{idx}\"}} {rpms}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
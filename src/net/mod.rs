@@ -3,26 +3,130 @@
 use crate::hardware::{setup::MainBus, NetworkStack, SystemTimer};
 
 use core::fmt::Write;
+use core::sync::atomic::{AtomicBool, Ordering};
 use heapless::String;
+use minireq::minimq::broker::Broker;
+use smoltcp_nal::smoltcp::wire::HardwareAddress;
 
+pub mod discovery;
+pub mod json;
+pub mod json_rpc;
+pub mod line_server;
+pub mod metrics;
 pub mod mqtt_control;
+pub mod scpi;
+pub mod syslog;
 
 type NetworkStackProxy = smoltcp_nal::shared::NetworkStackProxy<'static, NetworkStack>;
 
+pub type SettingsClient = miniconf::MqttClient<
+    'static,
+    crate::RuntimeSettings,
+    NetworkStackProxy,
+    SystemTimer,
+    minireq::minimq::broker::NamedBroker<NetworkStackProxy>,
+    4,
+>;
+
+/// The MQTT clients that process requests from hosts.
+#[derive(Copy, Clone, Debug)]
+pub enum Client {
+    /// The Miniconf run-time settings client.
+    Settings,
+
+    /// The Minireq control client.
+    Control,
+}
+
+/// Specified true for each [Client] whose most recent processing succeeded.
+static CLIENT_OK: [AtomicBool; 2] = [AtomicBool::new(false), AtomicBool::new(false)];
+
+/// Specified true while the telemetry client is connected to the broker.
+static BROKER_CONNECTED: AtomicBool = AtomicBool::new(false);
+
+/// Record the outcome of processing a client.
+///
+/// # Args
+/// * `client` - The client that was processed.
+/// * `ok` - Specified true if processing succeeded.
+pub fn report_client(client: Client, ok: bool) {
+    CLIENT_OK[client as usize].store(ok, Ordering::Relaxed);
+}
+
+/// Check whether the telemetry client is currently connected to the broker.
+pub fn broker_connected() -> bool {
+    BROKER_CONNECTED.load(Ordering::Relaxed)
+}
+
+/// The state of the settings and control clients, sampled when serialized.
+///
+/// # Note
+/// A client is reported as connected while the broker is connected and the most recent processing
+/// of the client succeeded.
+pub struct ClientStatus;
+
+impl serde::Serialize for ClientStatus {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let broker = BROKER_CONNECTED.load(Ordering::Relaxed);
+        let connected =
+            |client: Client| broker && CLIENT_OK[client as usize].load(Ordering::Relaxed);
+
+        let mut status = serializer.serialize_struct("ClientStatus", 3)?;
+        status.serialize_field("broker", &broker)?;
+        status.serialize_field("settings", &connected(Client::Settings))?;
+        status.serialize_field("control", &connected(Client::Control))?;
+        status.end()
+    }
+}
+
+/// The network subsystems compiled into the firmware.
+pub const SUBSYSTEMS: &[&str] = &[
+    "telemetry",
+    "settings",
+    "control",
+    "metrics",
+    "scpi",
+    "json-rpc",
+    "syslog",
+];
+
+/// The size of the MQTT telemetry client session state.
+///
+/// # Note
+/// The session state retains publications at QoS 1 until they are acknowledged by the broker. It
+/// is sized for a number of events and per-channel telemetry messages in flight.
+pub const TELEMETRY_SESSION_SIZE: usize = 1024;
+
+/// The size of the MQTT telemetry client buffer.
+///
+/// # Note
+/// The telemetry buffer is sized to contain combined telemetry for all 8 RF channels in addition
+/// to the session state.
+pub const TELEMETRY_BUFFER_SIZE: usize = 4096 + TELEMETRY_SESSION_SIZE;
+
+/// The size of the MQTT settings client buffer.
+pub const SETTINGS_BUFFER_SIZE: usize = 1024;
+
+/// The size of the MQTT control client buffer.
+pub const CONTROL_BUFFER_SIZE: usize = 1024;
+
 pub struct MqttStorage {
-    telemetry: [u8; 1024],
-    settings: [u8; 1024],
-    control: [u8; 1024],
-    minireq_handlers: [minireq::HandlerSlot<'static, MainBus, mqtt_control::Error>; 2],
+    telemetry: [u8; TELEMETRY_BUFFER_SIZE],
+    settings: [u8; SETTINGS_BUFFER_SIZE],
+    control: [u8; CONTROL_BUFFER_SIZE],
+    minireq_handlers:
+        [minireq::HandlerSlot<'static, MainBus, mqtt_control::Error>; mqtt_control::COMMANDS.len()],
 }
 
 impl Default for MqttStorage {
     fn default() -> Self {
         Self {
-            telemetry: [0u8; 1024],
-            settings: [0u8; 1024],
-            control: [0u8; 1024],
-            minireq_handlers: [None, None],
+            telemetry: [0u8; TELEMETRY_BUFFER_SIZE],
+            settings: [0u8; SETTINGS_BUFFER_SIZE],
+            control: [0u8; CONTROL_BUFFER_SIZE],
+            minireq_handlers: core::array::from_fn(|_| None),
         }
     }
 }
@@ -34,14 +138,11 @@ impl Default for MqttStorage {
 /// potential pre-emption when using the `shared` network stack.
 pub struct NetworkDevices {
     pub telemetry: mqtt_control::TelemetryClient,
-    pub settings: miniconf::MqttClient<
-        'static,
-        crate::RuntimeSettings,
-        NetworkStackProxy,
-        SystemTimer,
-        minireq::minimq::broker::NamedBroker<NetworkStackProxy>,
-        4,
-    >,
+    pub settings: SettingsClient,
+    pub metrics: metrics::MetricsServer,
+    pub scpi: scpi::ScpiServer,
+    pub json_rpc: json_rpc::JsonRpcServer,
+    pub syslog: syslog::SyslogClient,
     pub control: minireq::Minireq<
         'static,
         MainBus,
@@ -50,7 +151,10 @@ pub struct NetworkDevices {
         minireq::minimq::broker::NamedBroker<NetworkStackProxy>,
         mqtt_control::Error,
     >,
+    pub control_token: String<32>,
+    pub protect_mqtt_settings: bool,
     stack: NetworkStackProxy,
+    link_up: bool,
 }
 
 impl NetworkDevices {
@@ -58,17 +162,21 @@ impl NetworkDevices {
     ///
     /// # Args
     /// * `broker` - The broker IP address for MQTT.
+    /// * `broker_port` - The TCP port of the MQTT broker.
     /// * `stack` - The network stack to use for communications.
     /// * `identifier` - The unique identifier of this device.
+    /// * `control_token` - The access-control token required for state-changing requests.
     pub fn new(
         broker: &str,
+        broker_port: u16,
         stack: NetworkStack,
         identifier: &str,
+        control_token: &str,
         settings: crate::RuntimeSettings,
         clock: SystemTimer,
         metadata: &'static crate::hardware::metadata::ApplicationMetadata,
     ) -> Self {
-        log::info!("Using MQTT broker: `{broker}`");
+        log::info!("Using MQTT broker: `{broker}:{broker_port}`");
         let shared =
             cortex_m::singleton!(: smoltcp_nal::shared::NetworkManager<'static, crate::hardware::Mac, crate::hardware::SystemTimer> = smoltcp_nal::shared::NetworkManager::new(stack))
                 .unwrap();
@@ -82,8 +190,9 @@ impl NetworkDevices {
             let mut client_id: String<128> = String::new();
             write!(&mut client_id, "booster-{}-req", identifier).unwrap();
 
-            let broker =
+            let mut broker =
                 minireq::minimq::broker::NamedBroker::new(broker, shared.acquire_stack()).unwrap();
+            broker.set_port(broker_port);
             let config = minireq::minimq::ConfigBuilder::new(broker, &mut store.settings)
                 .client_id(&client_id)
                 .unwrap();
@@ -92,12 +201,9 @@ impl NetworkDevices {
             let mut control =
                 minireq::Minireq::new(&prefix, mqtt, &mut store.minireq_handlers).unwrap();
 
-            control
-                .register("save", mqtt_control::save_settings)
-                .unwrap();
-            control
-                .register("read-bias", mqtt_control::read_bias)
-                .unwrap();
+            for (command, handler) in mqtt_control::COMMANDS {
+                control.register(command, handler).unwrap();
+            }
 
             control
         };
@@ -106,18 +212,29 @@ impl NetworkDevices {
             let mut client_id: String<64> = String::new();
             write!(&mut client_id, "booster-{}-tlm", identifier).unwrap();
 
-            let broker =
+            let mut broker =
                 minireq::minimq::broker::NamedBroker::new(broker, shared.acquire_stack()).unwrap();
+            broker.set_port(broker_port);
             let config = miniconf::minimq::ConfigBuilder::new(broker, &mut store.telemetry)
                 // The telemetry client doesn't do much in terms of receiving data, so reserve the
                 // buffer for transmission.
                 .rx_buffer(miniconf::minimq::config::BufferConfig::Maximum(100))
+                .session_state(miniconf::minimq::config::BufferConfig::Exactly(
+                    TELEMETRY_SESSION_SIZE,
+                ))
                 .client_id(&client_id)
                 .unwrap();
+            let mut installed_channels = [false; 8];
+            for (installed, channel) in installed_channels.iter_mut().zip(settings.channel.iter()) {
+                *installed = channel.is_some();
+            }
+
             mqtt_control::TelemetryClient::new(
                 minimq::Minimq::new(shared.acquire_stack(), clock, config),
                 metadata,
                 &prefix,
+                identifier,
+                installed_channels,
             )
         };
 
@@ -125,8 +242,9 @@ impl NetworkDevices {
             let mut client_id: String<128> = String::new();
             write!(&mut client_id, "booster-{}-settings", identifier).unwrap();
 
-            let broker =
+            let mut broker =
                 minireq::minimq::broker::NamedBroker::new(broker, shared.acquire_stack()).unwrap();
+            broker.set_port(broker_port);
             let config = miniconf::minimq::ConfigBuilder::new(broker, &mut store.control)
                 .client_id(&client_id)
                 .unwrap();
@@ -138,7 +256,62 @@ impl NetworkDevices {
             telemetry,
             control,
             settings,
+            metrics: metrics::MetricsServer::new(shared.acquire_stack()),
+            scpi: scpi::ScpiServer::new(shared.acquire_stack(), metadata, identifier),
+            json_rpc: json_rpc::JsonRpcServer::new(shared.acquire_stack()),
+            syslog: syslog::SyslogClient::new(shared.acquire_stack(), identifier),
+            control_token: String::from(control_token),
+            protect_mqtt_settings: false,
             stack: shared.acquire_stack(),
+            link_up: false,
+        }
+    }
+
+    /// Check the state of the ethernet link and handle any changes.
+    ///
+    /// # Note
+    /// This function must be called periodically to detect link changes.
+    pub fn handle_link(&mut self) {
+        let was_up = self.link_up;
+
+        let (up, status) = self.stack.lock(|stack| {
+            let up = stack.device_mut().is_link_up();
+
+            match (was_up, up) {
+                (false, true) => {
+                    // Announce our address so that peers re-learn it quickly. If the address is
+                    // acquired via DHCP, it is announced during acquisition instead.
+                    let interface = stack.interface();
+                    if let (HardwareAddress::Ethernet(mac), Some(address)) =
+                        (interface.hardware_addr(), interface.ipv4_addr())
+                    {
+                        stack.device_mut().announce(mac, address);
+                    }
+                }
+                (true, false) => stack.handle_link_reset(),
+                _ => {}
+            }
+
+            let status = if up && !was_up {
+                stack.device_mut().link_status()
+            } else {
+                None
+            };
+
+            (up, status)
+        });
+
+        if up != was_up {
+            match status {
+                Some(status) => log::info!(
+                    "Ethernet link up at {} Mbps {} duplex",
+                    status.speed_mbps,
+                    if status.full_duplex { "full" } else { "half" }
+                ),
+                None => log::info!("Ethernet link {}", if up { "up" } else { "down" }),
+            }
+            self.link_up = up;
+            self.telemetry.report_link(up, status);
         }
     }
 
@@ -149,6 +322,7 @@ impl NetworkDevices {
     /// state management.
     pub fn process(&mut self) -> bool {
         self.telemetry.update();
+        self.syslog.update(|| crate::LOGGER.dequeue_syslog());
 
         self.stack.lock(|stack| stack.poll()).unwrap_or(true)
     }
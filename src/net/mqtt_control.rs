@@ -1,16 +1,33 @@
 //! Booster NGFW Application
 
 use crate::{
-    hardware::{metadata::ApplicationMetadata, setup::MainBus, SystemTimer},
+    hardware::{
+        external_mac::LinkStatus,
+        metadata::{ApplicationMetadata, Eui48},
+        net_interface,
+        platform::ResetTarget,
+        rf_channel::Warnings,
+        setup::MainBus,
+        SystemTimer,
+    },
+    protection::ShedReason,
+    settings::{
+        channel_settings::{ValidationError, CHANNEL_PROPERTIES},
+        deployment_lock,
+        global_settings::ModuleChange,
+    },
+    statistics::{self, Event, Statistics},
+    telemetry::{Maintenance, PowerUnit, Precision, TelemetrySchema},
     Channel,
 };
+use systick_monotonic::fugit::ExtU64;
 
 use minimq::{DeferredPublication, Publication};
 
-use super::NetworkStackProxy;
+use super::{discovery, json, NetworkStackProxy};
 
 use core::fmt::Write;
-use heapless::String;
+use heapless::{Deque, String};
 use serde::Serialize;
 
 /// Default metadata message if formatting errors occur.
@@ -19,9 +36,85 @@ const DEFAULT_METADATA: &str = "{\"message\":\"Truncated: See USB terminal\"}";
 /// The default telemetry period.
 pub const DEFAULT_TELEMETRY_PERIOD_SECS: u64 = 10;
 
+/// Specifies how channel telemetry is laid out into MQTT messages.
+#[derive(serde::Serialize, serde::Deserialize, Copy, Clone, Debug, PartialEq)]
+pub enum TelemetryLayout {
+    /// Each channel is published in a separate message on `<prefix>/telemetry/ch<N>`.
+    PerChannel,
+
+    /// All channels are published in a single message on `<prefix>/telemetry` as an array indexed
+    /// by channel. Absent channels are reported as `null`.
+    Combined,
+
+    /// Each measurement of a channel is published in a separate message on
+    /// `<prefix>/telemetry/ch<N>/<field>`, such that topics map directly to metrics.
+    PerField,
+}
+
+/// The MQTT quality of service of publications.
+#[derive(serde::Serialize, serde::Deserialize, Copy, Clone, Debug, PartialEq)]
+pub enum Qos {
+    /// Messages are published once and may be lost.
+    AtMostOnce,
+
+    /// Messages are retransmitted until acknowledged by the broker.
+    AtLeastOnce,
+}
+
+impl From<Qos> for minimq::QoS {
+    fn from(qos: Qos) -> Self {
+        match qos {
+            Qos::AtMostOnce => minimq::QoS::AtMostOnce,
+            Qos::AtLeastOnce => minimq::QoS::AtLeastOnce,
+        }
+    }
+}
+
+/// The publication options of a class of topics.
+#[derive(serde::Serialize, serde::Deserialize, miniconf::Tree, Copy, Clone, Debug, PartialEq)]
+pub struct PublicationOptions {
+    /// The MQTT quality of service of publications.
+    pub qos: Qos,
+
+    /// Specified true if the broker retains the last publication on each topic.
+    pub retain: bool,
+}
+
+impl Default for PublicationOptions {
+    fn default() -> Self {
+        Self {
+            qos: Qos::AtMostOnce,
+            retain: false,
+        }
+    }
+}
+
+/// The publication options of each class of topics.
+///
+/// # Note
+/// The build information, control protocol, telemetry schema, and discovery configurations are
+/// always retained, independent of these options.
+#[derive(
+    serde::Serialize, serde::Deserialize, miniconf::Tree, Copy, Clone, Debug, Default, PartialEq,
+)]
+pub struct PublicationSettings {
+    /// The options of topics below `<prefix>/telemetry`.
+    #[tree(depth(1))]
+    pub telemetry: PublicationOptions,
+
+    /// The options of topics below `<prefix>/event`.
+    #[tree(depth(1))]
+    pub events: PublicationOptions,
+
+    /// The options of the `<prefix>/alive/meta` topic.
+    #[tree(depth(1))]
+    pub alive: PublicationOptions,
+}
+
 pub enum Error {
     JsonDe(serde_json_core::de::Error),
     JsonSer(serde_json_core::ser::Error),
+    Device(crate::Error),
     Other(&'static str),
 }
 
@@ -37,6 +130,12 @@ impl From<serde_json_core::ser::Error> for Error {
     }
 }
 
+impl From<crate::Error> for Error {
+    fn from(e: crate::Error) -> Self {
+        Self::Device(e)
+    }
+}
+
 impl From<&'static str> for Error {
     fn from(e: &'static str) -> Self {
         Self::Other(e)
@@ -55,7 +154,140 @@ impl core::fmt::Display for Error {
             Error::JsonSer(e) => {
                 write!(f, "{}", e)
             }
+            Error::Device(e) => {
+                write!(f, "{}", e)
+            }
+        }
+    }
+}
+
+/// The version of the control protocol. The version is incremented whenever commands are removed
+/// or the format of their requests or responses changes incompatibly.
+pub const API_VERSION: u32 = 1;
+
+/// A handler of a control command.
+type Handler = fn(&mut MainBus, &str, &[u8], &mut [u8]) -> Result<usize, Error>;
+
+/// The commands of the MQTT control interface and their handlers.
+pub const COMMANDS: [(&str, Handler); 23] = [
+    ("save", save_settings),
+    ("read-bias", read_bias),
+    ("read-raw", read_raw),
+    ("i2c/scan", scan_i2c),
+    ("rearm", rearm),
+    ("channel/retry", retry_channel),
+    ("read-identity", read_identity),
+    ("reboot", reboot),
+    ("dfu", dfu),
+    ("republish-settings", republish_settings),
+    ("lock", lock),
+    ("unlock", unlock),
+    ("channel/clone", clone_channel),
+    ("channel/read", read_channel),
+    ("channel/write", write_channel),
+    ("factory-reset", factory_reset),
+    ("apply-network", apply_network),
+    ("identify", identify),
+    ("clock/set", set_clock),
+    ("maintenance/clear", clear_maintenance),
+    ("schedule/hold", hold_schedule),
+    ("stats", read_statistics),
+    ("checksums", read_checksums),
+];
+
+/// Commands of the control interfaces that modify the state of the device.
+const STATE_CHANGING_COMMANDS: &[&str] = &[
+    "save",
+    "set",
+    "rearm",
+    "channel/retry",
+    "reboot",
+    "dfu",
+    "apply-network",
+    "factory-reset",
+    "channel/clone",
+    "channel/write",
+    "clock/set",
+    "maintenance/clear",
+    "schedule/hold",
+];
+
+/// The serialized properties of a `channel/write` request.
+pub type ChannelProperties = heapless::Vec<u8, 256>;
+
+/// The delay between acknowledging a reset request and resetting the device in milliseconds. This
+/// allows the response to the request to be transmitted.
+pub const RESET_DELAY_MS: u64 = 500;
+
+/// The control protocol version a control request was written for.
+#[derive(serde::Deserialize)]
+struct ApiVersion {
+    api_version: Option<u32>,
+}
+
+/// Check that a control request is compatible with the control protocol of the device.
+///
+/// # Note
+/// Requests that do not specify an `api_version` are accepted.
+///
+/// # Args
+/// * `request` - The serialized request.
+pub fn check_version(request: &[u8]) -> Result<(), Error> {
+    let requested = serde_json_core::from_slice::<ApiVersion>(request)
+        .ok()
+        .and_then(|(version, _)| version.api_version);
+
+    match requested {
+        Some(version) if version != API_VERSION => {
+            log::warn!("Rejected request for API version {version}");
+            Err("Unsupported API version".into())
         }
+        _ => Ok(()),
+    }
+}
+
+/// The access-control token provided with a control request.
+#[derive(serde::Deserialize)]
+struct Authorization<'a> {
+    token: Option<&'a str>,
+}
+
+/// Check that a control request is authorized.
+///
+/// # Note
+/// Only state-changing commands require authorization. While the device is locked, all
+/// state-changing commands are rejected.
+///
+/// # Args
+/// * `token` - The configured access-control token. If empty, all requests are authorized.
+/// * `command` - The name (or topic) of the requested command.
+/// * `request` - The serialized request, which must contain a matching `token` member.
+pub fn authorize(token: &str, command: &str, request: &[u8]) -> Result<(), Error> {
+    let command = command
+        .rsplit_once("/command/")
+        .map_or(command, |(_, command)| command);
+    if !STATE_CHANGING_COMMANDS.contains(&command) {
+        return Ok(());
+    }
+
+    if deployment_lock::is_locked() {
+        log::warn!("Rejected `{command}` request while locked");
+        return Err("Device locked".into());
+    }
+
+    if token.is_empty() {
+        return Ok(());
+    }
+
+    let provided = serde_json_core::from_slice::<Authorization>(request)
+        .ok()
+        .and_then(|(authorization, _)| authorization.token);
+
+    if provided == Some(token) {
+        Ok(())
+    } else {
+        log::warn!("Rejected unauthorized `{command}` request");
+        Err("Unauthorized".into())
     }
 }
 
@@ -65,6 +297,90 @@ struct ChannelRequest {
     pub channel: Channel,
 }
 
+/// Identifiers of the device and its installed RF modules.
+#[derive(serde::Serialize)]
+struct IdentityResponse<'a> {
+    eui48: Eui48,
+    firmware_version: &'a str,
+    channels: [Option<Eui48>; 8],
+}
+
+/// Specifies a request to identify the device.
+#[derive(serde::Deserialize, Debug)]
+struct IdentifyRequest {
+    /// The number of seconds to blink the front-panel LEDs for.
+    duration: u32,
+}
+
+/// Specifies a request to reset the device.
+#[derive(serde::Deserialize, Debug)]
+struct ResetRequest<'a> {
+    /// Must match the name of the requested command to confirm the reset.
+    confirm: &'a str,
+}
+
+/// Specifies a request to lock or unlock the device.
+#[derive(serde::Deserialize, Debug)]
+struct LockRequest<'a> {
+    /// The configured lock key.
+    key: &'a str,
+}
+
+/// Specifies a request to set the real-time clock.
+#[derive(serde::Deserialize, Debug)]
+struct ClockRequest {
+    /// The number of seconds since 1970-01-01T00:00:00Z.
+    time: u32,
+}
+
+/// Specifies a request to hold or release the schedule of a channel.
+#[derive(serde::Deserialize, Debug)]
+struct HoldRequest {
+    /// The channel whose schedule to hold.
+    channel: Channel,
+
+    /// Specified true to suspend the schedule until released.
+    hold: bool,
+}
+
+/// Specifies a request to copy the configuration of a channel.
+#[derive(serde::Deserialize, Debug)]
+struct CloneRequest {
+    /// The channel to copy the configuration from.
+    source: Channel,
+
+    /// The channel to copy the configuration to. If absent, all other channels are configured.
+    #[serde(default)]
+    destination: Option<Channel>,
+}
+
+/// Specifies a request to read properties of a channel.
+#[derive(serde::Deserialize, Debug)]
+struct ReadChannelRequest<'a> {
+    channel: Channel,
+
+    /// The names of the properties to read. If absent, all properties are read.
+    #[serde(borrow, default)]
+    properties: heapless::Vec<&'a str, CHANNEL_PROPERTIES>,
+}
+
+/// Specifies a request to restore factory defaults.
+#[derive(serde::Deserialize, Debug)]
+struct FactoryResetRequest {
+    /// The confirmation code of a previously armed reset. If absent, the reset is armed.
+    confirm: Option<u32>,
+
+    /// Specified true if channel calibration transforms should be kept.
+    #[serde(default)]
+    preserve_calibration: bool,
+}
+
+/// Indicates the confirmation code of an armed factory reset.
+#[derive(serde::Serialize)]
+struct FactoryResetResponse {
+    confirm: u32,
+}
+
 /// Indicates the result of a channel bias setting request.
 #[derive(serde::Serialize)]
 struct ChannelBiasResponse {
@@ -72,6 +388,129 @@ struct ChannelBiasResponse {
     pub ids: f32,
 }
 
+/// Sizing of statically-allocated network buffers in bytes.
+#[derive(serde::Serialize)]
+struct BufferSizes {
+    tcp_sockets: usize,
+    tcp_rx: usize,
+    tcp_tx: usize,
+    server_tcp_sockets: usize,
+    server_tcp_rx: usize,
+    server_tcp_tx: usize,
+    mqtt_telemetry: usize,
+    mqtt_settings: usize,
+    mqtt_control: usize,
+    metrics_response: usize,
+    scpi_response: usize,
+    json_rpc_response: usize,
+}
+
+/// A description of the control protocol, used by hosts to check compatibility before sending
+/// requests.
+#[derive(serde::Serialize)]
+struct ApiInfo {
+    version: u32,
+    commands: [&'static str; COMMANDS.len()],
+}
+
+impl ApiInfo {
+    fn new() -> Self {
+        let mut commands = [""; COMMANDS.len()];
+        for (name, (command, _)) in commands.iter_mut().zip(COMMANDS.iter()) {
+            *name = *command;
+        }
+
+        Self {
+            version: API_VERSION,
+            commands,
+        }
+    }
+}
+
+/// Build information used to audit compatibility of firmware between devices.
+#[derive(serde::Serialize)]
+struct BuildInfo<'a> {
+    firmware_version: &'a str,
+    git_dirty: bool,
+    rust_version: &'a str,
+    profile: &'a str,
+    features: &'a str,
+    phy: &'a str,
+    supported_phys: &'a [&'a str],
+    subsystems: &'a [&'a str],
+    buffers: BufferSizes,
+}
+
+impl<'a> BuildInfo<'a> {
+    fn new(metadata: &'a ApplicationMetadata) -> Self {
+        Self {
+            firmware_version: metadata.firmware_version,
+            git_dirty: metadata.git_dirty,
+            rust_version: metadata.rust_version,
+            profile: metadata.profile,
+            features: metadata.features,
+            phy: metadata.phy,
+            supported_phys: crate::hardware::SUPPORTED_PHYS,
+            subsystems: super::SUBSYSTEMS,
+            buffers: BufferSizes {
+                tcp_sockets: net_interface::NUM_TCP_SOCKETS,
+                tcp_rx: net_interface::TCP_RX_BUFFER_SIZE,
+                tcp_tx: net_interface::TCP_TX_BUFFER_SIZE,
+                server_tcp_sockets: net_interface::NUM_SERVER_TCP_SOCKETS,
+                server_tcp_rx: net_interface::SERVER_TCP_RX_BUFFER_SIZE,
+                server_tcp_tx: net_interface::SERVER_TCP_TX_BUFFER_SIZE,
+                mqtt_telemetry: super::TELEMETRY_BUFFER_SIZE,
+                mqtt_settings: super::SETTINGS_BUFFER_SIZE,
+                mqtt_control: super::CONTROL_BUFFER_SIZE,
+                metrics_response: super::metrics::RESPONSE_SIZE,
+                scpi_response: super::scpi::RESPONSE_SIZE,
+                json_rpc_response: super::json_rpc::RESPONSE_SIZE,
+            },
+        }
+    }
+}
+
+/// An event indicating a change of the ethernet link state.
+#[derive(Serialize)]
+struct LinkEvent {
+    up: bool,
+
+    /// The negotiated link speed in Mbps, if the link is up and the PHY status could be read.
+    speed_mbps: Option<u16>,
+
+    /// True if the link is full duplex, if the link is up and the PHY status could be read.
+    full_duplex: Option<bool>,
+}
+
+/// An event indicating that the stored settings of a channel were rejected.
+#[derive(Serialize)]
+struct SettingsEvent {
+    channel: Channel,
+    field: &'static str,
+    reason: &'static str,
+}
+
+/// An event indicating that channel measurements have exceeded their warning thresholds.
+#[derive(Serialize)]
+struct WarningEvent {
+    channel: Channel,
+    warnings: Warnings,
+}
+
+/// An event indicating that the RF module of a channel was moved or replaced.
+#[derive(Serialize)]
+struct ModuleEvent {
+    channel: Channel,
+    change: ModuleChange,
+}
+
+/// An event indicating that a channel was disabled to protect the chassis.
+#[derive(Serialize)]
+struct ShedEvent {
+    channel: Channel,
+    reason: ShedReason,
+}
+
 /// Represents a means of handling MQTT-based control interface.
 pub struct TelemetryClient {
     mqtt: minimq::Minimq<
@@ -82,8 +521,32 @@ pub struct TelemetryClient {
     >,
     prefix: String<128>,
     telemetry_period: u64,
+    layout: TelemetryLayout,
+    power_unit: PowerUnit,
+    precision: Precision,
     meta_published: bool,
+    build_info_published: bool,
+    api_published: bool,
+    schema_published: bool,
+    // The number of connections established to the broker.
+    connections: u32,
+    connected: bool,
     metadata: &'static ApplicationMetadata,
+    identifier: String<32>,
+    installed_channels: [bool; 8],
+    // The index of the next Home Assistant discovery configuration to publish.
+    discovery_index: usize,
+    // Link state changes that have not yet been published.
+    link_events: Deque<(bool, Option<LinkStatus>), 4>,
+    // Rejected channel settings that have not yet been published.
+    settings_events: Deque<(Channel, ValidationError), 8>,
+    // Raised channel warnings that have not yet been published.
+    warning_events: Deque<(Channel, Warnings), 8>,
+    // Shed channels that have not yet been published.
+    shed_events: Deque<(Channel, ShedReason), 8>,
+    // Moved or replaced RF modules that have not yet been published.
+    module_events: Deque<(Channel, ModuleChange), 8>,
+    publication: PublicationSettings,
 }
 
 impl TelemetryClient {
@@ -97,16 +560,87 @@ impl TelemetryClient {
         >,
         metadata: &'static ApplicationMetadata,
         prefix: &str,
+        identifier: &str,
+        installed_channels: [bool; 8],
     ) -> Self {
         Self {
             mqtt,
+            identifier: String::from(identifier),
+            installed_channels,
+            discovery_index: 0,
+            link_events: Deque::new(),
+            settings_events: Deque::new(),
+            warning_events: Deque::new(),
+            shed_events: Deque::new(),
+            module_events: Deque::new(),
+            publication: PublicationSettings::default(),
             prefix: String::from(prefix),
             telemetry_period: DEFAULT_TELEMETRY_PERIOD_SECS,
+            layout: TelemetryLayout::PerChannel,
+            power_unit: PowerUnit::Dbm,
+            precision: Precision::default(),
             meta_published: false,
+            build_info_published: false,
+            api_published: false,
+            schema_published: false,
+            connections: 0,
+            connected: false,
             metadata,
         }
     }
 
+    /// Publish a message with the configured options of its topic class.
+    ///
+    /// # Args
+    /// * `topic` - The topic to publish the message on.
+    /// * `options` - The publication options of the topic class.
+    /// * `message` - The message to serialize and publish.
+    ///
+    /// # Returns
+    /// True if the message was published.
+    fn publish(
+        &mut self,
+        topic: &str,
+        options: PublicationOptions,
+        message: &impl Serialize,
+    ) -> bool {
+        let mut publication =
+            DeferredPublication::new(|buf| serde_json_core::to_slice(message, buf))
+                .topic(topic)
+                .qos(options.qos.into());
+        if options.retain {
+            publication = publication.retain();
+        }
+
+        self.mqtt
+            .client()
+            .publish(publication.finish().unwrap())
+            .is_ok()
+    }
+
+    /// Publish a serialized message with the configured options of its topic class.
+    ///
+    /// # Args
+    /// * `topic` - The topic to publish the message on.
+    /// * `options` - The publication options of the topic class.
+    /// * `payload` - The serialized message.
+    ///
+    /// # Returns
+    /// True if the message was published.
+    fn publish_raw(&mut self, topic: &str, options: PublicationOptions, payload: &[u8]) -> bool {
+        let mut publication = Publication::new(payload)
+            .topic(topic)
+            .qos(options.qos.into());
+        if options.retain {
+            publication = publication.retain();
+        }
+
+        self.mqtt
+            .client()
+            .publish(publication.finish().unwrap())
+            .is_ok()
+    }
+
     /// Publish telemetry for a specific channel.
     ///
     /// # Args
@@ -117,70 +651,455 @@ impl TelemetryClient {
         write!(&mut topic, "{}/telemetry/ch{}", self.prefix, channel as u8).unwrap();
 
         // All telemtry is published in a best-effort manner.
-        self.mqtt
-            .client()
-            .publish(
-                DeferredPublication::new(|buf| serde_json_core::to_slice(telemetry, buf))
-                    .topic(&topic)
-                    .finish()
-                    .unwrap(),
+        self.publish(&topic, self.publication.telemetry, telemetry);
+    }
+
+    /// Publish each measurement of a channel in a separate message.
+    ///
+    /// # Note
+    /// Each top-level field of the serialized telemetry is published on
+    /// `<prefix>/telemetry/ch<N>/<field>` with its serialized JSON value as payload.
+    ///
+    /// # Args
+    /// * `channel` - The channel that telemetry is being reported for.
+    /// * `telemetry` - The associated telemetry of the channel to report.
+    pub fn report_field_telemetry(&mut self, channel: Channel, telemetry: &impl Serialize) {
+        let mut buffer = [0u8; 512];
+        let Ok(len) = serde_json_core::to_slice(telemetry, &mut buffer) else {
+            log::warn!("Failed to serialize telemetry of {:?}", channel);
+            return;
+        };
+
+        for (key, value) in json::members(&buffer[..len]) {
+            let Ok(key) = core::str::from_utf8(key) else {
+                continue;
+            };
+
+            let mut topic: String<128> = String::new();
+            if write!(
+                &mut topic,
+                "{}/telemetry/ch{}/{}",
+                self.prefix, channel as u8, key
             )
-            .ok();
+            .is_err()
+            {
+                continue;
+            }
+
+            // All telemtry is published in a best-effort manner.
+            self.publish_raw(&topic, self.publication.telemetry, value);
+        }
+    }
+
+    /// Publish telemetry for all channels in a single message.
+    ///
+    /// # Args
+    /// * `telemetry` - The telemetry of all channels, indexed by channel.
+    pub fn report_combined_telemetry(&mut self, telemetry: &impl Serialize) {
+        let mut topic: String<64> = String::new();
+        write!(&mut topic, "{}/telemetry", self.prefix).unwrap();
+
+        // All telemtry is published in a best-effort manner.
+        self.publish(&topic, self.publication.telemetry, telemetry);
+    }
+
+    /// Publish the CPU load and task timing.
+    ///
+    /// # Args
+    /// * `timing` - The task timing measured since the previous report.
+    pub fn report_timing(&mut self, timing: &impl Serialize) {
+        let mut topic: String<64> = String::new();
+        write!(&mut topic, "{}/telemetry/timing", self.prefix).unwrap();
+
+        // All telemtry is published in a best-effort manner.
+        self.publish(&topic, self.publication.telemetry, timing);
+    }
+
+    /// Publish the event counters of the device.
+    ///
+    /// # Args
+    /// * `statistics` - The event counters since startup.
+    pub fn report_statistics(&mut self, statistics: &Statistics) {
+        let mut topic: String<64> = String::new();
+        write!(&mut topic, "{}/telemetry/stats", self.prefix).unwrap();
+
+        // All telemtry is published in a best-effort manner.
+        self.publish(&topic, self.publication.telemetry, statistics);
+    }
+
+    /// Report the operating hours and maintenance state of the device.
+    ///
+    /// # Args
+    /// * `maintenance` - The maintenance state to report.
+    pub fn report_maintenance(&mut self, maintenance: &Maintenance) {
+        let mut topic: String<64> = String::new();
+        write!(&mut topic, "{}/telemetry/maintenance", self.prefix).unwrap();
+
+        // All telemtry is published in a best-effort manner.
+        self.publish(&topic, self.publication.telemetry, maintenance);
+    }
+
+    /// Republish the device metadata to refresh dynamic values such as the uptime.
+    pub fn refresh_metadata(&mut self) {
+        self.meta_published = false;
+    }
+
+    /// Report a change of the ethernet link state.
+    ///
+    /// # Note
+    /// Events are queued until the broker is reachable. If the queue is full, the oldest event is
+    /// discarded.
+    ///
+    /// # Args
+    /// * `up` - Specified true if the link is established.
+    /// * `status` - The negotiated mode of the link, if known.
+    pub fn report_link(&mut self, up: bool, status: Option<LinkStatus>) {
+        if self.link_events.is_full() {
+            self.link_events.pop_front();
+        }
+
+        // Note(unwrap): Space was ensured above.
+        self.link_events.push_back((up, status)).unwrap();
+    }
+
+    /// Report that the stored settings of a channel were rejected.
+    ///
+    /// # Note
+    /// Events are queued until the broker is reachable.
+    ///
+    /// # Args
+    /// * `channel` - The channel with rejected settings.
+    /// * `error` - The reason that the settings were rejected.
+    pub fn report_settings_error(&mut self, channel: Channel, error: ValidationError) {
+        // Note: There is at most one event per channel, so the queue never overflows.
+        self.settings_events.push_back((channel, error)).ok();
+    }
+
+    /// Report that channel measurements have exceeded their warning thresholds.
+    ///
+    /// # Note
+    /// Events are queued until the broker is reachable. If the queue is full, the oldest event is
+    /// discarded.
+    ///
+    /// # Args
+    /// * `channel` - The channel that raised the warnings.
+    /// * `warnings` - The newly raised warnings.
+    pub fn report_warning(&mut self, channel: Channel, warnings: Warnings) {
+        if self.warning_events.is_full() {
+            self.warning_events.pop_front();
+        }
+
+        // Note(unwrap): Space was ensured above.
+        self.warning_events.push_back((channel, warnings)).unwrap();
+    }
+
+    /// Report that a channel was disabled to protect the chassis.
+    ///
+    /// # Note
+    /// Events are queued until the broker is reachable. If the queue is full, the oldest event is
+    /// discarded.
+    ///
+    /// # Args
+    /// * `channel` - The channel that was disabled.
+    /// * `reason` - The reason that the channel was disabled.
+    pub fn report_shed(&mut self, channel: Channel, reason: ShedReason) {
+        if self.shed_events.is_full() {
+            self.shed_events.pop_front();
+        }
+
+        // Note(unwrap): Space was ensured above.
+        self.shed_events.push_back((channel, reason)).unwrap();
+    }
+
+    /// Report that the RF module of a channel was moved or replaced.
+    ///
+    /// # Note
+    /// Events are queued until the broker is reachable.
+    ///
+    /// # Args
+    /// * `channel` - The channel with the changed module.
+    /// * `change` - The change of the module.
+    pub fn report_module_change(&mut self, channel: Channel, change: ModuleChange) {
+        // Note: There is at most one event per channel, so the queue never overflows.
+        self.module_events.push_back((channel, change)).ok();
     }
 
     /// Handle the MQTT-based telemetry interface.
     pub fn update(&mut self) {
         self.mqtt.poll(|_, _, _, _| {}).ok();
 
-        if !self.mqtt.client().is_connected() {
+        let connected = self.mqtt.client().is_connected();
+        if connected && !self.connected {
+            if self.connections > 0 {
+                statistics::record(Event::MqttReconnect);
+            }
+            self.connections = self.connections.saturating_add(1);
+        }
+        self.connected = connected;
+        super::BROKER_CONNECTED.store(connected, core::sync::atomic::Ordering::Relaxed);
+
+        if !connected {
             self.meta_published = false;
+            self.build_info_published = false;
+            self.api_published = false;
+            self.schema_published = false;
+            self.discovery_index = 0;
             return;
         }
 
         // If the metadata has not yet been published, but we can publish it, do so now.
-        if !self.meta_published && self.mqtt.client().can_publish(minimq::QoS::AtMostOnce) {
+        let options = self.publication.alive;
+        if !self.meta_published && self.mqtt.client().can_publish(options.qos.into()) {
             let mut topic: String<64> = String::new();
             write!(&mut topic, "{}/alive/meta", self.prefix).unwrap();
 
-            let Self {
-                ref mut mqtt,
-                metadata,
-                ..
-            } = self;
+            if !self.publish(&topic, options, self.metadata) {
+                // Note: This message is sent because we checked for ability to publish above.
+                self.publish_raw(&topic, options, DEFAULT_METADATA.as_bytes());
+            }
+
+            self.meta_published = true;
+        }
+
+        // Build information is retained so that it can be audited for devices at any time.
+        if !self.build_info_published && self.mqtt.client().can_publish(minimq::QoS::AtMostOnce) {
+            let mut topic: String<64> = String::new();
+            write!(&mut topic, "{}/alive/build", self.prefix).unwrap();
 
-            if mqtt
+            let build_info = BuildInfo::new(self.metadata);
+            self.build_info_published = self
+                .mqtt
                 .client()
                 .publish(
-                    DeferredPublication::new(|buf| serde_json_core::to_slice(&metadata, buf))
+                    DeferredPublication::new(|buf| serde_json_core::to_slice(&build_info, buf))
                         .topic(&topic)
+                        .retain()
                         .finish()
                         .unwrap(),
                 )
-                .is_err()
-            {
-                // Note(unwrap): We can guarantee that this message will be sent because we checked
-                // for ability to publish above.
-                mqtt.client()
-                    .publish(
-                        Publication::new(DEFAULT_METADATA.as_bytes())
-                            .topic(&topic)
-                            .finish()
-                            .unwrap(),
-                    )
-                    .unwrap();
-            }
-
-            self.meta_published = true;
+                .is_ok();
         }
-    }
 
-    /// Get the period between telemetry updates in CPU cycles.
-    pub fn telemetry_period_secs(&self) -> u64 {
-        self.telemetry_period
-    }
+        // The control protocol description is retained so that hosts can check compatibility
+        // before sending requests.
+        if !self.api_published && self.mqtt.client().can_publish(minimq::QoS::AtMostOnce) {
+            let mut topic: String<64> = String::new();
+            write!(&mut topic, "{}/alive/api", self.prefix).unwrap();
 
-    /// Set the telemetry period.
-    ///
+            let api = ApiInfo::new();
+            self.api_published = self
+                .mqtt
+                .client()
+                .publish(
+                    DeferredPublication::new(|buf| serde_json_core::to_slice(&api, buf))
+                        .topic(&topic)
+                        .retain()
+                        .finish()
+                        .unwrap(),
+                )
+                .is_ok();
+        }
+
+        // The telemetry schema is retained so that consumers can interpret telemetry at any time.
+        if !self.schema_published && self.mqtt.client().can_publish(minimq::QoS::AtMostOnce) {
+            let mut topic: String<64> = String::new();
+            write!(&mut topic, "{}/alive/schema", self.prefix).unwrap();
+
+            let schema = TelemetrySchema::new(self.power_unit);
+            self.schema_published = self
+                .mqtt
+                .client()
+                .publish(
+                    DeferredPublication::new(|buf| serde_json_core::to_slice(&schema, buf))
+                        .topic(&topic)
+                        .retain()
+                        .finish()
+                        .unwrap(),
+                )
+                .is_ok();
+        }
+
+        self.publish_discovery();
+        self.publish_link_events();
+        self.publish_settings_events();
+        self.publish_warning_events();
+        self.publish_shed_events();
+        self.publish_module_events();
+    }
+
+    /// Publish pending RF module change events on `<prefix>/event/module`.
+    fn publish_module_events(&mut self) {
+        let mut topic: String<64> = String::new();
+        write!(&mut topic, "{}/event/module", self.prefix).unwrap();
+
+        let options = self.publication.events;
+        let qos = options.qos.into();
+
+        while let Some(&(channel, change)) = self.module_events.front() {
+            if !self.mqtt.client().can_publish(qos) {
+                break;
+            }
+
+            let event = ModuleEvent { channel, change };
+            self.publish(&topic, options, &event);
+
+            self.module_events.pop_front();
+        }
+    }
+
+    /// Publish pending shed channel events on `<prefix>/event/shed`.
+    fn publish_shed_events(&mut self) {
+        let mut topic: String<64> = String::new();
+        write!(&mut topic, "{}/event/shed", self.prefix).unwrap();
+
+        let options = self.publication.events;
+        let qos = options.qos.into();
+
+        while let Some(&(channel, reason)) = self.shed_events.front() {
+            if !self.mqtt.client().can_publish(qos) {
+                break;
+            }
+
+            let event = ShedEvent { channel, reason };
+            self.publish(&topic, options, &event);
+
+            self.shed_events.pop_front();
+        }
+    }
+
+    /// Publish pending channel warning events on `<prefix>/event/warning`.
+    fn publish_warning_events(&mut self) {
+        let mut topic: String<64> = String::new();
+        write!(&mut topic, "{}/event/warning", self.prefix).unwrap();
+
+        let options = self.publication.events;
+        let qos = options.qos.into();
+
+        while let Some(&(channel, warnings)) = self.warning_events.front() {
+            if !self.mqtt.client().can_publish(qos) {
+                break;
+            }
+
+            let event = WarningEvent { channel, warnings };
+            self.publish(&topic, options, &event);
+
+            self.warning_events.pop_front();
+        }
+    }
+
+    /// Publish pending rejected channel settings events on `<prefix>/event/settings`.
+    fn publish_settings_events(&mut self) {
+        let mut topic: String<64> = String::new();
+        write!(&mut topic, "{}/event/settings", self.prefix).unwrap();
+
+        let options = self.publication.events;
+        let qos = options.qos.into();
+
+        while let Some(&(channel, error)) = self.settings_events.front() {
+            if !self.mqtt.client().can_publish(qos) {
+                break;
+            }
+
+            let event = SettingsEvent {
+                channel,
+                field: error.field,
+                reason: error.reason,
+            };
+            self.publish(&topic, options, &event);
+
+            self.settings_events.pop_front();
+        }
+    }
+
+    /// Publish pending ethernet link events on `<prefix>/event/link`.
+    fn publish_link_events(&mut self) {
+        let mut topic: String<64> = String::new();
+        write!(&mut topic, "{}/event/link", self.prefix).unwrap();
+
+        let options = self.publication.events;
+        let qos = options.qos.into();
+
+        while let Some(&(up, status)) = self.link_events.front() {
+            if !self.mqtt.client().can_publish(qos) {
+                break;
+            }
+
+            let event = LinkEvent {
+                up,
+                speed_mbps: status.map(|status| status.speed_mbps),
+                full_duplex: status.map(|status| status.full_duplex),
+            };
+            self.publish(&topic, options, &event);
+
+            self.link_events.pop_front();
+        }
+    }
+
+    /// Publish pending Home Assistant discovery configurations.
+    ///
+    /// # Note
+    /// Configurations are published as capacity in the MQTT client allows, so this function must be
+    /// called periodically until all configurations are published. A configuration that fails to
+    /// publish is retried on the next call.
+    fn publish_discovery(&mut self) {
+        let count = self.installed_channels.len() * discovery::ENTITIES.len();
+
+        while self.discovery_index < count
+            && self.mqtt.client().can_publish(minimq::QoS::AtMostOnce)
+        {
+            let channel = self.discovery_index / discovery::ENTITIES.len();
+            let entity = discovery::ENTITIES[self.discovery_index % discovery::ENTITIES.len()];
+
+            if !self.installed_channels[channel] {
+                self.discovery_index += 1;
+                continue;
+            }
+
+            let (Ok(topic), Ok(config)) = (
+                discovery::topic(entity, channel, &self.identifier),
+                discovery::Config::new(
+                    entity,
+                    channel,
+                    &self.identifier,
+                    &self.prefix,
+                    self.layout,
+                    self.power_unit,
+                    self.metadata,
+                ),
+            ) else {
+                log::warn!("Failed to format discovery configuration");
+                self.discovery_index += 1;
+                continue;
+            };
+
+            let result = self.mqtt.client().publish(
+                DeferredPublication::new(|buf| serde_json_core::to_slice(&config, buf))
+                    .topic(&topic)
+                    .retain()
+                    .finish()
+                    .unwrap(),
+            );
+
+            match result {
+                Ok(_) => self.discovery_index += 1,
+                // The configuration does not fit into the transmit buffer, so retrying would never
+                // succeed.
+                Err(minimq::PubError::Serialization(_)) => {
+                    log::warn!("Failed to serialize discovery configuration");
+                    self.discovery_index += 1;
+                }
+                Err(_) => break,
+            }
+        }
+    }
+
+    /// Get the period between telemetry updates in CPU cycles.
+    pub fn telemetry_period_secs(&self) -> u64 {
+        self.telemetry_period
+    }
+
+    /// Set the telemetry period.
+    ///
     /// # Note
     /// The telemetry period has a minimum period of 1 seconds
     ///
@@ -189,6 +1108,74 @@ impl TelemetryClient {
     pub fn set_telemetry_period(&mut self, period: u64) {
         self.telemetry_period = period.clamp(1, period);
     }
+
+    /// Get the configured telemetry layout.
+    pub fn layout(&self) -> TelemetryLayout {
+        self.layout
+    }
+
+    /// Set the telemetry layout.
+    ///
+    /// # Args
+    /// * `layout` - The layout to use for subsequent telemetry messages.
+    pub fn set_layout(&mut self, layout: TelemetryLayout) {
+        // Discovery configurations reference the telemetry topics, so they must be republished.
+        if layout != self.layout {
+            self.discovery_index = 0;
+        }
+
+        self.layout = layout;
+    }
+
+    /// Get the configured unit of power measurements in telemetry.
+    pub fn power_unit(&self) -> PowerUnit {
+        self.power_unit
+    }
+
+    /// Set the unit of power measurements in telemetry.
+    ///
+    /// # Args
+    /// * `unit` - The unit to use for subsequent telemetry messages.
+    pub fn set_power_unit(&mut self, unit: PowerUnit) {
+        // The telemetry schema and discovery configurations specify the unit, so they must be
+        // republished.
+        if unit != self.power_unit {
+            self.schema_published = false;
+            self.discovery_index = 0;
+        }
+
+        self.power_unit = unit;
+    }
+
+    /// Get the configured precision of measurements in telemetry.
+    pub fn precision(&self) -> Precision {
+        self.precision
+    }
+
+    /// Set the precision of measurements in telemetry.
+    ///
+    /// # Args
+    /// * `precision` - The number of decimal places of measurements in subsequent telemetry.
+    pub fn set_precision(&mut self, precision: Precision) {
+        self.precision = precision;
+    }
+
+    /// Configure the quality of service and retention of publications.
+    ///
+    /// # Note
+    /// Unacknowledged publications are held in the transmit buffer of the telemetry client, which
+    /// is reserved almost entirely for transmission.
+    ///
+    /// # Args
+    /// * `publication` - The publication options of each class of topics.
+    pub fn set_publication(&mut self, publication: PublicationSettings) {
+        // The metadata is republished so that a newly enabled retention takes effect.
+        if publication.alive != self.publication.alive {
+            self.meta_published = false;
+        }
+
+        self.publication = publication;
+    }
 }
 
 /// Read bias transistor parameters.
@@ -211,17 +1198,171 @@ pub fn read_bias(
 ) -> Result<usize, Error> {
     let request: ChannelRequest = serde_json_core::from_slice(request)?.0;
 
-    let Some((channel, _)) = main_bus.channels.channel_mut(request.channel) else {
+    let Some(channel) = main_bus.channels.channel_mut(request.channel) else {
         return Err("Channel not found".into());
     };
     let response = ChannelBiasResponse {
         vgs: channel.context_mut().get_bias_voltage(),
-        ids: channel.context_mut().get_p28v_current(),
+        ids: channel.context_mut().get_p28v_current()?,
     };
 
     Ok(serde_json_core::to_slice(&response, output)?)
 }
 
+/// Re-arm the interlocks of a tripped channel.
+///
+/// # Note
+/// This is a handler function for the control interface. The previous output state is restored if
+/// permitted by the channel settings.
+///
+/// # Args
+/// * `main_bus` - The main I2C bus to communicate with RF channels.
+/// * `_topic` - Unused, but reserved for the incoming topic of the request.
+/// * `request` - The serialized [ChannelRequest] to process.
+///
+/// # Returns
+/// A [minireq::Response] containing no data, which indicates the success of the command
+/// processing.
+pub fn rearm(
+    main_bus: &mut MainBus,
+    _topic: &str,
+    request: &[u8],
+    _buffer: &mut [u8],
+) -> Result<usize, Error> {
+    let request: ChannelRequest = serde_json_core::from_slice(request)?.0;
+
+    let Some(channel) = main_bus.channels.channel_mut(request.channel) else {
+        return Err("Channel not found".into());
+    };
+
+    channel
+        .rearm()
+        .map_err(|_| Error::Other("Channel not tripped"))?;
+
+    Ok(0)
+}
+
+/// Retry communication with a channel that was blocked due to RF module errors.
+///
+/// # Note
+/// This is a handler function for the control interface. If the RF module responds, any
+/// quarantine is lifted and the channel is powered up again if permitted by the channel settings.
+///
+/// # Args
+/// * `main_bus` - The main I2C bus to communicate with RF channels.
+/// * `_topic` - Unused, but reserved for the incoming topic of the request.
+/// * `request` - The serialized [ChannelRequest] to process.
+///
+/// # Returns
+/// A [minireq::Response] containing no data, which indicates the success of the command
+/// processing.
+pub fn retry_channel(
+    main_bus: &mut MainBus,
+    _topic: &str,
+    request: &[u8],
+    _buffer: &mut [u8],
+) -> Result<usize, Error> {
+    let request: ChannelRequest = serde_json_core::from_slice(request)?.0;
+
+    let Some(channel) = main_bus.channels.channel_mut(request.channel) else {
+        return Err("Channel not found".into());
+    };
+
+    channel.retry().map_err(|err| match err {
+        crate::Error::InvalidState => Error::Other("Channel not blocked by a bus error"),
+        err => Error::Device(err),
+    })?;
+
+    Ok(0)
+}
+
+/// Read the identifiers of the device and all installed RF modules.
+///
+/// # Note
+/// This is a handler function for the control interface.
+///
+/// # Args
+/// * `main_bus` - The main I2C bus to communicate with RF channels.
+/// * `_topic` - Unused, but reserved for the incoming topic of the request.
+/// * `_request` - Unused.
+///
+/// # Returns
+/// A [minireq::Response] containing a serialized [IdentityResponse].
+pub fn read_identity(
+    main_bus: &mut MainBus,
+    _topic: &str,
+    _request: &[u8],
+    output: &mut [u8],
+) -> Result<usize, Error> {
+    let mut channels = [None; 8];
+    for idx in enum_iterator::all::<Channel>() {
+        channels[idx as usize] = main_bus
+            .channels
+            .channel_mut(idx)
+            .map(|channel| Eui48(channel.context().eui48()));
+    }
+
+    let response = IdentityResponse {
+        eui48: main_bus.metadata.eui48,
+        firmware_version: main_bus.metadata.firmware_version,
+        channels,
+    };
+
+    Ok(serde_json_core::to_slice(&response, output)?)
+}
+
+/// Scan the shared I2C bus and the bus segment of each channel for responding devices.
+///
+/// # Note
+/// This is a handler function for the control interface. It is intended for debugging partially
+/// assembled or damaged RF modules.
+///
+/// # Args
+/// * `main_bus` - The main I2C bus to communicate with RF channels.
+/// * `_topic` - Unused, but reserved for the incoming topic of the request.
+/// * `_request` - Unused.
+///
+/// # Returns
+/// A [minireq::Response] containing a serialized [crate::hardware::booster_channels::I2cScan].
+pub fn scan_i2c(
+    main_bus: &mut MainBus,
+    _topic: &str,
+    _request: &[u8],
+    output: &mut [u8],
+) -> Result<usize, Error> {
+    let scan = main_bus.channels.scan_i2c();
+    Ok(serde_json_core::to_slice(&scan, output)?)
+}
+
+/// Read raw detector and monitor voltages of a channel.
+///
+/// # Note
+/// This is a handler function for the control interface. Voltages are reported before calibration
+/// transforms are applied.
+///
+/// # Args
+/// * `main_bus` - The main I2C bus to communicate with RF channels.
+/// * `_topic` - Unused, but reserved for the incoming topic of the request.
+/// * `request` - The serialized [ChannelRequest] to process.
+///
+/// # Returns
+/// A [minireq::Response] containing serialized [crate::hardware::rf_channel::RawVoltages].
+pub fn read_raw(
+    main_bus: &mut MainBus,
+    _topic: &str,
+    request: &[u8],
+    output: &mut [u8],
+) -> Result<usize, Error> {
+    let request: ChannelRequest = serde_json_core::from_slice(request)?.0;
+
+    let Some(channel) = main_bus.channels.channel_mut(request.channel) else {
+        return Err("Channel not found".into());
+    };
+    let response = channel.context_mut().get_raw_voltages()?;
+
+    Ok(serde_json_core::to_slice(&response, output)?)
+}
+
 /// Persist channel settings to EEPROM.
 ///
 /// # Note
@@ -243,7 +1384,7 @@ pub fn save_settings(
 ) -> Result<usize, Error> {
     let request: ChannelRequest = serde_json_core::from_slice(request)?.0;
 
-    let Some((channel, _)) = main_bus.channels.channel_mut(request.channel) else {
+    let Some(channel) = main_bus.channels.channel_mut(request.channel) else {
         return Err("Channel not found".into());
     };
 
@@ -251,3 +1392,480 @@ pub fn save_settings(
 
     Ok(0)
 }
+
+/// Reset the device into the firmware.
+///
+/// # Note
+/// This is a handler function for the control interface. All channels are shut down before the
+/// device is reset.
+///
+/// # Args
+/// * `_main_bus` - Unused.
+/// * `_topic` - Unused, but reserved for the incoming topic of the request.
+/// * `request` - The serialized [ResetRequest] to process. The confirmation must be `reboot`.
+///
+/// # Returns
+/// A [minireq::Response] containing no data, which indicates that the reset is scheduled.
+pub fn reboot(
+    _main_bus: &mut MainBus,
+    _topic: &str,
+    request: &[u8],
+    _buffer: &mut [u8],
+) -> Result<usize, Error> {
+    schedule_reset(request, "reboot", ResetTarget::Firmware)
+}
+
+/// Reset the device into the DFU bootloader.
+///
+/// # Note
+/// This is a handler function for the control interface. All channels are shut down before the
+/// device is reset.
+///
+/// # Args
+/// * `_main_bus` - Unused.
+/// * `_topic` - Unused, but reserved for the incoming topic of the request.
+/// * `request` - The serialized [ResetRequest] to process. The confirmation must be `dfu`.
+///
+/// # Returns
+/// A [minireq::Response] containing no data, which indicates that the reset is scheduled.
+pub fn dfu(
+    _main_bus: &mut MainBus,
+    _topic: &str,
+    request: &[u8],
+    _buffer: &mut [u8],
+) -> Result<usize, Error> {
+    schedule_reset(request, "dfu", ResetTarget::Bootloader)
+}
+
+/// Schedule a confirmed device reset.
+///
+/// # Args
+/// * `request` - The serialized [ResetRequest].
+/// * `command` - The name of the command, which must be provided as confirmation.
+/// * `target` - The target to reset the device into.
+fn schedule_reset(request: &[u8], command: &str, target: ResetTarget) -> Result<usize, Error> {
+    let request: ResetRequest = serde_json_core::from_slice(request)?.0;
+    if request.confirm != command {
+        return Err("Reset not confirmed".into());
+    }
+
+    crate::app::reset::spawn_after(RESET_DELAY_MS.millis(), target)
+        .map_err(|_| Error::Other("Reset already pending"))?;
+
+    log::warn!("Resetting device into {:?}", target);
+    Ok(0)
+}
+
+/// Persist the pending network settings and reset the device to apply them.
+///
+/// # Note
+/// This is a handler function for the control interface. All channels are shut down before the
+/// device is reset.
+///
+/// # Args
+/// * `_main_bus` - Unused.
+/// * `_topic` - Unused, but reserved for the incoming topic of the request.
+/// * `request` - The serialized [ResetRequest] to process. The confirmation must be
+///   `apply-network`.
+///
+/// # Returns
+/// A [minireq::Response] containing no data, which indicates that the settings will be applied.
+pub fn apply_network(
+    _main_bus: &mut MainBus,
+    _topic: &str,
+    request: &[u8],
+    _buffer: &mut [u8],
+) -> Result<usize, Error> {
+    let request: ResetRequest = serde_json_core::from_slice(request)?.0;
+    if request.confirm != "apply-network" {
+        return Err("Network settings not confirmed".into());
+    }
+
+    crate::app::apply_network::spawn().map_err(|_| Error::Other("Already applying"))?;
+    Ok(0)
+}
+
+/// Lock the device to reject state-changing commands.
+///
+/// # Note
+/// This is a handler function for the control interface. The lock state is retained across resets.
+///
+/// # Args
+/// * `_main_bus` - Unused.
+/// * `_topic` - Unused, but reserved for the incoming topic of the request.
+/// * `request` - The serialized [LockRequest] to process.
+///
+/// # Returns
+/// A [minireq::Response] containing no data, which indicates that the device is locked.
+pub fn lock(
+    _main_bus: &mut MainBus,
+    _topic: &str,
+    request: &[u8],
+    _buffer: &mut [u8],
+) -> Result<usize, Error> {
+    set_locked(request, true)
+}
+
+/// Unlock the device to accept state-changing commands.
+///
+/// # Note
+/// This is a handler function for the control interface. The lock state is retained across resets.
+///
+/// # Args
+/// * `_main_bus` - Unused.
+/// * `_topic` - Unused, but reserved for the incoming topic of the request.
+/// * `request` - The serialized [LockRequest] to process.
+///
+/// # Returns
+/// A [minireq::Response] containing no data, which indicates that the device is unlocked.
+pub fn unlock(
+    _main_bus: &mut MainBus,
+    _topic: &str,
+    request: &[u8],
+    _buffer: &mut [u8],
+) -> Result<usize, Error> {
+    set_locked(request, false)
+}
+
+/// Lock or unlock the device.
+///
+/// # Args
+/// * `request` - The serialized [LockRequest].
+/// * `locked` - Specified true if the device should be locked.
+fn set_locked(request: &[u8], locked: bool) -> Result<usize, Error> {
+    let request: LockRequest = serde_json_core::from_slice(request)?.0;
+    deployment_lock::set_locked(request.key, locked)?;
+
+    log::info!("Device {}", if locked { "locked" } else { "unlocked" });
+    Ok(0)
+}
+
+/// Copy the calibration, interlock threshold, and bias settings of a channel to other channels.
+///
+/// # Note
+/// This is a handler function for the control interface. The copied settings are applied to the
+/// run-time settings and republished, but are not saved to EEPROM.
+///
+/// # Args
+/// * `main_bus` - The main I2C bus to communicate with RF channels.
+/// * `_topic` - Unused, but reserved for the incoming topic of the request.
+/// * `request` - The serialized [CloneRequest] to process.
+///
+/// # Returns
+/// A [minireq::Response] containing no data, which indicates that the settings will be copied.
+pub fn clone_channel(
+    main_bus: &mut MainBus,
+    _topic: &str,
+    request: &[u8],
+    _buffer: &mut [u8],
+) -> Result<usize, Error> {
+    let request: CloneRequest = serde_json_core::from_slice(request)?.0;
+
+    if main_bus.channels.channel_mut(request.source).is_none() {
+        return Err("Source channel not found".into());
+    }
+
+    if let Some(destination) = request.destination {
+        if main_bus.channels.channel_mut(destination).is_none() {
+            return Err("Destination channel not found".into());
+        }
+    }
+
+    crate::app::clone_channel::spawn(request.source, request.destination)
+        .map_err(|_| Error::Other("Clone already pending"))?;
+    Ok(0)
+}
+
+/// Read properties of a channel in a single request.
+///
+/// # Note
+/// This is a handler function for the control interface. The active settings of the channel are
+/// reported.
+///
+/// # Args
+/// * `main_bus` - The main I2C bus to communicate with RF channels.
+/// * `_topic` - Unused, but reserved for the incoming topic of the request.
+/// * `request` - The serialized [ReadChannelRequest] to process.
+/// * `output` - The location to serialize the properties into.
+///
+/// # Returns
+/// A [minireq::Response] containing a JSON object of the requested properties keyed by name.
+pub fn read_channel(
+    main_bus: &mut MainBus,
+    _topic: &str,
+    request: &[u8],
+    output: &mut [u8],
+) -> Result<usize, Error> {
+    let request: ReadChannelRequest = serde_json_core::from_slice(request)?.0;
+
+    let Some(channel) = main_bus.channels.channel_mut(request.channel) else {
+        return Err("Channel not found".into());
+    };
+
+    Ok(channel
+        .context()
+        .settings()
+        .read_properties(&request.properties, output)?)
+}
+
+/// Write properties of a channel in a single request.
+///
+/// # Note
+/// This is a handler function for the control interface. The request is checked against the
+/// active settings of the channel. The properties are then applied to the run-time settings and
+/// republished, but are not saved to EEPROM.
+///
+/// # Args
+/// * `main_bus` - The main I2C bus to communicate with RF channels.
+/// * `_topic` - Unused, but reserved for the incoming topic of the request.
+/// * `request` - The serialized request, e.g.
+///   `{"channel": "Zero", "properties": {"bias_voltage": -2.5, "state": "Enabled"}}`.
+///
+/// # Returns
+/// A [minireq::Response] containing no data, which indicates that the properties will be written.
+pub fn write_channel(
+    main_bus: &mut MainBus,
+    _topic: &str,
+    request: &[u8],
+    _buffer: &mut [u8],
+) -> Result<usize, Error> {
+    let channel_request: ChannelRequest = serde_json_core::from_slice(request)?.0;
+    let properties = json::member(request, "properties").ok_or("Missing properties")?;
+
+    let Some(channel) = main_bus.channels.channel_mut(channel_request.channel) else {
+        return Err("Channel not found".into());
+    };
+
+    // Reject invalid properties before they are applied.
+    let mut settings = *channel.context().settings();
+    settings.write_properties(properties)?;
+
+    let properties =
+        ChannelProperties::from_slice(properties).map_err(|_| "Properties too large")?;
+    crate::app::write_channel::spawn(channel_request.channel, properties)
+        .map_err(|_| Error::Other("Write already pending"))?;
+    Ok(0)
+}
+
+/// Restore the factory default device and channel settings.
+///
+/// # Note
+/// This is a handler function for the control interface. A request without a confirmation code
+/// arms the reset and returns the code, which must be provided in a second request within 10
+/// seconds. Once confirmed, all channels are shut down and the device is reset.
+///
+/// # Args
+/// * `_main_bus` - Unused.
+/// * `_topic` - Unused, but reserved for the incoming topic of the request.
+/// * `request` - The serialized [FactoryResetRequest] to process.
+/// * `output` - A buffer to serialize the [FactoryResetResponse] into when arming the reset.
+///
+/// # Returns
+/// A [minireq::Response] containing the confirmation code if the reset was armed, or no data if
+/// the reset was confirmed.
+pub fn factory_reset(
+    _main_bus: &mut MainBus,
+    _topic: &str,
+    request: &[u8],
+    output: &mut [u8],
+) -> Result<usize, Error> {
+    let request: FactoryResetRequest = serde_json_core::from_slice(request)?.0;
+
+    let Some(code) = request.confirm else {
+        let response = FactoryResetResponse {
+            confirm: crate::settings::factory_reset::arm(request.preserve_calibration),
+        };
+        return Ok(serde_json_core::to_slice(&response, output)?);
+    };
+
+    let preserve_calibration = crate::settings::factory_reset::confirm(Some(code))?;
+    crate::app::factory_reset::spawn(preserve_calibration)
+        .map_err(|_| Error::Other("Factory reset already pending"))?;
+    Ok(0)
+}
+
+/// Republish the complete run-time settings tree.
+///
+/// # Note
+/// This is a handler function for the control interface.
+///
+/// # Args
+/// * `_main_bus` - Unused.
+/// * `_topic` - Unused, but reserved for the incoming topic of the request.
+/// * `_request` - Unused.
+///
+/// # Returns
+/// A [minireq::Response] containing no data, which indicates that the settings will be
+/// republished.
+pub fn republish_settings(
+    _main_bus: &mut MainBus,
+    _topic: &str,
+    _request: &[u8],
+    _buffer: &mut [u8],
+) -> Result<usize, Error> {
+    // Note: If a republish is already pending, the pending republish includes the latest settings.
+    crate::app::republish_settings::spawn().ok();
+    Ok(0)
+}
+
+/// Blink all front-panel LEDs to identify the device.
+///
+/// # Note
+/// This is a handler function for the control interface.
+///
+/// # Args
+/// * `_main_bus` - Unused.
+/// * `_topic` - Unused, but reserved for the incoming topic of the request.
+/// * `request` - The serialized [IdentifyRequest] to process.
+///
+/// # Returns
+/// A [minireq::Response] containing no data, which indicates the success of the command
+/// processing.
+pub fn identify(
+    _main_bus: &mut MainBus,
+    _topic: &str,
+    request: &[u8],
+    _buffer: &mut [u8],
+) -> Result<usize, Error> {
+    let request: IdentifyRequest = serde_json_core::from_slice(request)?.0;
+    crate::hardware::user_interface::identify(request.duration);
+    Ok(0)
+}
+
+/// Set the real-time clock.
+///
+/// # Note
+/// This is a handler function for the control interface.
+///
+/// # Args
+/// * `_main_bus` - Unused.
+/// * `_topic` - Unused, but reserved for the incoming topic of the request.
+/// * `request` - The serialized [ClockRequest] to process.
+///
+/// # Returns
+/// A [minireq::Response] containing no data, which indicates that the clock has been set.
+pub fn set_clock(
+    _main_bus: &mut MainBus,
+    _topic: &str,
+    request: &[u8],
+    _buffer: &mut [u8],
+) -> Result<usize, Error> {
+    let request: ClockRequest = serde_json_core::from_slice(request)?.0;
+    crate::hardware::rtc::set(request.time)?;
+    Ok(0)
+}
+
+/// Acknowledge fan service and restart the fan service interval.
+///
+/// # Note
+/// This is a handler function for the control interface.
+///
+/// # Args
+/// * `_main_bus` - Unused.
+/// * `_topic` - Unused, but reserved for the incoming topic of the request.
+/// * `_request` - Unused.
+///
+/// # Returns
+/// A [minireq::Response] containing no data, which indicates that the maintenance flag will be
+/// cleared.
+pub fn clear_maintenance(
+    _main_bus: &mut MainBus,
+    _topic: &str,
+    _request: &[u8],
+    _buffer: &mut [u8],
+) -> Result<usize, Error> {
+    crate::app::clear_maintenance::spawn().map_err(|_| Error::Other("Already clearing"))?;
+    Ok(0)
+}
+
+/// Hold or release the schedule of a channel.
+///
+/// # Note
+/// This is a handler function for the control interface.
+///
+/// # Args
+/// * `_main_bus` - Unused.
+/// * `_topic` - Unused, but reserved for the incoming topic of the request.
+/// * `request` - The serialized [HoldRequest] to process.
+///
+/// # Returns
+/// A [minireq::Response] containing no data, which indicates that the schedule was held or
+/// released.
+pub fn hold_schedule(
+    _main_bus: &mut MainBus,
+    _topic: &str,
+    request: &[u8],
+    _buffer: &mut [u8],
+) -> Result<usize, Error> {
+    let request: HoldRequest = serde_json_core::from_slice(request)?.0;
+    crate::scheduler::set_hold(request.channel, request.hold);
+    Ok(0)
+}
+
+/// Read the event counters of the device.
+///
+/// # Note
+/// This is a handler function for the control interface.
+///
+/// # Args
+/// * `_main_bus` - Unused.
+/// * `_topic` - Unused, but reserved for the incoming topic of the request.
+/// * `_request` - Unused.
+/// * `output` - The location to serialize the [Statistics] into.
+///
+/// # Returns
+/// A [minireq::Response] containing the serialized [Statistics].
+pub fn read_statistics(
+    _main_bus: &mut MainBus,
+    _topic: &str,
+    _request: &[u8],
+    output: &mut [u8],
+) -> Result<usize, Error> {
+    Ok(serde_json_core::to_slice(&Statistics::read(), output)?)
+}
+
+/// The checksums of the stored configuration of the device.
+#[derive(Serialize)]
+struct Checksums {
+    /// The CRC32 of the mainboard settings.
+    mainboard: u32,
+
+    /// The CRC32 of the settings stored on each RF module, or None if the channel is not installed
+    /// or its EEPROM contains no valid settings.
+    channels: [Option<u32>; 8],
+}
+
+/// Read the checksums of the stored configuration of the mainboard and each channel.
+///
+/// # Note
+/// This is a handler function for the control interface. The checksums allow detecting
+/// configuration changes without reading all settings.
+///
+/// # Args
+/// * `main_bus` - The main I2C bus to communicate with RF channels.
+/// * `_topic` - Unused, but reserved for the incoming topic of the request.
+/// * `_request` - Unused.
+/// * `output` - The location to serialize the [Checksums] into.
+///
+/// # Returns
+/// A [minireq::Response] containing the serialized [Checksums].
+pub fn read_checksums(
+    main_bus: &mut MainBus,
+    _topic: &str,
+    _request: &[u8],
+    output: &mut [u8],
+) -> Result<usize, Error> {
+    let mut checksums = Checksums {
+        mainboard: main_bus.settings_crc32,
+        channels: [None; 8],
+    };
+
+    for idx in enum_iterator::all::<Channel>() {
+        checksums.channels[idx as usize] = main_bus
+            .channels
+            .channel_mut(idx)
+            .and_then(|channel| channel.context().eeprom_crc32());
+    }
+
+    Ok(serde_json_core::to_slice(&checksums, output)?)
+}
@@ -0,0 +1,429 @@
+//! Booster SCPI command interface
+//!
+//! # Design
+//! A SCPI-style command server is run on TCP port 5025 so that Booster can be controlled by
+//! instrument-control frameworks that do not support MQTT. Commands are newline-terminated and
+//! multiple commands may be combined on a single line separated by `;`. Responses to queries are
+//! combined in the same manner and terminated with a newline.
+//!
+//! Channels are addressed by their index (0-7), either as a numeric header suffix (e.g. `OUTP1`)
+//! or as a query parameter (e.g. `MEAS:POW? 1`).
+//!
+//! The following commands are supported:
+//! * `*IDN?` - Identify the device.
+//! * `*CLS` - Clear the error queue.
+//! * `SYSTem:ERRor[:NEXT]?` - Pop the next error from the error queue.
+//! * `OUTPut<n>[:STATe] ON|OFF|1|0[,<token>]` - Enable or disable a channel. The access-control
+//!   token must be provided if one is configured.
+//! * `OUTPut<n>[:STATe]?` - Query if a channel is enabled.
+//! * `OUTPut<n>:CONDition?` - Query the state of the channel state machine, e.g. `Enabled` or
+//!   `Tripped(Output)`.
+//! * `MEASure:POWer[:OUTPut]? <n>` - Measure channel output power in dBm.
+//! * `MEASure:POWer:REFLected? <n>` - Measure channel reflected power in dBm.
+//! * `MEASure:POWer:INPut? <n>` - Measure channel input power in dBm.
+//! * `MEASure:TEMPerature? <n>` - Measure channel temperature in celsius.
+//! * `MEASure:CURRent? <n>` - Measure channel P28V rail current in amperes.
+
+use super::{
+    line_server::{Line, LineServer},
+    NetworkStackProxy, SettingsClient,
+};
+use crate::{
+    hardware::{metadata::ApplicationMetadata, rf_channel::ChannelStatus, setup::MainBus, Channel},
+    settings::{channel_settings::ChannelState, runtime_settings::RuntimeSettings},
+};
+
+use core::fmt::Write;
+use heapless::{Deque, String, Vec};
+
+/// The TCP port that the SCPI server listens on.
+pub const SCPI_PORT: u16 = 5025;
+
+/// The maximum size of the response to a single command line.
+pub const RESPONSE_SIZE: usize = 512;
+
+/// The maximum length of a single command line.
+const LINE_SIZE: usize = 256;
+
+/// The maximum number of errors retained in the error queue.
+const ERROR_QUEUE_SIZE: usize = 8;
+
+/// Errors reported via the SCPI error queue.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ScpiError {
+    Command,
+    Syntax,
+    MissingParameter,
+    UndefinedHeader,
+    Execution,
+    IllegalParameter,
+    HardwareMissing,
+    QueueOverflow,
+    Protected,
+}
+
+impl ScpiError {
+    /// Get the standard SCPI error code and description of the error.
+    fn describe(&self) -> (i16, &'static str) {
+        match self {
+            ScpiError::Command => (-100, "Command error"),
+            ScpiError::Syntax => (-102, "Syntax error"),
+            ScpiError::MissingParameter => (-109, "Missing parameter"),
+            ScpiError::UndefinedHeader => (-113, "Undefined header"),
+            ScpiError::Execution => (-200, "Execution error"),
+            ScpiError::IllegalParameter => (-224, "Illegal parameter value"),
+            ScpiError::HardwareMissing => (-241, "Hardware missing"),
+            ScpiError::QueueOverflow => (-350, "Queue overflow"),
+            ScpiError::Protected => (-203, "Command protected"),
+        }
+    }
+}
+
+impl From<core::fmt::Error> for ScpiError {
+    fn from(_: core::fmt::Error) -> Self {
+        ScpiError::Execution
+    }
+}
+
+/// Check if a header keyword matches a SCPI mnemonic.
+///
+/// # Args
+/// * `keyword` - The received keyword.
+/// * `mnemonic` - The mnemonic in SCPI notation, where the short form is written in upper case
+///   (e.g. `OUTPut`).
+///
+/// # Returns
+/// True if the keyword is the short or long form of the mnemonic.
+fn keyword_matches(keyword: &str, mnemonic: &str) -> bool {
+    let short_len = mnemonic
+        .chars()
+        .take_while(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || *c == '*')
+        .count();
+
+    keyword.eq_ignore_ascii_case(mnemonic) || keyword.eq_ignore_ascii_case(&mnemonic[..short_len])
+}
+
+/// Split a header keyword into the keyword and its numeric suffix.
+fn split_suffix(keyword: &str) -> (&str, Option<&str>) {
+    let mnemonic = keyword.trim_end_matches(|c: char| c.is_ascii_digit());
+
+    match &keyword[mnemonic.len()..] {
+        "" => (mnemonic, None),
+        suffix => (mnemonic, Some(suffix)),
+    }
+}
+
+/// Parse a channel index into a channel.
+fn parse_channel(index: Option<&str>) -> Result<Channel, ScpiError> {
+    let index: usize = index
+        .ok_or(ScpiError::MissingParameter)?
+        .trim()
+        .parse()
+        .map_err(|_| ScpiError::IllegalParameter)?;
+
+    enum_iterator::all::<Channel>()
+        .nth(index)
+        .ok_or(ScpiError::IllegalParameter)
+}
+
+/// Execute a single SCPI command that operates on the RF channels.
+///
+/// # Args
+/// * `main_bus` - The main I2C bus to communicate with RF channels.
+/// * `settings` - The run-time settings client.
+/// * `token` - The configured access-control token. If empty, all commands are authorized.
+/// * `command` - The command to execute, without any trailing terminator.
+/// * `output` - The location to write the query response into.
+pub fn execute(
+    main_bus: &mut MainBus,
+    settings: &mut SettingsClient,
+    token: &str,
+    command: &str,
+    output: &mut impl Write,
+) -> Result<(), ScpiError> {
+    let (header, parameter) = match command.split_once(char::is_whitespace) {
+        Some((header, parameter)) => (header, Some(parameter.trim())),
+        None => (command, None),
+    };
+
+    let (header, query) = match header.strip_suffix('?') {
+        Some(header) => (header, true),
+        None => (header, false),
+    };
+
+    let mut keywords = header.trim_start_matches(':').split(':');
+    let (root, suffix) = split_suffix(keywords.next().ok_or(ScpiError::Syntax)?);
+
+    if keyword_matches(root, "OUTPut") {
+        let condition = match keywords.next() {
+            None => false,
+            Some(keyword) if keyword_matches(keyword, "STATe") => false,
+            Some(keyword) if keyword_matches(keyword, "CONDition") && query => true,
+            Some(_) => return Err(ScpiError::UndefinedHeader),
+        };
+
+        let index = parse_channel(suffix)?;
+
+        if condition {
+            let channel = main_bus
+                .channels
+                .channel_mut(index)
+                .ok_or(ScpiError::HardwareMissing)?;
+            write!(output, "{}", channel.state().name())?;
+            return Ok(());
+        }
+
+        let channel = settings.settings().channel[index as usize]
+            .as_ref()
+            .ok_or(ScpiError::HardwareMissing)?;
+
+        if query {
+            let enabled = channel.state == ChannelState::Enabled;
+            write!(output, "{}", enabled as u8)?;
+            return Ok(());
+        }
+
+        if crate::settings::deployment_lock::is_locked() {
+            return Err(ScpiError::Protected);
+        }
+
+        let parameter = parameter.ok_or(ScpiError::MissingParameter)?;
+        let (parameter, provided) = match parameter.split_once(',') {
+            Some((parameter, provided)) => (parameter.trim(), Some(provided.trim())),
+            None => (parameter, None),
+        };
+
+        if !token.is_empty() && provided != Some(token) {
+            log::warn!("Rejected unauthorized SCPI output command");
+            return Err(ScpiError::Protected);
+        }
+
+        let state = if parameter.eq_ignore_ascii_case("ON") || parameter == "1" {
+            ChannelState::Enabled
+        } else if parameter.eq_ignore_ascii_case("OFF") || parameter == "0" {
+            ChannelState::Off
+        } else {
+            return Err(ScpiError::IllegalParameter);
+        };
+
+        let mut new_settings = settings.settings().clone();
+        if let Some(channel) = new_settings.channel[index as usize].as_mut() {
+            channel.state = state;
+        }
+
+        // The update is validated and rate limited in the same manner as network settings updates.
+        RuntimeSettings::handle_update("/channel", settings.settings_mut(), &new_settings)
+            .map_err(|_| ScpiError::Execution)?;
+
+        // Ensure that the settings reported via MQTT reflect the update.
+        settings.force_republish();
+
+        // Note: The settings update may already be pending, so it's acceptable if the spawn fails.
+        crate::app::update_settings::spawn().ok();
+        Ok(())
+    } else if keyword_matches(root, "MEASure") && suffix.is_none() {
+        if !query {
+            return Err(ScpiError::UndefinedHeader);
+        }
+
+        let measurement: fn(&ChannelStatus) -> f32 = match (keywords.next(), keywords.next()) {
+            (Some(power), None) if keyword_matches(power, "POWer") => |status| status.output_power,
+            (Some(power), Some(kind)) if keyword_matches(power, "POWer") => {
+                if keyword_matches(kind, "OUTPut") {
+                    |status| status.output_power
+                } else if keyword_matches(kind, "REFLected") {
+                    |status| status.reflected_power
+                } else if keyword_matches(kind, "INPut") {
+                    |status| status.input_power
+                } else {
+                    return Err(ScpiError::UndefinedHeader);
+                }
+            }
+            (Some(temperature), None) if keyword_matches(temperature, "TEMPerature") => {
+                |status| status.temperature
+            }
+            (Some(current), None) if keyword_matches(current, "CURRent") => {
+                |status| status.p28v_current
+            }
+            _ => return Err(ScpiError::UndefinedHeader),
+        };
+
+        let channel = main_bus
+            .channels
+            .channel_mut(parse_channel(parameter)?)
+            .ok_or(ScpiError::HardwareMissing)?;
+
+        let status = channel.get_status().map_err(|_| ScpiError::Execution)?;
+        write!(output, "{}", measurement(&status))?;
+        Ok(())
+    } else {
+        Err(ScpiError::UndefinedHeader)
+    }
+}
+
+/// The SCPI error queue.
+struct ErrorQueue(Deque<ScpiError, ERROR_QUEUE_SIZE>);
+
+impl ErrorQueue {
+    /// Record an error in the error queue.
+    fn push(&mut self, error: ScpiError) {
+        if self.0.is_full() {
+            // The most recent error is replaced with an overflow indication.
+            self.0.pop_back();
+            self.0.push_back(ScpiError::QueueOverflow).ok();
+        } else {
+            self.0.push_back(error).ok();
+        }
+    }
+}
+
+/// State of the SCPI interface that is independent of the client connection.
+struct Interface {
+    metadata: &'static ApplicationMetadata,
+    identifier: String<32>,
+    errors: ErrorQueue,
+}
+
+impl Interface {
+    /// Handle commands that are serviced by the interface itself.
+    ///
+    /// # Returns
+    /// None if the command is not an interface command. Otherwise, the result of the command.
+    fn handle_common(
+        &mut self,
+        command: &str,
+        output: &mut String<RESPONSE_SIZE>,
+    ) -> Option<Result<(), ScpiError>> {
+        if command.eq_ignore_ascii_case("*IDN?") {
+            let result = write!(
+                output,
+                "Sinara,Booster {},{},{}",
+                self.metadata.hardware_version, self.identifier, self.metadata.firmware_version
+            );
+            return Some(result.map_err(Into::into));
+        }
+
+        if command.eq_ignore_ascii_case("*CLS") {
+            self.errors.0.clear();
+            return Some(Ok(()));
+        }
+
+        let header = command.trim_start_matches(':').strip_suffix('?')?;
+        let mut keywords = header.split(':');
+        if !keywords
+            .next()
+            .is_some_and(|keyword| keyword_matches(keyword, "SYSTem"))
+            || !keywords
+                .next()
+                .is_some_and(|keyword| keyword_matches(keyword, "ERRor"))
+        {
+            return None;
+        }
+
+        match keywords.next() {
+            None => {}
+            Some(keyword) if keyword_matches(keyword, "NEXT") => {}
+            Some(_) => return None,
+        }
+
+        let (code, description) = match self.errors.0.pop_front() {
+            Some(error) => error.describe(),
+            None => (0, "No error"),
+        };
+
+        Some(write!(output, "{code},\"{description}\"").map_err(Into::into))
+    }
+
+    /// Execute a received command line and queue the response for transmission.
+    fn process_line(
+        &mut self,
+        line: &str,
+        response: &mut Vec<u8, RESPONSE_SIZE>,
+        execute: &mut impl FnMut(&str, &mut String<RESPONSE_SIZE>) -> Result<(), ScpiError>,
+    ) {
+        let mut responses: String<RESPONSE_SIZE> = String::new();
+
+        for command in line.split(';').map(str::trim).filter(|cmd| !cmd.is_empty()) {
+            let mut output: String<RESPONSE_SIZE> = String::new();
+            let result = match self.handle_common(command, &mut output) {
+                Some(result) => result,
+                None => execute(command, &mut output),
+            };
+
+            if let Err(error) = result {
+                self.errors.push(error);
+                continue;
+            }
+
+            if output.is_empty() {
+                continue;
+            }
+
+            if (!responses.is_empty() && responses.push(';').is_err())
+                || responses.push_str(&output).is_err()
+            {
+                self.errors.push(ScpiError::Execution);
+            }
+        }
+
+        if !responses.is_empty()
+            && (response.extend_from_slice(responses.as_bytes()).is_err()
+                || response.push(b'\n').is_err())
+        {
+            self.errors.push(ScpiError::Execution);
+        }
+    }
+}
+
+/// A TCP server for the SCPI command interface.
+pub struct ScpiServer {
+    server: LineServer<LINE_SIZE, RESPONSE_SIZE>,
+    interface: Interface,
+}
+
+impl ScpiServer {
+    /// Construct the SCPI server.
+    ///
+    /// # Args
+    /// * `stack` - The network stack to use for serving requests.
+    /// * `metadata` - The application metadata used to identify the device.
+    /// * `identifier` - The unique identifier of this device.
+    pub fn new(
+        stack: NetworkStackProxy,
+        metadata: &'static ApplicationMetadata,
+        identifier: &str,
+    ) -> Self {
+        let mut id = String::new();
+        id.push_str(identifier).unwrap();
+
+        Self {
+            server: LineServer::new(stack, SCPI_PORT),
+            interface: Interface {
+                metadata,
+                identifier: id,
+                errors: ErrorQueue(Deque::new()),
+            },
+        }
+    }
+
+    /// Handle the SCPI server.
+    ///
+    /// # Note
+    /// This function must be called periodically to service incoming connections.
+    ///
+    /// # Args
+    /// * `execute` - A function that executes a single channel command and writes any query
+    ///   response into the provided buffer.
+    pub fn poll(
+        &mut self,
+        mut execute: impl FnMut(&str, &mut String<RESPONSE_SIZE>) -> Result<(), ScpiError>,
+    ) {
+        let Self { server, interface } = self;
+
+        server.poll(|line, response| match line {
+            Line::Received(line) => interface.process_line(line, response, &mut execute),
+            Line::Overflow => interface.errors.push(ScpiError::Command),
+            Line::Invalid => interface.errors.push(ScpiError::Syntax),
+        });
+    }
+}
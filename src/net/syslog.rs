@@ -0,0 +1,148 @@
+//! Booster remote syslog logging sink
+//!
+//! # Design
+//! Log records are forwarded to a remote syslog server via UDP in the
+//! [RFC 5424](https://www.rfc-editor.org/rfc/rfc5424) format. Messages are timestamped using the
+//! real-time clock once it has been set. Until then, the timestamp of each message is left
+//! unspecified and the receiving server is expected to timestamp messages on reception. Delivery is
+//! best-effort: messages are dropped if they cannot be transmitted.
+
+use super::NetworkStackProxy;
+use crate::{hardware::rtc::DateTime, logger::SyslogRecord, settings::global_settings::IpAddr};
+
+use core::fmt::Write;
+use heapless::String;
+use smoltcp_nal::embedded_nal::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpClientStack};
+
+/// The UDP port of the remote syslog server.
+pub const SYSLOG_PORT: u16 = 514;
+
+/// The syslog facility used for all messages (local0).
+const FACILITY: u8 = 16;
+
+type UdpSocket = <NetworkStackProxy as UdpClientStack>::UdpSocket;
+
+/// Get the syslog severity of a log level.
+fn severity(level: log::Level) -> u8 {
+    match level {
+        log::Level::Error => 3,
+        log::Level::Warn => 4,
+        log::Level::Info => 6,
+        log::Level::Debug | log::Level::Trace => 7,
+    }
+}
+
+/// A client forwarding log records to a remote syslog server.
+pub struct SyslogClient {
+    stack: NetworkStackProxy,
+    socket: Option<UdpSocket>,
+    server: Option<Ipv4Addr>,
+    hostname: String<32>,
+}
+
+impl SyslogClient {
+    /// Construct the syslog client.
+    ///
+    /// # Args
+    /// * `stack` - The network stack to use for transmitting logs.
+    /// * `hostname` - The hostname reported in all messages.
+    pub fn new(stack: NetworkStackProxy, hostname: &str) -> Self {
+        let mut name = String::new();
+        name.push_str(hostname).unwrap();
+
+        Self {
+            stack,
+            socket: None,
+            server: None,
+            hostname: name,
+        }
+    }
+
+    /// Configure the remote syslog server.
+    ///
+    /// # Args
+    /// * `server` - The address of the syslog server. If unspecified, logs are not forwarded.
+    pub fn set_server(&mut self, server: IpAddr) {
+        let server = (!server.0.is_unspecified()).then(|| {
+            let [a, b, c, d] = server.0 .0;
+            Ipv4Addr::new(a, b, c, d)
+        });
+
+        if server == self.server {
+            return;
+        }
+
+        self.server = server;
+        if let Some(socket) = self.socket.take() {
+            self.stack.close(socket).ok();
+        }
+    }
+
+    /// Open a socket connected to the syslog server.
+    fn connect(&mut self, server: Ipv4Addr) -> Option<UdpSocket> {
+        let mut socket = self.stack.socket().ok()?;
+        let address = SocketAddr::V4(SocketAddrV4::new(server, SYSLOG_PORT));
+
+        if self.stack.connect(&mut socket, address).is_err() {
+            self.stack.close(socket).ok();
+            return None;
+        }
+
+        Some(socket)
+    }
+
+    /// Forward pending log records to the syslog server.
+    ///
+    /// # Note
+    /// This function must be called periodically to forward logs.
+    ///
+    /// # Args
+    /// * `next` - A function providing the next pending log record.
+    pub fn update(&mut self, mut next: impl FnMut() -> Option<SyslogRecord>) {
+        let Some(server) = self.server else {
+            // Discard records while no server is configured.
+            while next().is_some() {}
+            return;
+        };
+
+        if self.socket.is_none() {
+            self.socket = self.connect(server);
+        }
+
+        while let Some(record) = next() {
+            let Some(socket) = self.socket.as_mut() else {
+                continue;
+            };
+
+            let mut message: String<384> = String::new();
+            if write!(
+                &mut message,
+                "<{}>1 ",
+                FACILITY * 8 + severity(record.level)
+            )
+            .is_err()
+            {
+                continue;
+            }
+
+            // The NILVALUE is used if the time is unknown.
+            let timestamp = match record.timestamp {
+                Some(time) => write!(&mut message, "{}", DateTime::from_unix(time)),
+                None => write!(&mut message, "-"),
+            };
+
+            if timestamp.is_err()
+                || write!(
+                    &mut message,
+                    " {} booster - - - {}",
+                    self.hostname, record.message
+                )
+                .is_err()
+            {
+                continue;
+            }
+
+            self.stack.send(socket, message.as_bytes()).ok();
+        }
+    }
+}
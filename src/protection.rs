@@ -0,0 +1,181 @@
+//! Booster chassis-level protection
+//!
+//! # Design
+//! All RF modules share the chassis heat sink and the 12V input supply. Chassis-level protection
+//! disables the channels with the lowest priority when either is overloaded:
+//! * Thermal foldback: Nearly all of the power drawn from the P28V rail of a channel is dissipated
+//!   in the heat sink, so the dissipated power is conservatively estimated as the sum of the P28V
+//!   rail power of all channels.
+//! * Load shedding: Booster has no input current sensor, so the 12V input current is estimated
+//!   from the P28V and P5V rail power of all channels and the efficiency of the supply converters.
+//!   Channels are shed once the estimate approaches the configured current limit.
+//!
+//! Channels are disabled one at a time, once per telemetry acquisition cycle, so that the
+//! measurements reflect a disabled channel before further channels are disabled.
+use crate::{hardware::Channel, telemetry::ChannelTelemetry};
+use serde::{Deserialize, Serialize};
+
+/// The nominal voltage of the P28V rail of the RF modules.
+pub const P28V_VOLTAGE: f32 = 28.0;
+
+/// The nominal voltage of the input supply.
+const INPUT_VOLTAGE: f32 = 12.0;
+
+/// The assumed efficiency of the channel supply converters.
+const CONVERTER_EFFICIENCY: f32 = 0.85;
+
+/// The fraction of the input current limit at which channels are shed.
+const SHEDDING_MARGIN: f32 = 0.9;
+
+/// The P28V current in amperes above which a channel is considered to be drawing power.
+const MINIMUM_ACTIVE_CURRENT: f32 = 0.010;
+
+/// Configuration of the chassis thermal foldback.
+#[derive(Serialize, Deserialize, miniconf::Tree, Copy, Clone, Debug, PartialEq)]
+pub struct ThermalFoldback {
+    /// Specified true to disable channels when the power limit is exceeded.
+    pub enabled: bool,
+
+    /// The maximum power in watts that all channels may dissipate in the chassis heat sink.
+    pub power_limit: f32,
+}
+
+impl Default for ThermalFoldback {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            power_limit: 150.0,
+        }
+    }
+}
+
+/// Configuration of input supply load shedding.
+#[derive(Serialize, Deserialize, miniconf::Tree, Copy, Clone, Debug, PartialEq)]
+pub struct LoadShedding {
+    /// Specified true to shed channels when the input current limit is approached.
+    pub enabled: bool,
+
+    /// The current limit of the 12V input supply in amperes.
+    pub current_limit: f32,
+}
+
+impl Default for LoadShedding {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            current_limit: 20.0,
+        }
+    }
+}
+
+/// The reason that a channel was shed.
+#[derive(Serialize, Copy, Clone, Debug, PartialEq)]
+pub enum ShedReason {
+    /// The power dissipated by all channels exceeded the thermal foldback limit.
+    ThermalFoldback,
+
+    /// The estimated input current approached the input current limit.
+    InputCurrent,
+}
+
+/// Get the P28V rail current of a channel.
+///
+/// # Returns
+/// The current in amperes, or None if the channel was not measured.
+fn p28v_current(telemetry: &Option<ChannelTelemetry>) -> Option<f32> {
+    match telemetry {
+        Some(ChannelTelemetry::Status(status)) => Some(status.p28v_current),
+        _ => None,
+    }
+}
+
+/// Estimate the power dissipated by all channels.
+///
+/// # Args
+/// * `channels` - The most recent telemetry of all channels.
+///
+/// # Returns
+/// The dissipated power in watts.
+pub fn dissipated_power(channels: &[Option<ChannelTelemetry>; 8]) -> f32 {
+    channels
+        .iter()
+        .filter_map(p28v_current)
+        .map(|current| current * P28V_VOLTAGE)
+        .sum()
+}
+
+/// Estimate the current drawn from the 12V input supply by all channels.
+///
+/// # Args
+/// * `channels` - The most recent telemetry of all channels.
+///
+/// # Returns
+/// The estimated input current in amperes.
+pub fn input_current(channels: &[Option<ChannelTelemetry>; 8]) -> f32 {
+    let power: f32 = channels
+        .iter()
+        .filter_map(|telemetry| match telemetry {
+            Some(ChannelTelemetry::Status(status)) => {
+                Some(status.p28v_current * P28V_VOLTAGE + status.p5v_current * status.p5v_voltage)
+            }
+            _ => None,
+        })
+        .sum();
+
+    power / CONVERTER_EFFICIENCY / INPUT_VOLTAGE
+}
+
+/// Check if channels must be shed to protect the chassis.
+///
+/// # Args
+/// * `channels` - The most recent telemetry of all channels.
+/// * `foldback` - The thermal foldback configuration.
+/// * `shedding` - The load shedding configuration.
+///
+/// # Returns
+/// The reason that a channel must be shed, if any.
+pub fn check(
+    channels: &[Option<ChannelTelemetry>; 8],
+    foldback: &ThermalFoldback,
+    shedding: &LoadShedding,
+) -> Option<ShedReason> {
+    if foldback.enabled {
+        let power = dissipated_power(channels);
+        if power > foldback.power_limit {
+            log::warn!("Dissipated power of {} W exceeds the foldback limit", power);
+            return Some(ShedReason::ThermalFoldback);
+        }
+    }
+
+    if shedding.enabled {
+        let current = input_current(channels);
+        if current > shedding.current_limit * SHEDDING_MARGIN {
+            log::warn!("Input current of {} A approaches the supply limit", current);
+            return Some(ShedReason::InputCurrent);
+        }
+    }
+
+    None
+}
+
+/// Find the active channel with the lowest priority.
+///
+/// # Args
+/// * `channels` - The most recent telemetry of all channels.
+/// * `priority` - The priority of each channel. Higher values indicate more important channels.
+///
+/// # Returns
+/// The channel drawing power with the lowest priority. Of channels with equal priority, the channel
+/// with the highest index is selected. None if no channel is drawing power.
+pub fn lowest_priority(
+    channels: &[Option<ChannelTelemetry>; 8],
+    priority: &[u8; 8],
+) -> Option<Channel> {
+    enum_iterator::all::<Channel>()
+        .zip(channels.iter())
+        .filter(|(_, telemetry)| {
+            p28v_current(telemetry).is_some_and(|current| current > MINIMUM_ACTIVE_CURRENT)
+        })
+        .min_by_key(|(idx, _)| (priority[*idx as usize], core::cmp::Reverse(*idx as usize)))
+        .map(|(idx, _)| idx)
+}
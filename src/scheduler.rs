@@ -0,0 +1,89 @@
+//! Booster scheduled channel enable and disable
+//!
+//! # Design
+//! Each channel can be assigned a daily schedule during which it is enabled, e.g. to disable
+//! amplifiers overnight. Schedules are evaluated periodically against the real-time clock and are
+//! only evaluated once the clock has been set. All times are in UTC.
+//!
+//! Schedules are edge-triggered: the channel `state` setting is only updated when a scheduled
+//! window starts or ends, so that the channel may still be operated manually within a window. A
+//! schedule can additionally be held via the `schedule/hold` control command, which suspends the
+//! schedule of the channel until the hold is released.
+use crate::hardware::Channel;
+use core::sync::atomic::{AtomicU8, Ordering};
+use serde::{Deserialize, Serialize};
+
+/// The number of minutes in a day.
+const MINUTES_PER_DAY: u16 = 1440;
+
+/// The channels whose schedules are held, indexed by channel.
+static HELD: AtomicU8 = AtomicU8::new(0);
+
+/// A daily schedule of a channel.
+#[derive(Serialize, Deserialize, miniconf::Tree, Copy, Clone, Debug, PartialEq)]
+pub struct Schedule {
+    /// Specified true to enable and disable the channel according to the schedule.
+    pub enabled: bool,
+
+    /// The time at which the channel is enabled in minutes after midnight UTC.
+    pub enable_minute: u16,
+
+    /// The time at which the channel is disabled in minutes after midnight UTC. If earlier than
+    /// `enable_minute`, the channel remains enabled past midnight.
+    pub disable_minute: u16,
+
+    /// The weekdays on which the channel is enabled. Bit 0 corresponds to Monday and bit 6 to
+    /// Sunday.
+    pub weekdays: u8,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            // 08:00 to 20:00 on all days.
+            enable_minute: 8 * 60,
+            disable_minute: 20 * 60,
+            weekdays: 0x7F,
+        }
+    }
+}
+
+impl Schedule {
+    /// Check that the schedule is valid.
+    pub fn is_valid(&self) -> bool {
+        self.enable_minute < MINUTES_PER_DAY && self.disable_minute < MINUTES_PER_DAY
+    }
+
+    /// Check if the channel is scheduled to be enabled.
+    ///
+    /// # Args
+    /// * `time` - The number of seconds since 1970-01-01T00:00:00Z.
+    pub fn is_active(&self, time: u32) -> bool {
+        calendar::DailyWindow {
+            start_minute: self.enable_minute,
+            end_minute: self.disable_minute,
+            weekdays: self.weekdays,
+        }
+        .contains(time)
+    }
+}
+
+/// Hold or release the schedule of a channel.
+///
+/// # Args
+/// * `channel` - The channel whose schedule to hold.
+/// * `hold` - Specified true to suspend the schedule until released.
+pub fn set_hold(channel: Channel, hold: bool) {
+    let mask = 1 << channel as u8;
+    if hold {
+        HELD.fetch_or(mask, Ordering::Relaxed);
+    } else {
+        HELD.fetch_and(!mask, Ordering::Relaxed);
+    }
+}
+
+/// Check if the schedule of a channel is held.
+pub fn is_held(channel: Channel) -> bool {
+    HELD.load(Ordering::Relaxed) & (1 << channel as u8) != 0
+}
@@ -1,21 +1,123 @@
 //! Booster NGFW NVM channel settings
 
 use super::{SemVersion, SinaraBoardId, SinaraConfiguration};
-use crate::{hardware::I2cProxy, linear_transformation::LinearTransformation, Error};
+use crate::{hardware::I2cProxy, linear_transformation::LinearTransformation, net::json, Error};
+use core::fmt::Write;
 use encdec::{Decode, DecodeOwned, Encode};
 use enum_iterator::Sequence;
+use heapless::{String, Vec};
 use microchip_24aa02e48::Microchip24AA02E48;
-use miniconf::Tree;
+use miniconf::{JsonCoreSlash, Tree, TreeKey};
 use serde::{Deserialize, Serialize};
 
 /// The expected semver of the BoosterChannelSettings. This version must be updated whenever the
 /// `VersionedChannelData` layout is updated.
 const EXPECTED_VERSION: SemVersion = SemVersion {
     major: 1,
-    minor: 0,
-    patch: 1,
+    minor: 1,
+    patch: 0,
 };
 
+/// The rated number of write cycles of the RF module EEPROM.
+const EEPROM_ENDURANCE: u32 = 1_000_000;
+
+/// The number of properties of a channel.
+pub const CHANNEL_PROPERTIES: usize = 8;
+
+/// The maximum length of a channel property path.
+const PROPERTY_PATH_SIZE: usize = 32;
+
+/// The maximum length of a channel label in bytes.
+const LABEL_CAPACITY: usize = 16;
+
+/// A short user-assigned name of a channel.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct ChannelLabel {
+    len: u8,
+    data: [u8; LABEL_CAPACITY],
+}
+
+impl ChannelLabel {
+    /// Get the label as a string.
+    pub fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.data[..self.len as usize]).unwrap_or_default()
+    }
+}
+
+impl core::str::FromStr for ChannelLabel {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() > LABEL_CAPACITY {
+            return Err("Label too long");
+        }
+
+        let mut label = Self {
+            len: s.len() as u8,
+            ..Default::default()
+        };
+        label.data[..s.len()].copy_from_slice(s.as_bytes());
+        Ok(label)
+    }
+}
+
+impl Serialize for ChannelLabel {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ChannelLabel {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let label = <&str>::deserialize(deserializer)?;
+        label.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl Encode for ChannelLabel {
+    type Error = encdec::Error;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(1 + LABEL_CAPACITY)
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        if buff.len() < 1 + LABEL_CAPACITY {
+            return Err(encdec::Error::Length);
+        }
+
+        buff[0] = self.len;
+        buff[1..][..LABEL_CAPACITY].copy_from_slice(&self.data);
+        Ok(1 + LABEL_CAPACITY)
+    }
+}
+
+impl DecodeOwned for ChannelLabel {
+    type Output = ChannelLabel;
+
+    type Error = encdec::Error;
+
+    fn decode_owned(buff: &[u8]) -> Result<(Self::Output, usize), Self::Error> {
+        if buff.len() < 1 + LABEL_CAPACITY {
+            return Err(encdec::Error::Length);
+        }
+
+        let mut label = Self {
+            len: buff[0],
+            ..Default::default()
+        };
+        label.data.copy_from_slice(&buff[1..][..LABEL_CAPACITY]);
+
+        if label.len as usize > LABEL_CAPACITY
+            || core::str::from_utf8(&label.data[..label.len as usize]).is_err()
+        {
+            return Err(encdec::Error::Utf8);
+        }
+
+        Ok((label, 1 + LABEL_CAPACITY))
+    }
+}
+
 /// Indicates the desired state of a channel.
 #[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Sequence)]
 #[repr(u8)]
@@ -29,6 +131,10 @@ pub enum ChannelState {
 
     /// Stages are powered but RF switch is disabled. Used for bias current tuning.
     Powered = 2,
+
+    /// Stages are powered with the RF switch disabled, the bias starting in pinch-off and a
+    /// reduced drain current alarm window. Used for examining suspect modules.
+    Diagnostic = 3,
 }
 
 impl Encode for ChannelState {
@@ -69,6 +175,22 @@ impl DecodeOwned for ChannelState {
     }
 }
 
+/// Describes why stored channel settings were rejected.
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct ValidationError {
+    /// The invalid field of the stored settings.
+    pub field: &'static str,
+
+    /// The reason that the field is invalid.
+    pub reason: &'static str,
+}
+
+impl ValidationError {
+    fn new(field: &'static str, reason: &'static str) -> Self {
+        Self { field, reason }
+    }
+}
+
 /// Represents booster channel-specific configuration values.
 #[derive(Tree, Encode, DecodeOwned, Debug, Copy, Clone, PartialEq)]
 pub struct ChannelSettings {
@@ -78,41 +200,238 @@ pub struct ChannelSettings {
     pub input_power_transform: LinearTransformation,
     pub output_power_transform: LinearTransformation,
     pub reflected_power_transform: LinearTransformation,
+
+    /// A short user-assigned name of the channel.
+    pub label: ChannelLabel,
+}
+
+impl ChannelSettings {
+    /// Check that the settings are within the valid range.
+    fn validate(&self) -> Result<(), ValidationError> {
+        if !(-3.3..=0.0).contains(&self.bias_voltage) {
+            return Err(ValidationError::new("bias_voltage", "Out of range"));
+        }
+
+        if !self.output_interlock_threshold.is_finite() {
+            return Err(ValidationError::new(
+                "output_interlock_threshold",
+                "Not finite",
+            ));
+        }
+
+        for (field, transform) in [
+            ("input_power_transform", &self.input_power_transform),
+            ("output_power_transform", &self.output_power_transform),
+            ("reflected_power_transform", &self.reflected_power_transform),
+        ] {
+            if !transform.is_valid() {
+                return Err(ValidationError::new(field, "Not invertible"));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the settings as they are persisted to EEPROM.
+    ///
+    /// # Note
+    /// `Powered` and `Diagnostic` states are never persisted, since this is never desired. They
+    /// are stored as `Off` instead.
+    pub fn persisted(&self) -> Self {
+        let mut settings = *self;
+        if matches!(
+            settings.state,
+            ChannelState::Powered | ChannelState::Diagnostic
+        ) {
+            settings.state = ChannelState::Off;
+        }
+
+        settings
+    }
+
+    /// Copy the calibration, interlock threshold, and bias of another channel.
+    ///
+    /// # Note
+    /// The channel state and label are not copied.
+    ///
+    /// # Args
+    /// * `source` - The settings of the channel to copy from.
+    pub fn copy_configuration(&mut self, source: &ChannelSettings) {
+        *self = Self {
+            state: self.state,
+            label: self.label,
+            ..*source
+        };
+    }
+
+    /// Serialize properties of the channel into a JSON object keyed by property name.
+    ///
+    /// # Args
+    /// * `properties` - The names of the properties to serialize, e.g. `bias_voltage`. All
+    ///   properties are serialized if empty.
+    /// * `output` - The location to serialize the object into.
+    ///
+    /// # Returns
+    /// The length of the serialized object.
+    pub fn read_properties(
+        &self,
+        properties: &[&str],
+        output: &mut [u8],
+    ) -> Result<usize, &'static str> {
+        let mut paths: Vec<String<PROPERTY_PATH_SIZE>, CHANNEL_PROPERTIES> = Vec::new();
+        if properties.is_empty() {
+            for path in Self::iter_paths::<String<PROPERTY_PATH_SIZE>>("/") {
+                let path = path.map_err(|_| "Invalid property")?;
+                paths.push(path).map_err(|_| "Too many properties")?;
+            }
+        } else {
+            for property in properties {
+                let mut path = String::new();
+                write!(path, "/{property}").map_err(|_| "Invalid property")?;
+                paths.push(path).map_err(|_| "Too many properties")?;
+            }
+        }
+
+        let mut len = 0;
+        for (index, path) in paths.iter().enumerate() {
+            let separator: &[u8] = if index == 0 { b"{\"" } else { b",\"" };
+            append(output, &mut len, separator)?;
+            append(output, &mut len, path[1..].as_bytes())?;
+            append(output, &mut len, b"\":")?;
+            len += self
+                .get_json(path, &mut output[len..])
+                .map_err(|_| "Invalid property")?;
+        }
+        append(output, &mut len, b"}")?;
+
+        Ok(len)
+    }
+
+    /// Update properties of the channel from a JSON object keyed by property name.
+    ///
+    /// # Note
+    /// The settings are only modified if all properties are valid.
+    ///
+    /// # Args
+    /// * `properties` - The serialized JSON object of properties, e.g. `{"bias_voltage": -2.5}`.
+    pub fn write_properties(&mut self, properties: &[u8]) -> Result<(), &'static str> {
+        let mut settings = *self;
+        let mut written = 0;
+
+        for (property, value) in json::members(properties) {
+            let property = core::str::from_utf8(property).map_err(|_| "Invalid property")?;
+            let mut path: String<PROPERTY_PATH_SIZE> = String::new();
+            write!(path, "/{property}").map_err(|_| "Invalid property")?;
+            settings
+                .set_json(&path, value)
+                .map_err(|_| "Invalid property or value")?;
+            written += 1;
+        }
+
+        if written == 0 {
+            return Err("No properties");
+        }
+
+        *self = settings;
+        Ok(())
+    }
+}
+
+/// Append data to a partially filled buffer.
+fn append(output: &mut [u8], len: &mut usize, data: &[u8]) -> Result<(), &'static str> {
+    let end = *len + data.len();
+    output
+        .get_mut(*len..end)
+        .ok_or("Response too large")?
+        .copy_from_slice(data);
+    *len = end;
+    Ok(())
 }
 
 impl Default for ChannelSettings {
     /// Generate default booster channel data.
     fn default() -> Self {
+        // When operating at 100MHz, the power detectors specify the following output
+        // characteristics for -10 dBm to 10 dBm (the equation uses slightly different coefficients
+        // for different power levels and frequencies):
+        //
+        // dBm = V(Vout) / .035 V/dB - 35.6 dBm
+        let detector = LinearTransformation::new(1.0 / 0.035, -35.6);
+
+        // The input power and reflected power detectors are then passed through an op-amp with
+        // gain 1.5x - this modifies the slope from 35mV/dB to 52.5mV/dB
+        let amplifier = LinearTransformation::new(1.5, 0.0).invert();
+
+        // All of the power meters are preceded by attenuators which are incorporated in the
+        // offset.
+        let output_attenuation = LinearTransformation::new(1.0, 19.8 + 10.0);
+        let input_attenuation = LinearTransformation::new(1.0, 8.9);
+
         Self {
             output_interlock_threshold: 0.0,
             bias_voltage: -3.2,
             state: ChannelState::Off,
+            output_power_transform: detector.compose(&output_attenuation),
+            reflected_power_transform: amplifier.compose(&detector).compose(&output_attenuation),
+            input_power_transform: amplifier.compose(&detector).compose(&input_attenuation),
+            label: ChannelLabel::default(),
+        }
+    }
+}
 
-            // When operating at 100MHz, the power detectors specify the following output
-            // characteristics for -10 dBm to 10 dBm (the equation uses slightly different coefficients
-            // for different power levels and frequencies):
-            //
-            // dBm = V(Vout) / .035 V/dB - 35.6 dBm
-            //
-            // All of the power meters are preceded by attenuators which are incorporated in
-            // the offset.
-            output_power_transform: LinearTransformation::new(1.0 / 0.035, -35.6 + 19.8 + 10.0),
-            // The input power and reflected power detectors are then passed through an
-            // op-amp with gain 1.5x - this modifies the slope from 35mV/dB to 52.5mV/dB
-            reflected_power_transform: LinearTransformation::new(
-                1.0 / 1.5 / 0.035,
-                -35.6 + 19.8 + 10.0,
-            ),
-            input_power_transform: LinearTransformation::new(1.0 / 1.5 / 0.035, -35.6 + 8.9),
+/// The layout of channel settings stored by firmware prior to the addition of channel labels.
+#[derive(DecodeOwned, Debug, Copy, Clone)]
+struct LegacyChannelSettings {
+    output_interlock_threshold: f32,
+    bias_voltage: f32,
+    state: ChannelState,
+    input_power_transform: LinearTransformation,
+    output_power_transform: LinearTransformation,
+    reflected_power_transform: LinearTransformation,
+}
+
+impl From<LegacyChannelSettings> for ChannelSettings {
+    fn from(legacy: LegacyChannelSettings) -> Self {
+        Self {
+            output_interlock_threshold: legacy.output_interlock_threshold,
+            bias_voltage: legacy.bias_voltage,
+            state: legacy.state,
+            input_power_transform: legacy.input_power_transform,
+            output_power_transform: legacy.output_power_transform,
+            reflected_power_transform: legacy.reflected_power_transform,
+            label: ChannelLabel::default(),
         }
     }
 }
 
+/// The layout of versioned channel data stored by firmware prior to the addition of channel
+/// labels.
+#[derive(DecodeOwned, Debug, Copy, Clone)]
+struct LegacyVersionedChannelData {
+    version: SemVersion,
+    settings: LegacyChannelSettings,
+}
+
+/// The version of [LegacyVersionedChannelData].
+const LEGACY_VERSION: SemVersion = SemVersion {
+    major: 1,
+    minor: 0,
+    patch: 1,
+};
+
 /// Represents versioned channel-specific configuration values.
+///
+/// # Note
+/// The serialized data must fit into the 64 bytes of Sinara board data, of which 57 bytes are
+/// currently used. The upper half of the RF module EEPROM is write-protected, so no further storage
+/// is available for e.g. additional configuration profiles.
 #[derive(Encode, DecodeOwned, Debug, Copy, Clone)]
 struct VersionedChannelData {
     version: SemVersion,
     settings: ChannelSettings,
+
+    /// The number of times the settings have been written to EEPROM.
+    writes: u32,
 }
 
 impl Default for VersionedChannelData {
@@ -120,6 +439,7 @@ impl Default for VersionedChannelData {
         Self {
             version: EXPECTED_VERSION,
             settings: ChannelSettings::default(),
+            writes: 0,
         }
     }
 }
@@ -133,18 +453,28 @@ impl VersionedChannelData {
     ///
     /// # Returns
     /// The configuration if deserialization was successful. Otherwise, returns an error.
-    pub fn deserialize(data: &[u8; 64]) -> Result<Self, Error> {
-        let (data, _) = VersionedChannelData::decode_owned(data).or(Err(Error::Invalid))?;
-
-        // Validate configuration parameters.
-        if data.settings.bias_voltage < -3.3 || data.settings.bias_voltage > 0.0 {
-            return Err(Error::Invalid);
-        }
+    pub fn deserialize(data: &[u8; 64]) -> Result<Self, ValidationError> {
+        let (version, _) = SemVersion::decode_owned(data)
+            .map_err(|_| ValidationError::new("version", "Malformed"))?;
+
+        // Settings saved by older firmware do not contain a channel label.
+        let data = if EXPECTED_VERSION.is_compatible_with(&version) {
+            VersionedChannelData::decode_owned(data)
+                .map_err(|_| ValidationError::new("settings", "Malformed"))?
+                .0
+        } else if LEGACY_VERSION.is_compatible_with(&version) {
+            let (legacy, _) = LegacyVersionedChannelData::decode_owned(data)
+                .map_err(|_| ValidationError::new("settings", "Malformed"))?;
+            VersionedChannelData {
+                version: EXPECTED_VERSION,
+                settings: legacy.settings.into(),
+                writes: 0,
+            }
+        } else {
+            return Err(ValidationError::new("version", "Unsupported"));
+        };
 
-        // Validate the version of the settings.
-        if !EXPECTED_VERSION.is_compatible_with(&data.version) {
-            return Err(Error::Invalid);
-        }
+        data.settings.validate()?;
 
         Ok(data)
     }
@@ -154,12 +484,11 @@ impl VersionedChannelData {
     /// # Args
     /// * `config` - The sinara configuration to serialize the booster configuration into.
     pub fn serialize_into(&self, config: &mut SinaraConfiguration) {
-        // We will never store `Powered` in EEPROM, since this is never desired. Cache the current
-        // power state while we serialize to ensure we only serialize Enabled and Off.
-        let mut versioned_copy = *self;
-        if versioned_copy.settings.state == ChannelState::Powered {
-            versioned_copy.settings.state = ChannelState::Off;
-        }
+        // We will never store `Powered` or `Diagnostic` in EEPROM, since this is never desired.
+        let versioned_copy = Self {
+            settings: self.settings.persisted(),
+            ..*self
+        };
 
         let mut buffer: [u8; 64] = [0; 64];
         let len = versioned_copy.encode(&mut buffer).unwrap();
@@ -171,6 +500,15 @@ impl VersionedChannelData {
 pub struct BoosterChannelSettings {
     eeprom: Microchip24AA02E48<I2cProxy>,
     data: VersionedChannelData,
+
+    /// The settings most recently persisted to EEPROM, if the EEPROM contains valid settings.
+    saved: Option<ChannelSettings>,
+
+    /// The reason that the settings stored in EEPROM were rejected, if any.
+    error: Option<ValidationError>,
+
+    /// Specified true if the EEPROM contained a valid Sinara configuration when loaded.
+    eeprom_valid: bool,
 }
 
 impl BoosterChannelSettings {
@@ -185,28 +523,109 @@ impl BoosterChannelSettings {
         let mut settings = Self {
             eeprom,
             data: VersionedChannelData::default(),
+            saved: None,
+            error: None,
+            eeprom_valid: false,
         };
 
-        settings.data = settings
-            .load_config()
-            .and_then(|config|
-                // If we loaded sinara configuration, deserialize the board data.
-                VersionedChannelData::deserialize(&config.board_data))
-            .unwrap_or_default();
+        // Note: Modules without a valid Sinara configuration (e.g. with a blank EEPROM) silently
+        // use default settings.
+        if let Ok(config) = settings.load_config() {
+            settings.eeprom_valid = true;
+
+            // If we loaded sinara configuration, deserialize the board data.
+            match VersionedChannelData::deserialize(&config.board_data) {
+                Ok(data) => {
+                    settings.saved = Some(data.settings.persisted());
+                    settings.data = data;
+                }
+                Err(error) => {
+                    log::error!(
+                        "Stored channel settings are invalid ({}: {}). Using defaults",
+                        error.field,
+                        error.reason
+                    );
+                    settings.error = Some(error);
+                }
+            }
+        }
 
         settings
     }
 
     /// Save the configuration settings to EEPROM for retrieval.
+    ///
+    /// # Note
+    /// To limit EEPROM wear, the EEPROM is not written if it already contains the settings.
     pub fn save(&mut self) {
+        if !self.is_dirty() {
+            return;
+        }
+
         let mut config = match self.load_config() {
             Err(_) => SinaraConfiguration::default(SinaraBoardId::RfChannel),
             Ok(config) => config,
         };
 
+        self.data.writes = self.data.writes.saturating_add(1);
+        if self.data.writes == EEPROM_ENDURANCE {
+            log::warn!("RF module EEPROM has reached its rated write endurance");
+        }
+
         self.data.serialize_into(&mut config);
         config.update_crc32();
         self.save_config(&config);
+        self.saved = Some(self.data.settings.persisted());
+    }
+
+    /// Check if the settings have been modified since they were last saved.
+    pub fn is_dirty(&self) -> bool {
+        self.saved != Some(self.data.settings.persisted())
+    }
+
+    /// Get the reason that the settings stored in EEPROM were rejected when loading, if any.
+    pub fn error(&self) -> Option<ValidationError> {
+        self.error
+    }
+
+    /// Check if the EEPROM contained a valid Sinara configuration (i.e. with a valid CRC) when the
+    /// settings were loaded.
+    pub fn eeprom_valid(&self) -> bool {
+        self.eeprom_valid
+    }
+
+    /// Get the CRC32 of the settings most recently persisted to EEPROM.
+    ///
+    /// # Note
+    /// The CRC covers the encoded settings only, so it is not affected by the EEPROM write counter.
+    ///
+    /// # Returns
+    /// The CRC if the EEPROM contains valid settings. Otherwise, None.
+    pub fn saved_crc32(&self) -> Option<u32> {
+        let mut buffer = [0u8; 64];
+        let len = self.saved?.encode(&mut buffer).ok()?;
+        Some(super::crc32(&buffer[..len]))
+    }
+
+    /// Get the number of times the settings have been written to EEPROM.
+    pub fn writes(&self) -> u32 {
+        self.data.writes
+    }
+
+    /// Restore the default settings and save them to EEPROM.
+    ///
+    /// # Args
+    /// * `preserve_calibration` - Specified true if the power detector transforms should be kept.
+    pub fn factory_reset(&mut self, preserve_calibration: bool) {
+        let mut settings = ChannelSettings::default();
+        if preserve_calibration {
+            settings.input_power_transform = self.data.settings.input_power_transform;
+            settings.output_power_transform = self.data.settings.output_power_transform;
+            settings.reflected_power_transform = self.data.settings.reflected_power_transform;
+        }
+
+        self.data.settings = settings;
+        self.save();
     }
 
     /// Mutably borrow the channel settings.
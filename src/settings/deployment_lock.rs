@@ -0,0 +1,68 @@
+//! Booster deployment lock
+//!
+//! # Design
+//! Production installations may be locked to protect them from accidental reconfiguration. While
+//! locked, all state-changing commands are rejected until the device is unlocked with the
+//! configured lock key.
+//!
+//! The lock state is retained in an RTC backup register rather than in the flash settings, since
+//! erasing the flash settings sector stalls the CPU for more than a second. The lock state is thus
+//! retained across resets and, if a backup battery is populated, across power cycles.
+use crate::hardware::platform;
+use core::cell::RefCell;
+use core::sync::atomic::{AtomicBool, Ordering};
+use cortex_m::interrupt::Mutex;
+use heapless::String;
+
+/// The backup register that stores the lock state.
+const LOCK_REGISTER: usize = 3;
+
+/// The value of the lock register while the device is locked.
+const LOCK_MARKER: u32 = 0x4C4F_434B;
+
+static LOCKED: AtomicBool = AtomicBool::new(false);
+
+static KEY: Mutex<RefCell<String<32>>> = Mutex::new(RefCell::new(String::new()));
+
+/// Initialize the deployment lock from the retained lock state.
+///
+/// # Args
+/// * `key` - The configured lock key. The device cannot be locked or unlocked if empty.
+pub fn init(key: &str) {
+    cortex_m::interrupt::free(|cs| *KEY.borrow(cs).borrow_mut() = String::from(key));
+    LOCKED.store(
+        platform::read_backup(LOCK_REGISTER) == LOCK_MARKER,
+        Ordering::Relaxed,
+    );
+}
+
+/// Check if the device is locked.
+pub fn is_locked() -> bool {
+    LOCKED.load(Ordering::Relaxed)
+}
+
+/// Lock or unlock the device and retain the lock state.
+///
+/// # Args
+/// * `key` - The lock key provided with the request, which must match the configured key.
+/// * `locked` - Specified true if state-changing commands should be rejected.
+pub fn set_locked(key: &str, locked: bool) -> Result<(), &'static str> {
+    cortex_m::interrupt::free(|cs| {
+        let configured = KEY.borrow(cs).borrow();
+        if configured.is_empty() {
+            return Err("No lock key configured");
+        }
+
+        if configured.as_str() != key {
+            log::warn!("Rejected invalid lock key");
+            return Err("Invalid lock key");
+        }
+
+        Ok(())
+    })?;
+
+    platform::enable_backup_access();
+    platform::write_backup(LOCK_REGISTER, if locked { LOCK_MARKER } else { 0 });
+    LOCKED.store(locked, Ordering::Relaxed);
+    Ok(())
+}
@@ -0,0 +1,68 @@
+//! Booster factory reset confirmation
+//!
+//! # Design
+//! Restoring factory defaults erases all device and channel configuration, so it requires two
+//! steps. A reset is first armed, which returns a confirmation code. The reset is then only
+//! performed if the code is provided back within a short timeout.
+use core::cell::Cell;
+use cortex_m::interrupt::Mutex;
+
+/// The time in milliseconds an armed factory reset remains valid for confirmation.
+const CONFIRMATION_TIMEOUT_MS: u64 = 10_000;
+
+#[derive(Copy, Clone)]
+struct PendingReset {
+    code: u32,
+    preserve_calibration: bool,
+    deadline: u64,
+}
+
+static PENDING: Mutex<Cell<Option<PendingReset>>> = Mutex::new(Cell::new(None));
+
+fn now() -> u64 {
+    crate::app::monotonics::now().ticks()
+}
+
+/// Arm a factory reset.
+///
+/// # Args
+/// * `preserve_calibration` - Specified true if channel calibration transforms should be kept.
+///
+/// # Returns
+/// The code that must be provided to confirm the reset.
+pub fn arm(preserve_calibration: bool) -> u32 {
+    let now = now();
+
+    // The code only guards against accidental confirmation, so it need not be unpredictable.
+    let code = ((now as u32).wrapping_mul(2_654_435_761) % 900_000) + 100_000;
+
+    cortex_m::interrupt::free(|cs| {
+        PENDING.borrow(cs).set(Some(PendingReset {
+            code,
+            preserve_calibration,
+            deadline: now + CONFIRMATION_TIMEOUT_MS,
+        }))
+    });
+
+    code
+}
+
+/// Confirm an armed factory reset.
+///
+/// # Args
+/// * `code` - The confirmation code returned when the reset was armed. If `None`, any armed reset
+///   is confirmed.
+///
+/// # Returns
+/// Whether or not channel calibration transforms should be preserved if the reset was confirmed.
+pub fn confirm(code: Option<u32>) -> Result<bool, &'static str> {
+    let pending = cortex_m::interrupt::free(|cs| PENDING.borrow(cs).take())
+        .filter(|pending| now() <= pending.deadline)
+        .ok_or("No factory reset pending")?;
+
+    if code.map(|code| code != pending.code).unwrap_or(false) {
+        return Err("Invalid confirmation code");
+    }
+
+    Ok(pending.preserve_calibration)
+}
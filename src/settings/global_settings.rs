@@ -18,7 +18,8 @@
 //! bytes, but a domain name can be up to 255 characters.
 
 use crate::{
-    hardware::{flash::Flash, Eeprom},
+    hardware::{flash::Flash, Channel, Eeprom},
+    logger::LogLevel,
     Error,
 };
 use core::str::FromStr;
@@ -36,6 +37,12 @@ use core::fmt::Write;
 use miniconf::Tree;
 use serde_with::DeserializeFromStr;
 
+/// The default delay between the automatic power-up of consecutive channels in milliseconds.
+const DEFAULT_STARTUP_DELAY_MS: u32 = 500;
+
+/// The default TCP port of the MQTT broker.
+const DEFAULT_BROKER_PORT: u16 = 1883;
+
 /// The expected semver of the BoosterChannelSettings. This version must be updated whenever the
 /// `BoosterMainBoardData` layout is updated.
 const EXPECTED_VERSION: SemVersion = SemVersion {
@@ -102,6 +109,38 @@ impl encdec::DecodeOwned for IpAddr {
     }
 }
 
+/// A user-configured ethernet MAC address.
+#[derive(DeserializeFromStr, Copy, Clone, Debug, PartialEq)]
+pub struct MacAddress(pub smoltcp::wire::EthernetAddress);
+
+impl MacAddress {
+    /// The unspecified address, which indicates that no address is configured.
+    pub const UNSPECIFIED: Self = Self(smoltcp::wire::EthernetAddress([0; 6]));
+}
+
+impl Serialize for MacAddress {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut display: String<17> = String::new();
+        write!(&mut display, "{}", self.0).unwrap();
+        serializer.serialize_str(&display)
+    }
+}
+
+impl core::str::FromStr for MacAddress {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let addr = smoltcp::wire::EthernetAddress::from_str(s).map_err(|_| "Invalid MAC format")?;
+
+        // The unspecified address is accepted to clear a configured address.
+        if addr != Self::UNSPECIFIED.0 && !addr.is_unicast() {
+            return Err("MAC address must be unicast");
+        }
+
+        Ok(Self(addr))
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(transparent)]
 pub struct MqttIdentifier(pub String<23>);
@@ -182,6 +221,58 @@ impl SerializedMainBoardData {
             netmask: self.netmask,
             id: self.id.0,
             fan_speed: self.fan_speed,
+            dns: IpAddr::new(&[0, 0, 0, 0]),
+            secondary_dns: IpAddr::new(&[0, 0, 0, 0]),
+            control_token: String::new(),
+            broker_port: DEFAULT_BROKER_PORT,
+            mac_override: MacAddress::UNSPECIFIED,
+            lock_key: String::new(),
+            usb_log_level: LogLevel::Info,
+            locked: false,
+            startup_delay_ms: DEFAULT_STARTUP_DELAY_MS,
+            restore_after_fault: true,
+            lock_swapped_modules: false,
+            protect_mqtt_settings: false,
+        }
+    }
+}
+
+/// The layout of settings stored in flash by firmware prior to the addition of DNS servers, the
+/// control access token, and the broker port.
+#[derive(Deserialize)]
+struct LegacyMainBoardData {
+    version: SemVersion,
+    ip: IpAddr,
+    broker: heapless::String<255>,
+    gateway: IpAddr,
+    netmask: IpAddr,
+    id: heapless::String<23>,
+    fan_speed: f32,
+}
+
+impl LegacyMainBoardData {
+    fn with_mac(self, mac: smoltcp_nal::smoltcp::wire::EthernetAddress) -> BoosterMainBoardData {
+        BoosterMainBoardData {
+            mac,
+            version: self.version,
+            ip: self.ip,
+            broker: self.broker,
+            gateway: self.gateway,
+            netmask: self.netmask,
+            id: self.id,
+            fan_speed: self.fan_speed,
+            dns: IpAddr::new(&[0, 0, 0, 0]),
+            secondary_dns: IpAddr::new(&[0, 0, 0, 0]),
+            control_token: String::new(),
+            broker_port: DEFAULT_BROKER_PORT,
+            mac_override: MacAddress::UNSPECIFIED,
+            lock_key: String::new(),
+            usb_log_level: LogLevel::Info,
+            locked: false,
+            startup_delay_ms: DEFAULT_STARTUP_DELAY_MS,
+            restore_after_fault: true,
+            lock_swapped_modules: false,
+            protect_mqtt_settings: false,
         }
     }
 }
@@ -202,6 +293,51 @@ pub struct BoosterMainBoardData {
     pub netmask: IpAddr,
     pub id: heapless::String<23>,
     pub fan_speed: f32,
+
+    /// The primary DNS server. Only used when a static IP address is configured. With DHCP, the DNS
+    /// servers of the lease replace the configured servers, even if the lease provides none.
+    pub dns: IpAddr,
+
+    /// The secondary DNS server.
+    pub secondary_dns: IpAddr,
+
+    /// A shared secret that must be provided with state-changing control requests. Requests are
+    /// not checked if empty.
+    pub control_token: heapless::String<32>,
+
+    /// The TCP port of the MQTT broker.
+    pub broker_port: u16,
+
+    /// A MAC address that overrides the EUI-48 of the mainboard. The EUI-48 is used if
+    /// unspecified.
+    pub mac_override: MacAddress,
+
+    /// The key required to lock and unlock the device. The device cannot be locked if empty.
+    pub lock_key: heapless::String<32>,
+
+    /// Unused. Retained for compatibility of the stored settings, as the lock state is now held in
+    /// an RTC backup register.
+    #[tree(skip)]
+    pub locked: bool,
+
+    /// The maximum level of log messages written to the USB serial port.
+    pub usb_log_level: LogLevel,
+
+    /// The delay between the automatic power-up of consecutive channels at boot in milliseconds.
+    pub startup_delay_ms: u32,
+
+    /// Specified true if enabled channels are powered up automatically after a watchdog or panic
+    /// reset. Otherwise, channels remain off until the "Interlock Reset" button is pressed.
+    pub restore_after_fault: bool,
+
+    /// Specified true if channels whose RF module was moved or replaced remain off until the
+    /// lockout is released with the "Interlock Reset" button.
+    pub lock_swapped_modules: bool,
+
+    /// Specified true to reject run-time settings updates via the MQTT settings topics while a
+    /// `control_token` is configured, as they cannot carry the token. Otherwise, such updates are
+    /// accepted without a token.
+    pub protect_mqtt_settings: bool,
 }
 
 impl serial_settings::Settings for BoosterMainBoardData {
@@ -236,6 +372,18 @@ impl BoosterMainBoardData {
             netmask: IpAddr::new(&[0, 0, 0, 0]),
             id: name,
             fan_speed: DEFAULT_FAN_SPEED,
+            dns: IpAddr::new(&[0, 0, 0, 0]),
+            secondary_dns: IpAddr::new(&[0, 0, 0, 0]),
+            control_token: String::new(),
+            broker_port: DEFAULT_BROKER_PORT,
+            mac_override: MacAddress::UNSPECIFIED,
+            lock_key: String::new(),
+            usb_log_level: LogLevel::Info,
+            locked: false,
+            startup_delay_ms: DEFAULT_STARTUP_DELAY_MS,
+            restore_after_fault: true,
+            lock_swapped_modules: false,
+            protect_mqtt_settings: false,
         }
     }
 
@@ -243,8 +391,16 @@ impl BoosterMainBoardData {
     pub fn reload(&mut self, storage: &mut Flash) {
         let mut buffer = [0u8; 512];
         storage.read(0, &mut buffer).unwrap();
-        let Ok(mut settings) = postcard::from_bytes::<Self>(&buffer) else {
-            return;
+        let mut settings = match postcard::from_bytes::<Self>(&buffer) {
+            Ok(settings) => settings,
+            Err(_) => {
+                // Settings saved by older firmware do not contain DNS servers, a control token, or a
+                // broker port.
+                let Ok(legacy) = postcard::from_bytes::<LegacyMainBoardData>(&buffer) else {
+                    return;
+                };
+                legacy.with_mac(self.mac)
+            }
         };
 
         settings.mac = self.mac;
@@ -253,6 +409,12 @@ impl BoosterMainBoardData {
         log::info!("Loaded settings from Flash");
     }
 
+    /// Get the CRC32 of the settings as serialized into flash.
+    pub fn crc32(&self) -> u32 {
+        let mut buffer = [0u8; 512];
+        postcard::to_slice(self, &mut buffer).map_or(0, |serialized| super::crc32(serialized))
+    }
+
     /// Construct booster configuration data from serialized `board_data` from a
     /// SinaraConfiguration.
     ///
@@ -339,12 +501,241 @@ impl BoosterMainBoardData {
 
         smoltcp::wire::IpCidr::new(smoltcp::wire::IpAddress::Ipv4(ip_addr), prefix)
     }
+
+    /// Get the ethernet MAC address of the device.
+    ///
+    /// # Returns
+    /// The configured MAC address override if specified. Otherwise, the mainboard EUI-48.
+    pub fn mac_address(&self) -> smoltcp::wire::EthernetAddress {
+        if self.mac_override == MacAddress::UNSPECIFIED {
+            self.mac
+        } else {
+            self.mac_override.0
+        }
+    }
+
+    /// Get the configured DNS servers.
+    ///
+    /// # Note
+    /// Unspecified server addresses are omitted.
+    pub fn dns_servers(&self) -> heapless::Vec<smoltcp::wire::IpAddress, 2> {
+        [self.dns, self.secondary_dns]
+            .iter()
+            .filter(|server| !server.0.is_unspecified())
+            .map(|server| smoltcp::wire::IpAddress::Ipv4(server.0))
+            .collect()
+    }
+}
+
+/// Network configuration exposed via the run-time settings interface.
+///
+/// # Note
+/// Changes remain pending until they are applied to the mainboard settings and the device is
+/// rebooted.
+#[derive(Debug, Clone, Tree, Serialize, Deserialize)]
+pub struct NetworkSettings {
+    /// Specified true if the IP address should be acquired via DHCP.
+    pub dhcp: bool,
+    pub ip: IpAddr,
+    pub gateway: IpAddr,
+    pub netmask: IpAddr,
+    pub broker: heapless::String<255>,
+    pub broker_port: u16,
+}
+
+impl From<&BoosterMainBoardData> for NetworkSettings {
+    fn from(properties: &BoosterMainBoardData) -> Self {
+        Self {
+            dhcp: properties.ip.0.is_unspecified(),
+            ip: properties.ip,
+            gateway: properties.gateway,
+            netmask: properties.netmask,
+            broker: properties.broker.clone(),
+            broker_port: properties.broker_port,
+        }
+    }
+}
+
+impl NetworkSettings {
+    /// Check that the network settings are valid.
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if smoltcp::wire::IpAddress::Ipv4(self.netmask.0)
+            .prefix_len()
+            .is_none()
+        {
+            return Err("Netmasks must contain no trailing bits");
+        }
+
+        if !self.dhcp && self.ip.0.is_unspecified() {
+            return Err("An IP address is required without DHCP");
+        }
+
+        Ok(())
+    }
+
+    /// Apply the network settings to the mainboard settings.
+    ///
+    /// # Args
+    /// * `properties` - The mainboard settings to update.
+    pub fn apply(&self, properties: &mut BoosterMainBoardData) {
+        properties.ip = if self.dhcp {
+            IpAddr::new(&[0, 0, 0, 0])
+        } else {
+            self.ip
+        };
+        properties.gateway = self.gateway;
+        properties.netmask = self.netmask;
+        properties.broker = self.broker.clone();
+        properties.broker_port = self.broker_port;
+    }
+}
+
+/// The offset of the operating hour counters within the mainboard EEPROM board data.
+///
+/// # Note
+/// The counters are located after the serialized [SerializedMainBoardData], which occupies the
+/// first 50 bytes of the board data.
+const OPERATING_HOURS_OFFSET: usize = 50;
+
+/// A marker indicating that the operating hour counters have been initialized.
+const OPERATING_HOURS_MARKER: [u8; 2] = *b"OH";
+
+/// Lifetime operating hour counters of the device, which are stored in mainboard EEPROM.
+#[derive(Copy, Clone, Debug, Default, Serialize)]
+pub struct OperatingHours {
+    /// The number of hours that the device has been powered on.
+    pub device: u32,
+
+    /// The number of hours that the chassis fans have been running.
+    pub fan: u32,
+
+    /// The fan operating hours when the fans were last serviced.
+    pub fan_serviced: u32,
+}
+
+impl OperatingHours {
+    /// Get the number of fan operating hours since the fans were last serviced.
+    pub fn fan_since_service(&self) -> u32 {
+        self.fan.saturating_sub(self.fan_serviced)
+    }
+
+    /// Load the operating hour counters from the mainboard board data.
+    ///
+    /// # Note
+    /// Counters are reset if they were never initialized (e.g. on devices upgraded from earlier
+    /// firmware).
+    fn deserialize(data: &[u8; 64]) -> Self {
+        let data = &data[OPERATING_HOURS_OFFSET..];
+        if data[..2] != OPERATING_HOURS_MARKER {
+            log::info!("Initializing operating hour counters");
+            return Self::default();
+        }
+
+        let counter = |idx: usize| {
+            let offset = 2 + idx * 4;
+            u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+        };
+
+        Self {
+            device: counter(0),
+            fan: counter(1),
+            fan_serviced: counter(2),
+        }
+    }
+
+    /// Serialize the operating hour counters into the mainboard board data.
+    fn serialize_into(&self, config: &mut SinaraConfiguration) {
+        let data = &mut config.board_data[OPERATING_HOURS_OFFSET..];
+        data[..2].copy_from_slice(&OPERATING_HOURS_MARKER);
+        for (idx, counter) in [self.device, self.fan, self.fan_serviced]
+            .iter()
+            .enumerate()
+        {
+            let offset = 2 + idx * 4;
+            data[offset..offset + 4].copy_from_slice(&counter.to_le_bytes());
+        }
+    }
+}
+
+/// A change of the RF module installed in a channel since the module was last recorded.
+#[derive(Copy, Clone, Debug, Serialize)]
+pub enum ModuleChange {
+    /// The module was previously installed in another channel.
+    Moved(Channel),
+
+    /// A module that was not previously installed in any channel replaced the recorded module.
+    Replaced,
+}
+
+/// Fingerprints of the EUI-48 of the RF module last recorded in each channel, which are stored in
+/// the user data of the mainboard EEPROM.
+///
+/// # Note
+/// Each fingerprint is the lower 16 bits of the CRC32 of the EUI-48. A fingerprint of zero
+/// indicates that no module is recorded.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ModuleFingerprints([u16; 8]);
+
+impl ModuleFingerprints {
+    /// Calculate the fingerprint of an RF module.
+    fn fingerprint(eui48: &[u8; 6]) -> u16 {
+        (super::crc32(eui48) as u16).max(1)
+    }
+
+    /// Load the fingerprints from the mainboard user data.
+    fn deserialize(data: &[u8; 16]) -> Self {
+        Self(core::array::from_fn(|idx| {
+            u16::from_le_bytes([data[2 * idx], data[2 * idx + 1]])
+        }))
+    }
+
+    /// Serialize the fingerprints into the mainboard user data.
+    fn serialize_into(&self, config: &mut SinaraConfiguration) {
+        for (idx, fingerprint) in self.0.iter().enumerate() {
+            config.user_data[2 * idx..][..2].copy_from_slice(&fingerprint.to_le_bytes());
+        }
+    }
+
+    /// Record the RF module installed in a channel.
+    ///
+    /// # Args
+    /// * `channel` - The channel to record the module of.
+    /// * `eui48` - The EUI-48 of the installed module, or None if no module is installed.
+    pub fn record(&mut self, channel: Channel, eui48: Option<&[u8; 6]>) {
+        self.0[channel as usize] = eui48.map_or(0, Self::fingerprint);
+    }
+
+    /// Check if the RF module installed in a channel differs from the recorded module.
+    ///
+    /// # Args
+    /// * `channel` - The channel to check.
+    /// * `eui48` - The EUI-48 of the installed module.
+    ///
+    /// # Returns
+    /// The change of the module, if any. Modules in channels without a recorded module are not
+    /// reported.
+    pub fn detect_change(&self, channel: Channel, eui48: &[u8; 6]) -> Option<ModuleChange> {
+        let fingerprint = Self::fingerprint(eui48);
+        let recorded = self.0[channel as usize];
+        if recorded == 0 || recorded == fingerprint {
+            return None;
+        }
+
+        let change = enum_iterator::all::<Channel>()
+            .find(|&other| other != channel && self.0[other as usize] == fingerprint)
+            .map_or(ModuleChange::Replaced, ModuleChange::Moved);
+        Some(change)
+    }
 }
 
 /// Booster device-wide configurable settings.
 pub struct BoosterSettings {
     pub properties: BoosterMainBoardData,
+    pub operating_hours: OperatingHours,
+    pub modules: ModuleFingerprints,
     eeprom: Eeprom,
+    // True if the EEPROM contained a valid configuration at startup.
+    eeprom_valid: bool,
 }
 
 impl BoosterSettings {
@@ -357,13 +748,29 @@ impl BoosterSettings {
         eeprom.read_eui48(&mut mac).unwrap();
 
         // Load the sinara configuration from EEPROM.
-        let (board_data, write_back) = Self::load_config(&mut eeprom)
+        let config = Self::load_config(&mut eeprom);
+        let eeprom_valid = config.is_ok();
+
+        let operating_hours = config
+            .as_ref()
+            .map(|config| OperatingHours::deserialize(&config.board_data))
+            .unwrap_or_default();
+
+        let modules = config
+            .as_ref()
+            .map(|config| ModuleFingerprints::deserialize(&config.user_data))
+            .unwrap_or_default();
+
+        let (board_data, write_back) = config
             .and_then(|config| BoosterMainBoardData::deserialize(&mac, &config.board_data))
             .unwrap_or((BoosterMainBoardData::default(&mac), true));
 
         let mut settings = Self {
             properties: board_data,
+            operating_hours,
+            modules,
             eeprom,
+            eeprom_valid,
         };
 
         if write_back {
@@ -373,6 +780,11 @@ impl BoosterSettings {
         settings
     }
 
+    /// Check if the EEPROM contained a valid configuration (i.e. with a valid CRC) at startup.
+    pub fn eeprom_valid(&self) -> bool {
+        self.eeprom_valid
+    }
+
     /// Save the configuration settings to EEPROM for retrieval.
     pub fn save(&mut self) {
         let mut config = match Self::load_config(&mut self.eeprom) {
@@ -382,6 +794,44 @@ impl BoosterSettings {
 
         let board_data: BoosterMainBoardData = self.properties.clone();
         board_data.serialize_into(&mut config);
+        self.operating_hours.serialize_into(&mut config);
+        self.modules.serialize_into(&mut config);
+        config.update_crc32();
+        self.save_config(&config);
+    }
+
+    /// Save the recorded RF modules to EEPROM.
+    ///
+    /// # Note
+    /// The remaining mainboard configuration stored in EEPROM is left unmodified.
+    pub fn save_modules(&mut self) {
+        let mut config = match Self::load_config(&mut self.eeprom) {
+            Err(_) => {
+                log::warn!("Mainboard EEPROM configuration invalid. RF modules not recorded");
+                return;
+            }
+            Ok(config) => config,
+        };
+
+        self.modules.serialize_into(&mut config);
+        config.update_crc32();
+        self.save_config(&config);
+    }
+
+    /// Save the operating hour counters to EEPROM.
+    ///
+    /// # Note
+    /// The remaining mainboard configuration stored in EEPROM is left unmodified.
+    pub fn save_operating_hours(&mut self) {
+        let mut config = match Self::load_config(&mut self.eeprom) {
+            Err(_) => {
+                log::warn!("Mainboard EEPROM configuration invalid. Operating hours not saved");
+                return;
+            }
+            Ok(config) => config,
+        };
+
+        self.operating_hours.serialize_into(&mut config);
         config.update_crc32();
         self.save_config(&config);
     }
@@ -1,7 +1,10 @@
 //! Booster NGFW NVM settings
 
 pub mod channel_settings;
+pub mod deployment_lock;
+pub mod factory_reset;
 pub mod global_settings;
+pub mod rate_limit;
 pub mod runtime_settings;
 mod sinara;
 use encdec::{Decode, DecodeOwned, Encode};
@@ -12,6 +15,16 @@ use sinara::{BoardId as SinaraBoardId, SinaraConfiguration};
 pub use channel_settings::BoosterChannelSettings;
 pub use global_settings::BoosterSettings;
 
+/// Calculate the CRC32 of serialized settings.
+///
+/// # Args
+/// * `data` - The serialized settings.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc32 = crc_any::CRC::crc32();
+    crc32.digest(data);
+    crc32.get_crc() as u32
+}
+
 /// A semantic version control for recording software versions.
 #[derive(Encode, DecodeOwned, Serialize, Deserialize, Debug, PartialEq, Copy, Clone)]
 pub struct SemVersion {
@@ -0,0 +1,55 @@
+//! Booster channel state change rate limiting
+//!
+//! # Design
+//! Rapidly cycling channels on and off, e.g. by a faulty host script, stresses the RF amplifiers
+//! and the channel supplies. Each request to power up or enable a channel via the network consumes
+//! a token of a per-channel bucket. Turning a channel off or disabling its output never consumes a
+//! token, so that a channel can always be shut down. A token is restored every [REFILL_MS] up to
+//! [BURST] tokens, so that occasional bursts of changes are accepted while sustained cycling is
+//! throttled.
+use core::cell::Cell;
+use cortex_m::interrupt::Mutex;
+
+/// The number of state changes of a channel accepted in a burst.
+const BURST: u64 = 4;
+
+/// The time in milliseconds after which a state change token is restored.
+const REFILL_MS: u64 = 5_000;
+
+/// The time at which the bucket of each channel is full again.
+static FULL_AT: Mutex<Cell<[u64; 8]>> = Mutex::new(Cell::new([0; 8]));
+
+/// Request channels to be powered up or enabled.
+///
+/// # Args
+/// * `channels` - A bit mask of the channels to power up or enable. Bit 0 corresponds to channel 0.
+///
+/// # Returns
+/// Ok if the changes are permitted. Otherwise, no change is recorded and an error is returned.
+pub fn request(channels: u8) -> Result<(), &'static str> {
+    let now = crate::app::monotonics::now().ticks();
+
+    cortex_m::interrupt::free(|cs| {
+        let cell = FULL_AT.borrow(cs);
+        let mut full_at = cell.get();
+
+        for (idx, full_at) in full_at.iter_mut().enumerate() {
+            if channels & (1 << idx) == 0 {
+                continue;
+            }
+
+            // The bucket is empty if it would not be full again within the time to restore all
+            // other tokens.
+            let start = (*full_at).max(now);
+            if start - now > (BURST - 1) * REFILL_MS {
+                log::warn!("Throttled state change of channel {}", idx);
+                return Err("Channel state changed too frequently, retry later");
+            }
+
+            *full_at = start + REFILL_MS;
+        }
+
+        cell.set(full_at);
+        Ok(())
+    })
+}
@@ -1,11 +1,29 @@
 //! Booster NGFW runtime settings
 
-use super::channel_settings::ChannelSettings;
+use super::{
+    channel_settings::{ChannelSettings, ChannelState},
+    global_settings::{BoosterMainBoardData, IpAddr, NetworkSettings},
+};
 use crate::{
-    hardware::{self, platform, Channel},
-    net,
+    hardware::{
+        self,
+        filter::MeasurementFilter,
+        gate::Gate,
+        platform,
+        rf_channel::{AdcSampleTime, TemperatureSource, ThermalDerating, WarningThresholds},
+        Channel,
+    },
+    logger::LogLevel,
+    net::{
+        self,
+        mqtt_control::{PublicationSettings, TelemetryLayout},
+    },
+    protection::{LoadShedding, ThermalFoldback},
+    scheduler::Schedule,
+    telemetry::{PowerUnit, Precision},
 };
 use miniconf::Tree;
+use smoltcp_nal::smoltcp;
 
 #[derive(Clone, Tree)]
 pub struct RuntimeSettings {
@@ -16,21 +34,158 @@ pub struct RuntimeSettings {
     /// off.
     pub fan_speed: f32,
 
+    /// The number of fan operating hours after which fan service is reported as due. Maintenance
+    /// reminders are disabled if zero.
+    pub fan_service_hours: u32,
+
     /// The configured telemetry period in seconds.
     pub telemetry_period: u64,
+
+    /// The layout of published telemetry messages.
+    pub telemetry_layout: TelemetryLayout,
+
+    /// The unit of power measurements in published telemetry.
+    pub power_unit: PowerUnit,
+
+    /// The number of decimal places of measurements in published telemetry.
+    #[tree(depth(1))]
+    pub precision: Precision,
+
+    /// The MQTT quality of service and retention of telemetry, event, and metadata publications.
+    #[tree(depth(2))]
+    pub publication: PublicationSettings,
+
+    /// The temperature measurement used for channel thermal protection.
+    pub temperature_source: TemperatureSource,
+
+    /// The ADC sample time of output and reflected power detector measurements.
+    pub adc_sample_time: AdcSampleTime,
+
+    /// The time constants of the channel measurement filters.
+    #[tree(depth(1))]
+    pub filter: MeasurementFilter,
+
+    /// The thresholds of channel measurements above which warnings are reported in telemetry.
+    #[tree(depth(1))]
+    pub warning: WarningThresholds,
+
+    /// The thermal derating curve of the output interlock threshold of each channel.
+    #[tree(depth(2))]
+    pub derating: [ThermalDerating; 8],
+
+    /// The channels that are tripped when the interlock of each channel trips, as a bit mask where
+    /// bit 0 corresponds to channel 0.
+    #[tree(depth(1))]
+    pub dependents: [u8; 8],
+
+    /// The priority of each channel for chassis-level protection. Channels with lower priority are
+    /// disabled first.
+    #[tree(depth(1))]
+    pub priority: [u8; 8],
+
+    /// The chassis thermal foldback configuration.
+    #[tree(depth(1))]
+    pub foldback: ThermalFoldback,
+
+    /// The input supply load shedding configuration.
+    #[tree(depth(1))]
+    pub load_shedding: LoadShedding,
+
+    /// The daily schedule of each channel.
+    #[tree(depth(2))]
+    pub schedule: [Schedule; 8],
+
+    /// The hardware gate input configuration.
+    #[tree(depth(1))]
+    pub gate: Gate,
+
+    /// The channels monitored by the status output. The status output is only asserted while all
+    /// selected channels are enabled. Bit 0 corresponds to channel 0.
+    pub status_channels: u8,
+
+    /// The address of the remote syslog server. Logs are not forwarded if unspecified.
+    pub syslog_server: IpAddr,
+
+    /// The maximum level of log messages forwarded to the syslog server.
+    pub syslog_level: LogLevel,
+
+    /// The number of seconds that modified channel settings must remain unchanged before they are
+    /// saved to EEPROM automatically. Automatic saving is disabled if zero.
+    pub auto_save_delay: u32,
+
+    /// The number of minutes the MQTT broker may remain unreachable before the watchdog is no
+    /// longer fed, resetting the device. The network liveness criterion is disabled if zero.
+    pub network_watchdog: u32,
+
+    /// The network configuration. Changes are pending until applied with the `apply-network`
+    /// command.
+    #[tree(depth(1))]
+    pub network: NetworkSettings,
 }
 
-impl Default for RuntimeSettings {
-    fn default() -> Self {
+impl RuntimeSettings {
+    /// Construct the default run-time settings.
+    ///
+    /// # Args
+    /// * `properties` - The mainboard settings that the network configuration is sourced from.
+    pub fn new(properties: &BoosterMainBoardData) -> Self {
         Self {
             channel: [None; 8],
             fan_speed: hardware::chassis_fans::DEFAULT_FAN_SPEED,
+            fan_service_hours: hardware::chassis_fans::DEFAULT_FAN_SERVICE_HOURS,
             telemetry_period: net::mqtt_control::DEFAULT_TELEMETRY_PERIOD_SECS,
+            telemetry_layout: TelemetryLayout::PerChannel,
+            power_unit: PowerUnit::Dbm,
+            precision: Precision::default(),
+            publication: PublicationSettings::default(),
+            temperature_source: TemperatureSource::Remote,
+            adc_sample_time: AdcSampleTime::Cycles480,
+            filter: MeasurementFilter::default(),
+            warning: WarningThresholds::default(),
+            derating: [ThermalDerating::default(); 8],
+            dependents: [0; 8],
+            priority: [0; 8],
+            foldback: ThermalFoldback::default(),
+            load_shedding: LoadShedding::default(),
+            schedule: [Schedule::default(); 8],
+            gate: Gate::default(),
+            status_channels: 0,
+            syslog_server: IpAddr(smoltcp::wire::Ipv4Address::UNSPECIFIED),
+            syslog_level: LogLevel::Info,
+            auto_save_delay: 0,
+            network_watchdog: 0,
+            network: properties.into(),
         }
     }
-}
 
-impl RuntimeSettings {
+    /// Copy the configuration of a channel to other channels.
+    ///
+    /// # Args
+    /// * `source` - The channel to copy the configuration from.
+    /// * `destination` - The channel to copy the configuration to. If `None`, the configuration is
+    ///   copied to all other installed channels.
+    pub fn clone_channel(
+        &mut self,
+        source: Channel,
+        destination: Option<Channel>,
+    ) -> Result<(), &'static str> {
+        let source_settings = self.channel[source as usize].ok_or("Source channel not found")?;
+
+        if let Some(destination) = destination {
+            self.channel[destination as usize]
+                .as_mut()
+                .ok_or("Destination channel not found")?
+                .copy_configuration(&source_settings);
+            return Ok(());
+        }
+
+        for settings in self.channel.iter_mut().flatten() {
+            settings.copy_configuration(&source_settings);
+        }
+
+        Ok(())
+    }
+
     pub fn handle_update(
         _: &str,
         settings: &mut Self,
@@ -52,17 +207,59 @@ impl RuntimeSettings {
                 // configurable on the DAC.
                 let output_interlock_voltage = settings
                     .output_power_transform
-                    .invert(settings.output_interlock_threshold);
+                    .apply_inverse(settings.output_interlock_threshold);
                 if !(0.00..=ad5627::MAX_VOLTAGE).contains(&output_interlock_voltage) {
                     return Err("Output interlock threshold voltage out of range");
                 }
             }
         }
 
+        if !new_settings.filter.is_valid() {
+            return Err("Invalid filter time constant");
+        }
+
+        for derating in new_settings.derating.iter() {
+            if !derating.start_temperature.is_finite() || !(0.0..=10.0).contains(&derating.slope) {
+                return Err("Invalid thermal derating");
+            }
+        }
+
+        if !(new_settings.foldback.power_limit.is_finite()
+            && new_settings.foldback.power_limit > 0.0)
+        {
+            return Err("Invalid foldback power limit");
+        }
+
+        if !new_settings.schedule.iter().all(Schedule::is_valid) {
+            return Err("Invalid schedule");
+        }
+
+        let current_limit = new_settings.load_shedding.current_limit;
+        if !(current_limit.is_finite() && current_limit > 0.0) {
+            return Err("Invalid load shedding current limit");
+        }
+
         if !(0.0..=1.0).contains(&new_settings.fan_speed) {
             return Err("Invalid fan speed");
         }
 
+        new_settings.network.validate()?;
+
+        // Powering up and enabling channels is rate limited per channel to protect the RF
+        // amplifiers and supplies. Turning channels off or disabling the output is always accepted.
+        let state_changes = enum_iterator::all::<Channel>()
+            .filter(|&idx| {
+                matches!(
+                    (settings.channel[idx as usize], new_settings.channel[idx as usize]),
+                    (Some(old), Some(new)) if old.state != new.state
+                        && (old.state == ChannelState::Off || new.state == ChannelState::Enabled)
+                )
+            })
+            .fold(0u8, |mask, idx| mask | 1 << idx as u8);
+        if state_changes != 0 {
+            super::rate_limit::request(state_changes)?;
+        }
+
         *settings = new_settings.clone();
         Ok(())
     }
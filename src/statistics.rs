@@ -0,0 +1,97 @@
+//! Booster NGFW event statistics
+//!
+//! # Design
+//! Notable events since startup are counted in atomics, such that they can be recorded from any
+//! context without locking. The counters are reported via the `stats` control command and
+//! periodically on `<prefix>/telemetry/stats` for fleet health monitoring.
+use crate::hardware::rf_channel::Interlock;
+use core::sync::atomic::{AtomicU32, Ordering};
+use serde::Serialize;
+
+/// The counted events.
+#[derive(Copy, Clone, Debug)]
+pub enum Event {
+    /// A control request was processed.
+    Command,
+
+    /// A control request failed.
+    CommandError,
+
+    /// The MQTT telemetry client reconnected to the broker.
+    MqttReconnect,
+
+    /// A write of a bias DAC failed or could not be verified, and was repeated or abandoned.
+    I2cRetry,
+
+    /// The devices of an RF channel could not be read.
+    I2cError,
+}
+
+const ZERO: AtomicU32 = AtomicU32::new(0);
+
+static EVENTS: [AtomicU32; 5] = [ZERO; 5];
+
+static TRIPS: [AtomicU32; 4] = [ZERO; 4];
+
+/// Record the occurrence of an event.
+pub fn record(event: Event) {
+    EVENTS[event as usize].fetch_add(1, Ordering::Relaxed);
+}
+
+/// Record a processed control request.
+///
+/// # Args
+/// * `success` - Specified true if the request succeeded.
+pub fn record_command(success: bool) {
+    record(Event::Command);
+    if !success {
+        record(Event::CommandError);
+    }
+}
+
+/// Record a tripped interlock.
+pub fn record_trip(interlock: Interlock) {
+    TRIPS[interlock as usize].fetch_add(1, Ordering::Relaxed);
+}
+
+/// The number of interlock trips of all channels per cause.
+#[derive(Serialize, Copy, Clone, Debug)]
+pub struct InterlockTrips {
+    pub input: u32,
+    pub output: u32,
+    pub reflected: u32,
+    pub chained: u32,
+}
+
+/// The event counters since startup.
+#[derive(Serialize, Copy, Clone, Debug)]
+pub struct Statistics {
+    pub commands: u32,
+    pub command_errors: u32,
+    pub mqtt_reconnects: u32,
+    pub i2c_retries: u32,
+    pub i2c_errors: u32,
+    pub interlock_trips: InterlockTrips,
+}
+
+impl Statistics {
+    /// Read the current event counters.
+    pub fn read() -> Self {
+        let event = |event: Event| EVENTS[event as usize].load(Ordering::Relaxed);
+        let trips = |interlock: Interlock| TRIPS[interlock as usize].load(Ordering::Relaxed);
+
+        Self {
+            commands: event(Event::Command),
+            command_errors: event(Event::CommandError),
+            mqtt_reconnects: event(Event::MqttReconnect),
+            i2c_retries: event(Event::I2cRetry),
+            i2c_errors: event(Event::I2cError),
+            interlock_trips: InterlockTrips {
+                input: trips(Interlock::Input),
+                output: trips(Interlock::Output),
+                reflected: trips(Interlock::Reflected),
+                chained: trips(Interlock::Chained),
+            },
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Booster task timing instrumentation
+//!
+//! # Design
+//! Execution times are measured using the DWT cycle counter. The worst-case execution time of each
+//! instrumented task is tracked over a reporting interval, as is the time during which any task
+//! other than `idle` is executing. The CPU load is the fraction of the interval spent in these
+//! tasks. The remaining time is available to `idle` for network processing.
+//!
+//! Execution times include any time spent in higher-priority tasks that preempted the measured
+//! task.
+use crate::hardware::CPU_FREQ;
+use core::sync::atomic::{AtomicU32, Ordering};
+use cortex_m::peripheral::DWT;
+use serde::Serialize;
+
+/// The number of CPU cycles per microsecond.
+const CYCLES_PER_US: u32 = CPU_FREQ / 1_000_000;
+
+/// The tasks whose execution time is measured.
+#[derive(Copy, Clone, Debug)]
+pub enum TimedTask {
+    ChannelMonitor = 0,
+    Telemetry = 1,
+
+    /// The measurement of a single channel for telemetry.
+    TelemetryAcquisition = 2,
+    Usb = 3,
+
+    /// One iteration of network processing in the `idle` task.
+    Network = 4,
+}
+
+static WORST_CASE_US: [AtomicU32; 5] = [
+    AtomicU32::new(0),
+    AtomicU32::new(0),
+    AtomicU32::new(0),
+    AtomicU32::new(0),
+    AtomicU32::new(0),
+];
+
+/// The number of non-idle tasks currently executing, including preempted tasks.
+static DEPTH: AtomicU32 = AtomicU32::new(0);
+
+/// The cycle count at which the current busy period started.
+static BUSY_START: AtomicU32 = AtomicU32::new(0);
+
+/// The time spent executing non-idle tasks in the current interval.
+static BUSY_US: AtomicU32 = AtomicU32::new(0);
+
+/// The time in milliseconds at which the current interval started.
+static INTERVAL_START_MS: AtomicU32 = AtomicU32::new(0);
+
+/// Measures the execution time of a task until dropped.
+pub struct TaskTimer {
+    task: TimedTask,
+    start: u32,
+}
+
+impl TaskTimer {
+    /// Start measuring the execution time of a task.
+    ///
+    /// # Args
+    /// * `task` - The task being executed.
+    pub fn start(task: TimedTask) -> Self {
+        let start = DWT::cycle_count();
+
+        if !matches!(task, TimedTask::Network) && DEPTH.fetch_add(1, Ordering::Relaxed) == 0 {
+            BUSY_START.store(start, Ordering::Relaxed);
+        }
+
+        Self { task, start }
+    }
+}
+
+impl Drop for TaskTimer {
+    fn drop(&mut self) {
+        let now = DWT::cycle_count();
+        let elapsed_us = now.wrapping_sub(self.start) / CYCLES_PER_US;
+        WORST_CASE_US[self.task as usize].fetch_max(elapsed_us, Ordering::Relaxed);
+
+        if !matches!(self.task, TimedTask::Network) && DEPTH.fetch_sub(1, Ordering::Relaxed) == 1 {
+            let busy_us = now.wrapping_sub(BUSY_START.load(Ordering::Relaxed)) / CYCLES_PER_US;
+            BUSY_US.fetch_add(busy_us, Ordering::Relaxed);
+        }
+    }
+}
+
+/// The worst-case execution times of the instrumented tasks in microseconds.
+#[derive(Serialize, Debug)]
+pub struct WorstCaseTimes {
+    pub channel_monitor: u32,
+    pub telemetry: u32,
+    pub telemetry_acquisition: u32,
+    pub usb: u32,
+    pub network: u32,
+}
+
+/// Task timing measured over a reporting interval.
+#[derive(Serialize, Debug)]
+pub struct TaskTiming {
+    /// The fraction of time spent executing tasks other than `idle`.
+    pub cpu_load: f32,
+
+    /// The fraction of time available to the `idle` task.
+    pub idle: f32,
+
+    /// The worst-case execution times in microseconds.
+    pub wcet_us: WorstCaseTimes,
+}
+
+/// Get the task timing since the previous report and start a new reporting interval.
+///
+/// # Args
+/// * `now_ms` - The current time in milliseconds.
+pub fn report(now_ms: u32) -> TaskTiming {
+    let take = |task: TimedTask| WORST_CASE_US[task as usize].swap(0, Ordering::Relaxed);
+
+    let interval_ms = now_ms.wrapping_sub(INTERVAL_START_MS.swap(now_ms, Ordering::Relaxed));
+    let busy_us = BUSY_US.swap(0, Ordering::Relaxed);
+
+    let cpu_load = if interval_ms > 0 {
+        (busy_us as f32 / (interval_ms as f32 * 1000.0)).min(1.0)
+    } else {
+        0.0
+    };
+
+    TaskTiming {
+        cpu_load,
+        idle: 1.0 - cpu_load,
+        wcet_us: WorstCaseTimes {
+            channel_monitor: take(TimedTask::ChannelMonitor),
+            telemetry: take(TimedTask::Telemetry),
+            telemetry_acquisition: take(TimedTask::TelemetryAcquisition),
+            usb: take(TimedTask::Usb),
+            network: take(TimedTask::Network),
+        },
+    }
+}
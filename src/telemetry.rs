@@ -0,0 +1,266 @@
+//! Booster channel telemetry snapshot
+//!
+//! # Design
+//! Channel telemetry is acquired by a periodic task that measures one channel per invocation and
+//! stores the result in a shared snapshot. The telemetry task publishes from the snapshot, so that
+//! the latency of I2C measurements is decoupled from the throughput of the MQTT interface.
+//!
+//! The format of channel telemetry is described by a [TelemetrySchema] that is published alongside
+//! the telemetry, so that consumers can check compatibility as fields are added.
+use crate::{
+    hardware::{
+        rf_channel::{ChannelStatus, Warnings},
+        Channel,
+    },
+    settings::global_settings::OperatingHours,
+};
+use serde::Serialize;
+
+/// The version of the channel telemetry format. This is incremented whenever fields are removed or
+/// their meaning is changed. Adding fields does not change the version.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The unit of power measurements in published telemetry.
+#[derive(Serialize, serde::Deserialize, Copy, Clone, Debug, PartialEq)]
+pub enum PowerUnit {
+    /// Powers are reported in dBm.
+    #[serde(rename = "dBm")]
+    Dbm,
+
+    /// Powers are reported in Watts.
+    Watts,
+}
+
+impl PowerUnit {
+    /// Convert a power from dBm into the unit.
+    ///
+    /// # Args
+    /// * `dbm` - The power in dBm.
+    pub fn convert(&self, dbm: f32) -> f32 {
+        match self {
+            PowerUnit::Dbm => dbm,
+            PowerUnit::Watts => libm::powf(10.0, (dbm - 30.0) / 10.0),
+        }
+    }
+
+    /// Get the symbol of the unit.
+    fn symbol(&self) -> &'static str {
+        match self {
+            PowerUnit::Dbm => "dBm",
+            PowerUnit::Watts => "W",
+        }
+    }
+}
+
+/// The number of decimal places at or above which measurements are published at full precision.
+const FULL_PRECISION: u8 = 7;
+
+/// The number of decimal places of measurements in published telemetry.
+///
+/// # Note
+/// Measurements are rounded after conversion into the configured [PowerUnit]. Measurements are
+/// published at full precision if the number of decimal places is 7 or more.
+#[derive(Serialize, serde::Deserialize, miniconf::Tree, Copy, Clone, Debug, PartialEq)]
+pub struct Precision {
+    /// The decimal places of input, output, and reflected powers.
+    pub power: u8,
+
+    /// The decimal places of supply currents.
+    pub current: u8,
+
+    /// The decimal places of supply voltages.
+    pub voltage: u8,
+
+    /// The decimal places of temperatures.
+    pub temperature: u8,
+}
+
+impl Default for Precision {
+    fn default() -> Self {
+        Self {
+            power: 2,
+            current: 3,
+            voltage: 2,
+            temperature: 1,
+        }
+    }
+}
+
+/// Round a value to a number of decimal places.
+///
+/// # Args
+/// * `value` - The value to round.
+/// * `decimals` - The number of decimal places to round to.
+fn round(value: f32, decimals: u8) -> f32 {
+    if decimals >= FULL_PRECISION {
+        return value;
+    }
+
+    let scale = libm::powf(10.0, decimals as f32);
+    libm::roundf(value * scale) / scale
+}
+
+/// The units of the measurements reported in channel telemetry.
+#[derive(Serialize)]
+pub struct Units {
+    pub temperature: &'static str,
+    pub p28v_current: &'static str,
+    pub p5v_current: &'static str,
+    pub p5v_voltage: &'static str,
+    pub input_power: &'static str,
+    pub output_power: &'static str,
+    pub reflected_power: &'static str,
+    pub interlock_derating: &'static str,
+}
+
+/// A description of the channel telemetry format.
+#[derive(Serialize)]
+pub struct TelemetrySchema {
+    pub schema_version: u32,
+    pub units: Units,
+}
+
+impl TelemetrySchema {
+    /// Construct the schema of channel telemetry.
+    ///
+    /// # Args
+    /// * `power_unit` - The unit that power measurements are published in.
+    pub fn new(power_unit: PowerUnit) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            units: Units {
+                temperature: "C",
+                p28v_current: "A",
+                p5v_current: "A",
+                p5v_voltage: "V",
+                input_power: power_unit.symbol(),
+                output_power: power_unit.symbol(),
+                reflected_power: power_unit.symbol(),
+                interlock_derating: "dB",
+            },
+        }
+    }
+}
+
+/// The telemetry of a single channel.
+#[derive(Serialize, Clone)]
+#[serde(untagged)]
+pub enum ChannelTelemetry {
+    /// The measured status of the channel.
+    Status(ChannelStatus),
+
+    /// The channel has been quarantined due to repeated measurement errors and is no longer
+    /// measured.
+    Quarantined {
+        state: &'static str,
+        quarantined: bool,
+    },
+}
+
+impl ChannelTelemetry {
+    /// Convert the power measurements of the telemetry from dBm into the specified unit.
+    ///
+    /// # Args
+    /// * `unit` - The unit to convert power measurements into.
+    pub fn convert_power(&mut self, unit: PowerUnit) {
+        if let Self::Status(status) = self {
+            status.input_power = unit.convert(status.input_power);
+            status.output_power = unit.convert(status.output_power);
+            status.reflected_power = unit.convert(status.reflected_power);
+        }
+    }
+
+    /// Round the measurements of the telemetry to reduce the size of published messages.
+    ///
+    /// # Args
+    /// * `precision` - The number of decimal places of each measurement.
+    pub fn round(&mut self, precision: &Precision) {
+        if let Self::Status(status) = self {
+            status.input_power = round(status.input_power, precision.power);
+            status.output_power = round(status.output_power, precision.power);
+            status.reflected_power = round(status.reflected_power, precision.power);
+            status.p28v_current = round(status.p28v_current, precision.current);
+            status.p5v_current = round(status.p5v_current, precision.current);
+            status.p5v_voltage = round(status.p5v_voltage, precision.voltage);
+            status.temperature = round(status.temperature, precision.temperature);
+        }
+    }
+}
+
+/// The operating hours and maintenance state of the device.
+#[derive(Serialize, Copy, Clone, Debug)]
+pub struct Maintenance {
+    /// The number of hours that the device has been powered on.
+    pub device_hours: u32,
+
+    /// The number of hours that the chassis fans have been running.
+    pub fan_hours: u32,
+
+    /// The number of fan operating hours since the fans were last serviced.
+    pub fan_hours_since_service: u32,
+
+    /// Indicates that the configured fan service interval has been exceeded.
+    pub fan_service_due: bool,
+}
+
+impl Maintenance {
+    /// Determine the maintenance state from the operating hours.
+    ///
+    /// # Args
+    /// * `hours` - The operating hours of the device.
+    /// * `fan_service_hours` - The fan service interval in hours. Disabled if zero.
+    pub fn new(hours: &OperatingHours, fan_service_hours: u32) -> Self {
+        Self {
+            device_hours: hours.device,
+            fan_hours: hours.fan,
+            fan_hours_since_service: hours.fan_since_service(),
+            fan_service_due: fan_service_hours != 0
+                && hours.fan_since_service() >= fan_service_hours,
+        }
+    }
+}
+
+/// The most recently acquired telemetry of all channels.
+#[derive(Default)]
+pub struct TelemetrySnapshot {
+    channels: [Option<ChannelTelemetry>; 8],
+
+    // Warnings that were raised since the snapshot was last published.
+    warnings: [Option<Warnings>; 8],
+}
+
+impl TelemetrySnapshot {
+    /// Update the telemetry of a channel.
+    ///
+    /// # Args
+    /// * `channel` - The channel that telemetry was acquired for.
+    /// * `status` - The telemetry of the channel. None if the channel is not installed or could
+    ///   not be measured.
+    /// * `raised` - The warnings that were newly raised by the channel, if any.
+    pub fn update(
+        &mut self,
+        channel: Channel,
+        status: Option<ChannelTelemetry>,
+        raised: Option<Warnings>,
+    ) {
+        self.channels[channel as usize] = status;
+
+        if let Some(raised) = raised {
+            let pending = self.warnings[channel as usize].get_or_insert_with(Warnings::default);
+            pending.temperature |= raised.temperature;
+            pending.p28v_current |= raised.p28v_current;
+            pending.reflected_power |= raised.reflected_power;
+            pending.no_input |= raised.no_input;
+        }
+    }
+
+    /// Get the telemetry of all channels, indexed by channel.
+    pub fn channels(&self) -> &[Option<ChannelTelemetry>; 8] {
+        &self.channels
+    }
+
+    /// Take the warnings raised since the previous call, indexed by channel.
+    pub fn take_warnings(&mut self) -> [Option<Warnings>; 8] {
+        core::mem::take(&mut self.warnings)
+    }
+}
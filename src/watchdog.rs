@@ -1,6 +1,9 @@
 //! Booster NGFW watchdog manager
 
-use crate::hal;
+use crate::{
+    hal,
+    hardware::{platform::ResetTarget, safe_mode},
+};
 use hal::prelude::*;
 
 /// Represents various clients that can check in with the watchdog.
@@ -13,10 +16,15 @@ pub enum WatchdogClient {
 
 /// A manager for the device indepedent watchdog.
 ///
-/// The manager waits for a number of clients to check in before feeding the watchdog.
+/// The manager waits for a number of clients to check in before feeding the watchdog. If a network
+/// timeout is configured and the MQTT broker has not been connected within the timeout, a
+/// controlled reset is requested, such that a wedged network stack eventually recovers.
 pub struct WatchdogManager {
     watchdog: hal::watchdog::IndependentWatchdog,
     check_ins: [bool; 4],
+    network_timeout_ms: u64,
+    last_connected: u64,
+    network_expired: bool,
 }
 
 impl WatchdogManager {
@@ -31,6 +39,49 @@ impl WatchdogManager {
         Self {
             watchdog,
             check_ins: [false; 4],
+            network_timeout_ms: 0,
+            last_connected: 0,
+            network_expired: false,
+        }
+    }
+
+    /// Configure the network liveness criterion of the watchdog.
+    ///
+    /// # Args
+    /// * `minutes` - The number of minutes the MQTT broker may remain disconnected before the
+    ///   device is reset. The criterion is disabled if zero.
+    pub fn set_network_timeout(&mut self, minutes: u32) {
+        let timeout_ms = minutes as u64 * 60_000;
+
+        // Restart the timeout when enabling or changing it, so that enabling the criterion while
+        // disconnected does not immediately reset the device.
+        if timeout_ms != self.network_timeout_ms {
+            self.last_connected = crate::app::monotonics::now().ticks();
+        }
+
+        self.network_timeout_ms = timeout_ms;
+    }
+
+    /// Check the network liveness criterion and reset the device once it has expired.
+    ///
+    /// # Note
+    /// The device is reset via software rather than by starving the watchdog, so that the reset is
+    /// not counted as an abnormal reset and does not keep channels disabled afterwards.
+    fn check_network(&mut self) {
+        let now = crate::app::monotonics::now().ticks();
+        if crate::net::broker_connected() {
+            self.last_connected = now;
+        }
+
+        if self.network_timeout_ms == 0 || self.network_expired {
+            return;
+        }
+
+        if now.saturating_sub(self.last_connected) >= self.network_timeout_ms {
+            log::error!("MQTT broker unreachable, resetting device");
+            self.network_expired = true;
+            safe_mode::record_network_reset();
+            crate::app::reset::spawn(ResetTarget::Firmware).ok();
         }
     }
 
@@ -41,6 +92,8 @@ impl WatchdogManager {
     pub fn check_in(&mut self, client: WatchdogClient) {
         self.check_ins[client as usize] = true;
 
+        self.check_network();
+
         // If all clients have checked in, service the watchdog.
         if self.check_ins.iter().all(|&x| x) {
             self.watchdog.feed();